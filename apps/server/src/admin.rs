@@ -0,0 +1,55 @@
+use std::{sync::Arc, time::SystemTime};
+
+use axum::{
+    Json,
+    extract::State,
+    response::{IntoResponse, Response},
+};
+use http::{HeaderMap, StatusCode};
+use serde::Serialize;
+
+use crate::{AppState, GameState, bearer_token};
+
+/// What operators see about a room. Never includes tokens.
+#[derive(Serialize, Debug)]
+pub struct RoomSummary {
+    pub code: String,
+    pub player_count: usize,
+    pub state: GameState,
+    /// Seconds since the room last saw activity
+    pub idle_secs: u64,
+}
+
+/// Whether the request carries `Authorization: Bearer <ADMIN_TOKEN>`.
+/// Admin routes are closed entirely when no token is configured.
+fn is_admin(state: &AppState, headers: &HeaderMap) -> bool {
+    let Some(expected) = &state.admin_token else {
+        return false;
+    };
+    bearer_token(headers).is_some_and(|token| token == expected)
+}
+
+#[tracing::instrument(skip(state, headers))]
+pub async fn list_rooms(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if !is_admin(&state, &headers) {
+        return (StatusCode::UNAUTHORIZED, "Invalid admin token").into_response();
+    }
+
+    let room_map = state.room_map.lock().await;
+    let now = SystemTime::now();
+    let mut rooms: Vec<RoomSummary> = room_map
+        .values()
+        .map(|room| RoomSummary {
+            code: room.code.clone(),
+            player_count: room.players.len(),
+            state: room.state.clone(),
+            idle_secs: now
+                .duration_since(room.last_activity)
+                .unwrap_or_default()
+                .as_secs(),
+        })
+        .collect();
+    rooms.sort_by(|a, b| a.code.cmp(&b.code));
+
+    Json(rooms).into_response()
+}
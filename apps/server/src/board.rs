@@ -0,0 +1,484 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+};
+use http::{HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState, GameState, RoomParams, bearer_token,
+    game::{Category, Question, QuestionMode},
+};
+
+const CSV_HEADER: [&str; 4] = ["category", "question", "answer", "value"];
+
+/// Why a CSV board couldn't be parsed. `row` is 1-based and counts the header.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct CsvError {
+    pub row: usize,
+    pub message: String,
+}
+
+impl CsvError {
+    fn new(row: usize, message: impl Into<String>) -> Self {
+        Self {
+            row,
+            message: message.into(),
+        }
+    }
+}
+
+/// Split CSV text into rows of fields. Quoted fields may contain commas,
+/// newlines and `""` escapes. Each row carries its 1-based starting line.
+fn split_csv_rows(text: &str) -> Result<Vec<(usize, Vec<String>)>, CsvError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut row_start = 1;
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            '"' => {
+                return Err(CsvError::new(line, "Quotes must wrap the whole field"));
+            }
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push((row_start, std::mem::take(&mut row)));
+                line += 1;
+                row_start = line;
+            }
+            '\n' => {
+                field.push(c);
+                line += 1;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(CsvError::new(row_start, "Unterminated quoted field"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_start, row));
+    }
+
+    // Spreadsheets often leave trailing blank lines
+    rows.retain(|(_, fields)| fields.iter().any(|f| !f.trim().is_empty()));
+    Ok(rows)
+}
+
+/// Parse a `category,question,answer,value` CSV into a board, grouping rows
+/// by category in the order each category first appears. A header row is
+/// optional.
+pub fn parse_board_csv(text: &str) -> Result<Vec<Category>, CsvError> {
+    let mut rows = split_csv_rows(text)?;
+    let has_header = rows.first().is_some_and(|(_, fields)| {
+        fields.len() == CSV_HEADER.len()
+            && fields
+                .iter()
+                .zip(CSV_HEADER)
+                .all(|(field, name)| field.trim().eq_ignore_ascii_case(name))
+    });
+    if has_header {
+        rows.remove(0);
+    }
+
+    let mut categories: Vec<Category> = Vec::new();
+    for (row, fields) in rows {
+        let [category, question, answer, value] = fields.as_slice() else {
+            return Err(CsvError::new(
+                row,
+                format!("Expected 4 columns, found {}", fields.len()),
+            ));
+        };
+        let value = match value.trim().parse::<u32>() {
+            Ok(value) if value > 0 => value,
+            _ => {
+                return Err(CsvError::new(
+                    row,
+                    format!("Value {:?} is not a positive whole number", value),
+                ));
+            }
+        };
+        let title = category.trim();
+        if title.is_empty() {
+            return Err(CsvError::new(row, "Category is empty"));
+        }
+
+        let question = Question {
+            question: question.trim().to_string(),
+            answer: answer.trim().to_string(),
+            value,
+            answered: false,
+            mode: QuestionMode::Buzzer,
+            media_url: None,
+            media_type: None,
+        };
+        match categories.iter_mut().find(|c| c.title == title) {
+            Some(existing) => existing.questions.push(question),
+            None => categories.push(Category {
+                title: title.to_string(),
+                questions: vec![question],
+            }),
+        }
+    }
+    Ok(categories)
+}
+
+/// A board question as exported for re-import; play state is left out.
+#[derive(Serialize, Debug)]
+pub struct ExportedQuestion<'a> {
+    pub question: &'a str,
+    pub answer: &'a str,
+    pub value: u32,
+    pub mode: QuestionMode,
+    #[serde(rename = "mediaUrl", skip_serializing_if = "Option::is_none")]
+    pub media_url: Option<&'a str>,
+    #[serde(rename = "mediaType", skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ExportedCategory<'a> {
+    pub title: &'a str,
+    pub questions: Vec<ExportedQuestion<'a>>,
+}
+
+/// Strip play state from a board, keeping questions in board order.
+pub fn export_board(categories: &[Category]) -> Vec<ExportedCategory<'_>> {
+    categories
+        .iter()
+        .map(|category| ExportedCategory {
+            title: &category.title,
+            questions: category
+                .questions
+                .iter()
+                .map(|q| ExportedQuestion {
+                    question: &q.question,
+                    answer: &q.answer,
+                    value: q.value,
+                    mode: q.mode,
+                    media_url: q.media_url.as_deref(),
+                    media_type: q.media_type.as_deref(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Tidy up a board as it arrives, before it is validated or stored.
+pub fn normalize_board(categories: &mut [Category]) {
+    for question in categories.iter_mut().flat_map(|cat| &mut cat.questions) {
+        if let Some(url) = &mut question.media_url {
+            *url = url.trim().to_string();
+        }
+    }
+}
+
+/// Check an imported board is playable, returning why it isn't.
+pub fn validate_board(categories: &[Category]) -> Result<(), String> {
+    for (idx, category) in categories.iter().enumerate() {
+        if category.questions.is_empty() {
+            return Err(format!(
+                "Category {} ({:?}) has no questions",
+                idx, category.title
+            ));
+        }
+        if let Some(q_idx) = category.questions.iter().position(|q| q.value == 0) {
+            return Err(format!(
+                "Question {} in category {} ({:?}) must have a positive value",
+                q_idx, idx, category.title
+            ));
+        }
+        if let Some(q_idx) = category
+            .questions
+            .iter()
+            .position(|q| q.media_url.as_deref().is_some_and(|url| !is_web_url(url)))
+        {
+            return Err(format!(
+                "Question {} in category {} ({:?}) has a media URL that isn't http(s)",
+                q_idx, idx, category.title
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Media has to come from the web; `javascript:`, `file:` and `data:` URLs
+/// are turned away.
+fn is_web_url(url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    ["http://", "https://"]
+        .iter()
+        .any(|scheme| url.len() > scheme.len() && url.starts_with(scheme))
+}
+
+/// A question or answer longer than the server allows. Every `GameState`
+/// carries the whole board, so one huge question slows down every update.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct OversizedText {
+    pub category: usize,
+    pub question: usize,
+    /// `"question"` or `"answer"`
+    pub field: &'static str,
+    /// Length in characters
+    pub length: usize,
+}
+
+/// Find every question or answer longer than `max_chars` characters.
+pub fn check_text_lengths(categories: &[Category], max_chars: usize) -> Vec<OversizedText> {
+    categories
+        .iter()
+        .enumerate()
+        .flat_map(move |(c_idx, category)| {
+            category
+                .questions
+                .iter()
+                .enumerate()
+                .flat_map(move |(q_idx, q)| {
+                    [("question", &q.question), ("answer", &q.answer)]
+                        .into_iter()
+                        .map(|(field, text)| (field, text.chars().count()))
+                        .filter(move |&(_, length)| length > max_chars)
+                        .map(move |(field, length)| OversizedText {
+                            category: c_idx,
+                            question: q_idx,
+                            field,
+                            length,
+                        })
+                })
+        })
+        .collect()
+}
+
+/// A question whose value doesn't climb above the one before it, usually a
+/// typo in the board file.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct LadderIssue {
+    pub category: usize,
+    pub question: usize,
+    pub message: String,
+}
+
+/// Check every category's values go up strictly from question to question.
+pub fn check_value_ladder(categories: &[Category]) -> Vec<LadderIssue> {
+    categories
+        .iter()
+        .enumerate()
+        .flat_map(|(c_idx, category)| {
+            category
+                .questions
+                .windows(2)
+                .enumerate()
+                .filter(|(_, pair)| pair[1].value <= pair[0].value)
+                .map(move |(q_idx, pair)| LadderIssue {
+                    category: c_idx,
+                    question: q_idx + 1,
+                    message: format!(
+                        "Question {} in category {} ({:?}) is worth {}, not more than the {} before it",
+                        q_idx + 1,
+                        c_idx,
+                        category.title,
+                        pair[1].value,
+                        pair[0].value
+                    ),
+                })
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct ImportParams {
+    /// Reject boards whose values don't increase instead of only warning
+    #[serde(default)]
+    strict: bool,
+}
+
+/// Download a room's board in the format `import_board` accepts, so a game
+/// can be saved and re-created later. Authenticated like the imports.
+#[tracing::instrument(skip(state, headers))]
+pub(crate) async fn download_board(
+    State(state): State<Arc<AppState>>,
+    Path(RoomParams { code }): Path<RoomParams>,
+    headers: HeaderMap,
+) -> Response {
+    let room_map = state.room_map.lock().await;
+    let Some(room) = room_map.get(&code) else {
+        return (StatusCode::NOT_FOUND, "Room does not exist").into_response();
+    };
+    if bearer_token(&headers) != Some(room.host_token.as_str()) {
+        return (StatusCode::UNAUTHORIZED, "Invalid host token").into_response();
+    }
+
+    Json(export_board(&room.categories)).into_response()
+}
+
+/// Replace a room's board before the game starts, authenticated with
+/// `Authorization: Bearer <host_token>`.
+#[tracing::instrument(skip(state, headers, categories))]
+pub(crate) async fn import_board(
+    State(state): State<Arc<AppState>>,
+    Path(RoomParams { code }): Path<RoomParams>,
+    Query(params): Query<ImportParams>,
+    headers: HeaderMap,
+    Json(categories): Json<Vec<Category>>,
+) -> Response {
+    replace_board(&state, &code, &headers, &params, || {
+        Ok::<_, String>(categories)
+    })
+    .await
+}
+
+/// Replace a room's board from a `category,question,answer,value` CSV.
+/// Malformed input gets a 400 with the offending row.
+#[tracing::instrument(skip(state, headers, body))]
+pub(crate) async fn import_board_csv(
+    State(state): State<Arc<AppState>>,
+    Path(RoomParams { code }): Path<RoomParams>,
+    Query(params): Query<ImportParams>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    replace_board(&state, &code, &headers, &params, || parse_board_csv(&body)).await
+}
+
+/// Shared by every board import format. `parse` only runs once the host is
+/// authenticated and the game hasn't started; its errors are returned as 400s,
+/// as are questions or answers over `max_board_text_chars`.
+/// Values that don't increase are sent back as warnings with a 200, or
+/// rejected with a 400 when `strict`.
+async fn replace_board<E: Serialize>(
+    state: &AppState,
+    code: &str,
+    headers: &HeaderMap,
+    params: &ImportParams,
+    parse: impl FnOnce() -> Result<Vec<Category>, E>,
+) -> Response {
+    let mut room_map = state.room_map.lock().await;
+    let Some(room) = room_map.get_mut(code) else {
+        return (StatusCode::NOT_FOUND, "Room does not exist").into_response();
+    };
+    if bearer_token(headers) != Some(room.host_token.as_str()) {
+        return (StatusCode::UNAUTHORIZED, "Invalid host token").into_response();
+    }
+    if room.state != GameState::Start {
+        return (StatusCode::CONFLICT, "The game has already started").into_response();
+    }
+    let mut categories = match parse() {
+        Ok(categories) => categories,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(e)).into_response(),
+    };
+    normalize_board(&mut categories);
+    if let Err(reason) = validate_board(&categories) {
+        return (StatusCode::BAD_REQUEST, reason).into_response();
+    }
+    let oversized = check_text_lengths(&categories, state.max_board_text_chars);
+    if !oversized.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(oversized)).into_response();
+    }
+    let warnings = check_value_ladder(&categories);
+    if params.strict && !warnings.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(warnings)).into_response();
+    }
+
+    // A fresh board is unplayed no matter what the file says
+    for question in categories.iter_mut().flat_map(|cat| &mut cat.questions) {
+        question.answered = false;
+    }
+    if let Some(first) = room.boards.first_mut() {
+        *first = categories.clone();
+    }
+    tracing::info!(count = categories.len(), "Host imported board");
+    room.categories = categories;
+    room.touch();
+
+    if warnings.is_empty() {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        tracing::warn!(
+            count = warnings.len(),
+            "Imported board has values out of order"
+        );
+        (StatusCode::OK, Json(warnings)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_groups_rows_by_category() {
+        let csv = "category,question,answer,value\n\
+                   Science,What is H2O?,Water,200\n\
+                   History,Who was first?,Washington,200\n\
+                   Science,What is NaCl?,Salt,400\n";
+        let board = parse_board_csv(csv).expect("Valid CSV");
+
+        assert_eq!(board.len(), 2);
+        assert_eq!(board[0].title, "Science");
+        assert_eq!(board[0].questions.len(), 2);
+        assert_eq!(board[0].questions[1].answer, "Salt");
+        assert_eq!(board[1].title, "History");
+    }
+
+    #[test]
+    fn test_csv_handles_quoted_fields() {
+        let csv = "Trivia,\"Name a fruit, any fruit\",\"The \"\"apple\"\"\",100\r\n\
+                   Trivia,\"Two\nlines\",Yes,200";
+        let board = parse_board_csv(csv).expect("Valid CSV");
+
+        let questions = &board[0].questions;
+        assert_eq!(questions[0].question, "Name a fruit, any fruit");
+        assert_eq!(questions[0].answer, "The \"apple\"");
+        assert_eq!(questions[1].question, "Two\nlines");
+    }
+
+    #[test]
+    fn test_csv_errors_name_the_row() {
+        let missing_column = "category,question,answer,value\nScience,Q,A,100\nScience,Q,A\n";
+        assert_eq!(
+            parse_board_csv(missing_column).err().map(|e| e.row),
+            Some(3)
+        );
+
+        let bad_value = "Science,Q,A,lots";
+        assert_eq!(parse_board_csv(bad_value).err().map(|e| e.row), Some(1));
+
+        let unterminated = "Science,Q,A,100\nScience,\"Q,A,100";
+        assert_eq!(parse_board_csv(unterminated).err().map(|e| e.row), Some(2));
+    }
+
+    #[test]
+    fn test_value_ladder_names_out_of_order_questions() {
+        let board = parse_board_csv(
+            "Science,Q,A,100\nScience,Q,A,200\nScience,Q,A,200\n\
+             History,Q,A,300\nHistory,Q,A,100\nHistory,Q,A,500\n",
+        )
+        .expect("Valid CSV");
+
+        let issues: Vec<_> = check_value_ladder(&board)
+            .iter()
+            .map(|i| (i.category, i.question))
+            .collect();
+        assert_eq!(issues, [(0, 2), (1, 1)]);
+        assert!(check_value_ladder(&board[..0]).is_empty());
+    }
+}
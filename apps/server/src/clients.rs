@@ -0,0 +1,91 @@
+use std::{collections::HashMap, time::Instant};
+
+use tokio::sync::Mutex;
+
+/// How many returning clients the server remembers at once
+pub const MAX_KNOWN_CLIENTS: usize = 10_000;
+
+/// A random UUID a client generates once and keeps (e.g. in local storage)
+/// so the server can recognise it across rooms. It carries no rights: a room
+/// still only trusts its own player tokens.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClientId(String);
+
+impl ClientId {
+    /// Accepts a hyphenated UUID in either case. Anything else is `None`.
+    pub fn parse(raw: &str) -> Option<Self> {
+        const GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+        let groups: Vec<&str> = raw.split('-').collect();
+        let valid = groups.len() == GROUPS.len()
+            && groups.iter().zip(GROUPS).all(|(group, len)| {
+                group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit())
+            });
+        valid.then(|| Self(raw.to_ascii_lowercase()))
+    }
+}
+
+/// The name each returning client last played under, so their next join can
+/// be prefilled. Only the name is kept: no room, token or address, and
+/// nothing outlives the process.
+#[derive(Debug, Default)]
+pub struct KnownClients {
+    names: Mutex<HashMap<ClientId, (String, Instant)>>,
+}
+
+impl KnownClients {
+    pub async fn last_name(&self, id: &ClientId) -> Option<String> {
+        self.names
+            .lock()
+            .await
+            .get(id)
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Remember `name` for `id`, forgetting the least recently seen client
+    /// if the map is full.
+    pub async fn remember(&self, id: ClientId, name: String) {
+        let mut names = self.names.lock().await;
+        if names.len() >= MAX_KNOWN_CLIENTS
+            && !names.contains_key(&id)
+            && let Some(oldest) = names
+                .iter()
+                .min_by_key(|(_, (_, seen))| *seen)
+                .map(|(id, _)| id.clone())
+        {
+            names.remove(&oldest);
+        }
+        names.insert(id, (name, Instant::now()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_client_id() {
+        let id = ClientId::parse("3F2504E0-4F89-11D3-9A0C-0305E82C3301").expect("Valid UUID");
+        assert_eq!(
+            Some(id),
+            ClientId::parse("3f2504e0-4f89-11d3-9a0c-0305e82c3301"),
+            "Case doesn't matter"
+        );
+        assert_eq!(ClientId::parse("3f2504e04f8911d39a0c0305e82c3301"), None);
+        assert_eq!(
+            ClientId::parse("3f2504e0-4f89-11d3-9a0c-0305e82c330z"),
+            None
+        );
+        assert_eq!(ClientId::parse(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_remembers_latest_name() {
+        let known = KnownClients::default();
+        let id = ClientId::parse("3f2504e0-4f89-11d3-9a0c-0305e82c3301").expect("Valid UUID");
+        assert_eq!(known.last_name(&id).await, None);
+
+        known.remember(id.clone(), "AJ".to_string()).await;
+        known.remember(id.clone(), "AJ the Great".to_string()).await;
+        assert_eq!(known.last_name(&id).await.as_deref(), Some("AJ the Great"));
+    }
+}
@@ -1,21 +1,110 @@
-use std::{fmt, time::SystemTime};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    net::IpAddr,
+    time::{Duration, Instant, SystemTime},
+};
 
+use rand::{
+    Rng, SeedableRng,
+    rngs::StdRng,
+    seq::{IndexedRandom, SliceRandom},
+};
 use serde::{Deserialize, Serialize};
+use tokio::{sync::broadcast, time::timeout};
 
 use crate::{
-    PlayerEntry,
+    ConnectionStatus, PlayerEntry, UnixMs,
+    grading::answers_match,
+    handshake::HandshakeFailures,
     host::HostEntry,
     player::{Player, PlayerId},
+    replay::{EventLog, SequencedEvent},
+    scoring::ScoringStrategy,
+    settings::{AnswerTimeoutAction, RoomSettings},
+    stats::{BuzzStat, QuestionStats, Ruling},
+    timer::{ScheduledTimer, TimerKind},
+    transcript::{Audience, Transcript},
     ws_msg::WsMsg,
 };
 
+pub const MAX_NAME_LEN: usize = 24;
+pub const MAX_CHAT_LEN: usize = 280;
+pub const MAX_POLL_ANSWER_LEN: usize = 280;
+
+/// How long players who buzzed before buzzing opened have to wait once it does
+pub const EARLY_BUZZ_LOCKOUT: Duration = Duration::from_millis(250);
+
+/// How many host rulings can be undone
+pub const UNDO_DEPTH: usize = 10;
+
+/// Timers that belong to the question in play, as opposed to the room
+const QUESTION_TIMERS: [TimerKind; 6] = [
+    TimerKind::AutoReady,
+    TimerKind::OpenBuzzing,
+    TimerKind::EarlyBuzzLockout,
+    TimerKind::AnswerTimeout,
+    TimerKind::StealWindow,
+    TimerKind::BuzzWindow,
+];
+
+/// Each player may send this many chat messages per `CHAT_RATE_WINDOW`
+const CHAT_RATE_LIMIT: usize = 3;
+const CHAT_RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Latencies above this percentile are capped when timing witnesses
+const WITNESS_LATENCY_PERCENTILE: f64 = 0.95;
+
+/// Events buffered per read-only observer before it starts missing some
+const OBSERVER_BUFFER: usize = 64;
+
+/// Longest a host can ask for their room to be kept while idle
+pub const MAX_ROOM_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How long a send waits on a client whose channel is full before giving up
+/// on it
+pub const SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Question {
     pub question: String,
+    /// Empty (and left out of the JSON) in the redacted copies players see
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub answer: String,
     pub value: u32,
     #[serde(default)]
     pub answered: bool,
+    #[serde(default)]
+    pub mode: QuestionMode,
+    /// An image, audio or video clip to show with the question; only
+    /// http(s) URLs are accepted on import
+    #[serde(rename = "mediaUrl", default, skip_serializing_if = "Option::is_none")]
+    pub media_url: Option<String>,
+    /// MIME type of `media_url`, e.g. `image/png`, so clients know how to
+    /// render it
+    #[serde(rename = "mediaType", default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+}
+
+/// How players answer a question.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QuestionMode {
+    /// First to buzz answers
+    #[default]
+    Buzzer,
+    /// Everyone submits an answer and the host grades each one
+    Poll,
+}
+
+impl Question {
+    /// The question as players may see it, without the answer.
+    pub fn redacted(&self) -> Self {
+        Self {
+            answer: String::new(),
+            ..self.clone()
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -24,17 +113,261 @@ pub struct Category {
     pub questions: Vec<Question>,
 }
 
+impl Category {
+    pub fn redacted(&self) -> Self {
+        Self {
+            title: self.title.clone(),
+            questions: self.questions.iter().map(Question::redacted).collect(),
+        }
+    }
+}
+
+/// Strip answers from a message bound for anyone but the host. Handlers
+/// already send players the redacted `GameState`; this catches any that
+/// slip through.
+pub fn redact_for_players(mut msg: WsMsg) -> WsMsg {
+    if let WsMsg::GameState { categories, .. } = &mut msg {
+        for category in categories.iter_mut() {
+            *category = category.redacted();
+        }
+    }
+    msg
+}
+
+/// How long to hold back a witness for each player so they all see it at
+/// about the same moment. Each delay is `base - latency`, clamped to
+/// `[0, base]`, with latencies capped at the 95th percentile so one terrible
+/// connection can't warp everyone's timing.
+pub fn witness_delays(base: Duration, latencies_ms: &[u64]) -> Vec<Duration> {
+    let mut sorted = latencies_ms.to_vec();
+    sorted.sort_unstable();
+    let rank = (sorted.len() as f64 * WITNESS_LATENCY_PERCENTILE).ceil() as usize;
+    let cap = sorted
+        .get(rank.saturating_sub(1))
+        .copied()
+        .unwrap_or_default();
+
+    latencies_ms
+        .iter()
+        .map(|&latency| base.saturating_sub(Duration::from_millis(latency.min(cap))))
+        .collect()
+}
+
+/// What a question worth `value` scores under `multiplier`. Saturates
+/// rather than wrapping negative for absurd multipliers.
+fn scaled_value(value: u32, multiplier: u32) -> i32 {
+    i32::try_from(value.saturating_mul(multiplier)).unwrap_or(i32::MAX)
+}
+
+/// Number `msg` in `player`'s own sequence, keeping it for `ResumeFrom` if
+/// it's worth replaying.
+fn stamp_for(player: &mut PlayerEntry, msg: WsMsg) -> WsMsg {
+    let event = player.events.stamp(msg);
+    if EventLog::is_replayable(&event.event) {
+        player.events.record(event.clone());
+    }
+    event.into_envelope()
+}
+
+/// Deliver `msg` to a connected player. One whose channel is closed, or stays
+/// full for [`SEND_TIMEOUT`], is marked disconnected and skipped from then on,
+/// so a client that stopped reading can't hold up the room. Their channel is
+/// closed too, which drops the socket so the client reconnects and catches up.
+async fn send_to_player(player: &mut PlayerEntry, msg: WsMsg) {
+    if matches!(player.status, ConnectionStatus::Disconnected) {
+        return;
+    }
+    let error = match timeout(SEND_TIMEOUT, player.sender.send(msg)).await {
+        Ok(Ok(())) => return,
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => "Player isn't reading messages".to_string(),
+    };
+    tracing::warn!(
+        player_id = player.player.pid,
+        error,
+        "Dropped message to player, marking disconnected"
+    );
+    player.status = ConnectionStatus::Disconnected;
+    player.sender.close();
+}
+
+/// Messages that move the game along, as opposed to chat, heartbeats and
+/// other housekeeping.
+fn is_game_action(msg: &WsMsg) -> bool {
+    matches!(
+        msg,
+        WsMsg::StartGame {}
+            | WsMsg::HostChoice { .. }
+            | WsMsg::RandomQuestion { .. }
+            | WsMsg::HostReady {}
+            | WsMsg::HostChecked { .. }
+            | WsMsg::AwardQuestion { .. }
+            | WsMsg::HostSkip { .. }
+            | WsMsg::VoidQuestion {}
+            | WsMsg::HostContinue { .. }
+            | WsMsg::Undo {}
+            | WsMsg::NextBoard {}
+            | WsMsg::EndGame {}
+            | WsMsg::Buzz { .. }
+            | WsMsg::SubmitAnswer { .. }
+            | WsMsg::PollChecked { .. }
+    )
+}
+
+/// Whether a big-screen display gets `msg`: it shows the board and who
+/// buzzed, nothing else.
+pub fn shown_on_display(msg: &WsMsg) -> bool {
+    matches!(msg, WsMsg::GameState { .. } | WsMsg::Buzzed { .. })
+}
+
+/// Speaking rate assumed when estimating how long a question takes to read
+const READING_MS_PER_WORD: u64 = 400;
+/// Even a one-word question takes a moment to read
+const MIN_READING_MS: u64 = 1_000;
+
+/// Roughly how long a TTS engine takes to read `text` aloud, in ms.
+pub fn estimate_reading_ms(text: &str) -> u64 {
+    let words = text.split_whitespace().count() as u64;
+    (words * READING_MS_PER_WORD).max(MIN_READING_MS)
+}
+
+/// A buzz held during the buzz window: who, when it reached the server (ms)
+/// and that player's latency (ms) at the time.
+pub type HeldBuzz = (PlayerId, UnixMs, u64);
+
+/// The player whose buzz was earliest once each arrival time is moved back
+/// by that player's latency. Ties go to whoever reached the server first.
+pub fn earliest_buzz(buzzes: &[HeldBuzz]) -> Option<PlayerId> {
+    buzzes
+        .iter()
+        .enumerate()
+        .min_by_key(|(order, (_, arrived, latency))| (arrived.saturating_sub(*latency), *order))
+        .map(|(_, (pid, _, _))| *pid)
+}
+
+/// What a host ruling (`HostChecked`/`HostSkip`) can change, saved so a
+/// misclick can be undone.
+#[derive(Clone, Debug)]
+pub struct RoomSnapshot {
+    pub state: GameState,
+    pub scores: Vec<(PlayerId, i32)>,
+    pub streaks: Vec<(PlayerId, u32)>,
+    /// Players who had already buzzed on the question
+    pub buzzed: Vec<PlayerId>,
+    pub current_question: Option<(usize, usize)>,
+    pub current_buzzer: Option<PlayerId>,
+    pub winner: Option<PlayerId>,
+    pub wins: HashMap<PlayerId, u32>,
+    pub question_stats: Vec<QuestionStats>,
+    /// The current question's `answered` flag
+    pub answered: bool,
+    pub buzz_queue: Vec<(PlayerId, UnixMs)>,
+    pub held_buzzes: Vec<HeldBuzz>,
+    pub poll_answers: Vec<(PlayerId, String)>,
+    pub poll_graded: HashMap<PlayerId, (bool, i32)>,
+    /// Question timers that were running; undo starts them over
+    pub timers: Vec<TimerKind>,
+}
+
 pub struct Room {
     pub code: String,
     pub host_token: String,
     pub state: GameState,
-    pub host: Option<HostEntry>,
+    /// Every socket authenticated with the host token. Big games can split
+    /// host duties (one reads, one scores) across several devices.
+    pub hosts: Vec<HostEntry>,
+    /// Every host socket dropped and none has reconnected yet
+    pub host_away: bool,
     pub players: Vec<PlayerEntry>,
+    /// New players are turned away; existing players can still reconnect
+    pub locked: bool,
+    /// The board in play. In multi-round games this is a working copy of
+    /// `boards[current_board]`.
     pub categories: Vec<Category>,
+    /// Every round's board in play order (e.g. Jeopardy, Double Jeopardy).
+    /// Empty for single-board rooms, whose only board is `categories`.
+    pub boards: Vec<Vec<Category>>,
+    pub current_board: usize,
+    /// Every question is worth this many times its value (2 for Double
+    /// Jeopardy)
+    pub value_multiplier: u32,
     pub current_question: Option<(usize, usize)>, // (category_index, question_index)
     pub current_buzzer: Option<PlayerId>,
     pub last_activity: SystemTime,
+    /// How long the room may sit idle before cleanup, instead of the
+    /// server-wide TTL
+    pub ttl_override: Option<Duration>,
+    /// Everyone was told the room is about to expire; cleared by activity
+    pub expiry_warned: bool,
     pub winner: Option<PlayerId>,
+    /// Games won per player across `ResetGame`s, for best-of-N series
+    pub wins: HashMap<PlayerId, u32>,
+    pub settings: RoomSettings,
+    /// Chosen from `settings` when the room is created
+    pub scoring: Box<dyn ScoringStrategy>,
+    pub raised_hands: Vec<PlayerId>,
+    pub paused_from: Option<GameState>,
+    /// Public events for read-only observers (e.g. the SSE stream)
+    pub observers: broadcast::Sender<WsMsg>,
+    /// Websocket viewers; they get the same events as `observers`
+    pub spectators: Vec<tokio_mpmc::Sender<WsMsg>>,
+    /// Big-screen boards. They only get the board and who buzzed, see
+    /// [`shown_on_display`].
+    pub displays: Vec<tokio_mpmc::Sender<WsMsg>>,
+    /// Players who have rendered the current question
+    pub ready_acks: HashSet<PlayerId>,
+    /// The host is ready but buzzing waits on `ready_acks`
+    pub awaiting_acks: bool,
+    /// Players who buzzed while the question was still being read
+    pub early_buzz: HashSet<PlayerId>,
+    /// When buzzing last opened for the current question
+    pub buzz_opened_at: Option<Instant>,
+    /// When buzzing first opened for the current question, which
+    /// `BuzzStat::ms_to_buzz` counts from even after a steal reopens it
+    pub first_opened_at: Option<Instant>,
+    /// Every buzz on the current question with its server arrival time (ms),
+    /// accepted or not. Informational only; scoring uses `current_buzzer`.
+    pub buzz_queue: Vec<(PlayerId, UnixMs)>,
+    /// Highest buzz `seq` seen from each player on the current question
+    pub last_buzz_seq: HashMap<PlayerId, u32>,
+    /// When each player's unconfirmed first tap arrived, for rooms that
+    /// need a double buzz
+    pub first_taps: HashMap<PlayerId, Instant>,
+    /// Buzzes waiting for the buzz window to close, in arrival order
+    pub held_buzzes: Vec<HeldBuzz>,
+    /// Snapshots taken before each host ruling, newest last
+    pub history: Vec<RoomSnapshot>,
+    /// Every player id handed out in this room, so ids are never reused
+    pub issued_pids: HashSet<PlayerId>,
+    /// Recent bad-credential handshakes per address
+    pub failed_handshakes: HashMap<IpAddr, HandshakeFailures>,
+    /// Poll answers the host hasn't graded yet, in submission order
+    pub poll_answers: Vec<(PlayerId, String)>,
+    /// Poll answers already graded, with how much each changed the player's
+    /// score, so the host can overrule an auto-graded (or mistaken) ruling
+    pub poll_graded: HashMap<PlayerId, (bool, i32)>,
+    /// Everyone's score when the current question was picked, so voiding it
+    /// can take back rulings already made on it
+    pub question_start_scores: Vec<(PlayerId, i32)>,
+    /// Everything sent to the hosts, replayed to a host that reconnects.
+    /// Players keep their own, see [`PlayerEntry::events`].
+    pub host_events: EventLog,
+    /// Numbers messages to spectators, who can't resume
+    pub spectator_events: EventLog,
+    /// Numbers messages to displays, which only get some of the spectators'
+    pub display_events: EventLog,
+    /// How each question played out this game, in the order they were played
+    pub question_stats: Vec<QuestionStats>,
+    /// Bumped on every state change, see [`Room::set_state`]
+    pub turn_token: u64,
+    /// Every message in and out, when `settings.transcript` is on
+    pub transcript: Transcript,
+    /// Longest question or answer the host may edit in, from
+    /// `AppState::max_board_text_chars`
+    pub max_text_chars: usize,
+    /// Latest id of each pending timer, see [`ScheduledTimer`]
+    timers: HashMap<TimerKind, u64>,
+    next_timer_id: u64,
 }
 
 impl fmt::Debug for Room {
@@ -42,12 +375,24 @@ impl fmt::Debug for Room {
         f.debug_struct("Room")
             .field("code", &self.code)
             .field("host_token", &self.host_token)
-            .field("host", &self.host)
+            .field("hosts", &self.hosts)
             .field("state", &self.state)
             .field("players", &self.players)
             .field("category count", &self.categories.len())
             .field("current question", &self.current_question)
             .field("current buzzer", &self.current_buzzer)
+            .field("settings", &self.settings)
+            .field("raised hands", &self.raised_hands)
+            .field("paused from", &self.paused_from)
+            .field("observer count", &self.observers.receiver_count())
+            .field("spectator count", &self.spectators.len())
+            .field("display count", &self.displays.len())
+            .field("ready acks", &self.ready_acks)
+            .field("awaiting acks", &self.awaiting_acks)
+            .field("early buzz", &self.early_buzz)
+            .field("buzz queue", &self.buzz_queue)
+            .field("undo depth", &self.history.len())
+            .field("timers", &self.timers)
             .finish()
     }
 }
@@ -56,6 +401,8 @@ pub struct RoomResponse {
     pub messages_to_host: Vec<WsMsg>,
     pub messages_to_players: Vec<WsMsg>,
     pub messages_to_specific: Vec<(PlayerId, WsMsg)>,
+    pub messages_to_observers: Vec<WsMsg>,
+    pub timers: Vec<ScheduledTimer>,
 }
 
 impl Default for RoomResponse {
@@ -70,30 +417,59 @@ impl RoomResponse {
             messages_to_host: vec![],
             messages_to_players: vec![],
             messages_to_specific: vec![],
+            messages_to_observers: vec![],
+            timers: vec![],
         }
     }
 
     pub fn broadcast_state(state_msg: WsMsg) -> Self {
         Self {
             messages_to_host: vec![state_msg.clone()],
-            messages_to_players: vec![state_msg],
+            messages_to_players: vec![state_msg.clone()],
             messages_to_specific: vec![],
+            messages_to_observers: vec![state_msg],
+            timers: vec![],
         }
     }
 
     pub fn to_host(msg: WsMsg) -> Self {
         Self {
             messages_to_host: vec![msg],
-            messages_to_players: vec![],
-            messages_to_specific: vec![],
+            ..Self::new()
         }
     }
 
     pub fn to_player(player_id: PlayerId, msg: WsMsg) -> Self {
         Self {
-            messages_to_host: vec![],
-            messages_to_players: vec![],
             messages_to_specific: vec![(player_id, msg)],
+            ..Self::new()
+        }
+    }
+
+    pub fn to_observers(msg: WsMsg) -> Self {
+        Self {
+            messages_to_observers: vec![msg],
+            ..Self::new()
+        }
+    }
+
+    /// Send `host_msg` to the host and `player_msg` to players and observers,
+    /// for messages where the host sees more (e.g. answers).
+    pub fn broadcast_state_split(host_msg: WsMsg, player_msg: WsMsg) -> Self {
+        Self {
+            messages_to_host: vec![host_msg],
+            messages_to_players: vec![player_msg.clone()],
+            messages_to_observers: vec![player_msg],
+            ..Self::new()
+        }
+    }
+
+    /// Everyone but the host.
+    pub fn to_players_and_observers(msg: WsMsg) -> Self {
+        Self {
+            messages_to_players: vec![msg.clone()],
+            messages_to_observers: vec![msg],
+            ..Self::new()
         }
     }
 
@@ -101,6 +477,9 @@ impl RoomResponse {
         self.messages_to_host.extend(other.messages_to_host);
         self.messages_to_players.extend(other.messages_to_players);
         self.messages_to_specific.extend(other.messages_to_specific);
+        self.messages_to_observers
+            .extend(other.messages_to_observers);
+        self.timers.extend(other.timers);
         self
     }
 }
@@ -111,22 +490,98 @@ impl Room {
             code,
             host_token,
             state: GameState::default(),
-            host: None,
+            hosts: Vec::new(),
+            host_away: false,
             players: Vec::new(),
+            locked: false,
             categories: Vec::new(),
+            boards: Vec::new(),
+            current_board: 0,
+            value_multiplier: 1,
             current_question: None,
             current_buzzer: None,
             last_activity: SystemTime::now(),
+            ttl_override: None,
+            expiry_warned: false,
             winner: None,
+            wins: HashMap::new(),
+            settings: RoomSettings::default(),
+            scoring: RoomSettings::default().scoring_strategy(),
+            raised_hands: Vec::new(),
+            paused_from: None,
+            observers: broadcast::channel(OBSERVER_BUFFER).0,
+            spectators: Vec::new(),
+            displays: Vec::new(),
+            ready_acks: HashSet::new(),
+            awaiting_acks: false,
+            early_buzz: HashSet::new(),
+            buzz_opened_at: None,
+            first_opened_at: None,
+            buzz_queue: Vec::new(),
+            last_buzz_seq: HashMap::new(),
+            first_taps: HashMap::new(),
+            held_buzzes: Vec::new(),
+            history: Vec::new(),
+            issued_pids: HashSet::new(),
+            failed_handshakes: HashMap::new(),
+            poll_answers: Vec::new(),
+            poll_graded: HashMap::new(),
+            question_start_scores: Vec::new(),
+            host_events: EventLog::default(),
+            spectator_events: EventLog::new(0),
+            display_events: EventLog::new(0),
+            question_stats: Vec::new(),
+            turn_token: 0,
+            transcript: Transcript::default(),
+            max_text_chars: crate::DEFAULT_MAX_BOARD_TEXT_CHARS,
+            timers: HashMap::new(),
+            next_timer_id: 0,
         }
     }
 
     pub fn touch(&mut self) {
         self.last_activity = SystemTime::now();
+        self.expiry_warned = false;
+    }
+
+    /// Pick a random player id that has never been used in this room. Ids
+    /// are unpredictable and a player who joins after another leaves can't
+    /// inherit their id. 0 is reserved for the host.
+    pub fn allocate_player_id(&mut self) -> PlayerId {
+        let mut rng = rand::rng();
+        loop {
+            let candidate = rng.random_range(1..=PlayerId::MAX);
+            if self.issued_pids.insert(candidate) {
+                return candidate;
+            }
+        }
+    }
+
+    /// Pick a display name for a joining player, suffixing it ("Sam (2)") if
+    /// another player already uses it (case-insensitively).
+    pub fn unique_player_name(&self, requested: &str) -> String {
+        let taken = |candidate: &str| {
+            let lowered = candidate.to_lowercase();
+            self.players
+                .iter()
+                .any(|p| p.player.name.to_lowercase() == lowered)
+        };
+
+        if !taken(requested) {
+            return requested.to_string();
+        }
+        (2..)
+            .map(|n| format!("{requested} ({n})"))
+            .find(|candidate| !taken(candidate))
+            .unwrap_or_else(|| requested.to_string())
     }
 }
 
 impl Room {
+    /// Highest score wins, ties mean no winner. Scores are compared as plain
+    /// integers, so any `starting_score` (or negative totals) needs no special
+    /// handling here. The winner is credited in the series standings, unless
+    /// the game was already over.
     fn determine_winner(&mut self) {
         if self.players.is_empty() {
             self.winner = None;
@@ -156,6 +611,9 @@ impl Room {
                 score = max_score,
                 "Winner determined"
             );
+            if self.state != GameState::GameEnd {
+                *self.wins.entry(winners[0].player.pid).or_default() += 1;
+            }
             winner_id
         } else {
             tracing::info!(
@@ -168,95 +626,466 @@ impl Room {
         };
     }
 
-    fn build_game_state_msg(&self) -> WsMsg {
+    /// `GameState` as players and observers see it, with answers stripped.
+    pub(crate) fn build_player_game_state_msg(&self) -> WsMsg {
+        self.build_game_state_msg_with(self.categories.iter().map(Category::redacted).collect())
+    }
+
+    pub(crate) fn build_game_state_msg(&self) -> WsMsg {
+        self.build_game_state_msg_with(self.categories.clone())
+    }
+
+    fn build_game_state_msg_with(&self, categories: Vec<Category>) -> WsMsg {
         let players: Vec<Player> = self.players.iter().map(|e| e.player.clone()).collect();
 
         WsMsg::GameState {
             state: self.state.clone(),
-            categories: self.categories.clone(),
+            categories,
             players,
             current_question: self.current_question,
             current_buzzer: self.current_buzzer,
             winner: self.winner,
+            series_standings: self.wins.clone(),
+            turn_token: self.turn_token,
         }
     }
 
-    fn build_player_state_msg(&self, player_id: PlayerId) -> Option<WsMsg> {
+    /// The full `GameState` for the host and the redacted one for everyone else.
+    fn broadcast_game_state(&self) -> RoomResponse {
+        RoomResponse::broadcast_state_split(
+            self.build_game_state_msg(),
+            self.build_player_game_state_msg(),
+        )
+    }
+
+    fn build_player_list_msg(&self) -> WsMsg {
+        WsMsg::PlayerList(self.players.iter().map(|e| e.player.clone()).collect())
+    }
+
+    pub(crate) fn build_player_state_msg(&self, player_id: PlayerId) -> Option<WsMsg> {
         let player = self.players.iter().find(|p| p.player.pid == player_id)?;
-        let can_buzz = self.state == GameState::WaitingForBuzz && !player.player.buzzed;
+        let blocked_reason = if self.state != GameState::WaitingForBuzz {
+            None
+        } else if player.player.buzzed {
+            Some(BuzzBlockedReason::AlreadyAnswered)
+        } else if player.bench_remaining().is_some() {
+            Some(BuzzBlockedReason::Benched)
+        } else if self.in_early_buzz_lockout(player_id) {
+            Some(BuzzBlockedReason::EarlyBuzz)
+        } else {
+            None
+        };
+        let can_buzz = self.state == GameState::WaitingForBuzz && blocked_reason.is_none();
 
         Some(WsMsg::PlayerState {
             pid: player.player.pid,
             buzzed: player.player.buzzed,
             score: player.player.score,
             can_buzz,
+            blocked_reason,
         })
     }
 
     #[tracing::instrument(skip(self, msg), fields(room_code = %self.code))]
     pub fn handle_message(&mut self, msg: &WsMsg, sender_id: Option<PlayerId>) -> RoomResponse {
+        if self.state == GameState::Paused && is_game_action(msg) {
+            // The game is frozen until the host resumes; remind buzzers they can't
+            tracing::debug!(?sender_id, "Ignoring message while paused");
+            return match (msg, sender_id) {
+                (WsMsg::Buzz { .. }, Some(player_id)) => self.build_player_state_delta(player_id),
+                _ => RoomResponse::new(),
+            };
+        }
+
+        let recovered = if is_game_action(msg) {
+            self.clear_stale_selection()
+        } else {
+            RoomResponse::new()
+        };
+        recovered.merge(self.handle_action(msg, sender_id))
+    }
+
+    /// Forget how the question in play went: who buzzed or is holding a
+    /// buzz, poll answers, and its timers. Scores, the question itself and
+    /// `question_start_scores` are left to the caller.
+    fn reset_question_state(&mut self) {
+        self.current_buzzer = None;
+        self.raised_hands.clear();
+        self.ready_acks.clear();
+        self.awaiting_acks = false;
+        self.early_buzz.clear();
+        self.buzz_opened_at = None;
+        self.first_opened_at = None;
+        self.buzz_queue.clear();
+        self.last_buzz_seq.clear();
+        self.first_taps.clear();
+        self.held_buzzes.clear();
+        self.poll_answers.clear();
+        self.poll_graded.clear();
+        for kind in QUESTION_TIMERS {
+            self.cancel_timer(kind);
+        }
+        for player in &mut self.players {
+            player.player.buzzed = false;
+        }
+    }
+
+    /// If the board changed under the current question (e.g. its category
+    /// was removed), drop it and go back to `Selection` rather than wedge.
+    fn clear_stale_selection(&mut self) -> RoomResponse {
+        let Some((category_index, question_index)) = self.current_question else {
+            return RoomResponse::new();
+        };
+        let exists = self
+            .categories
+            .get(category_index)
+            .is_some_and(|cat| question_index < cat.questions.len());
+        if exists {
+            return RoomResponse::new();
+        }
+
+        tracing::warn!(
+            category_index,
+            question_index,
+            state = ?self.state,
+            "Current question no longer exists, returning to selection"
+        );
+        self.current_question = None;
+        self.reset_question_state();
+        self.question_start_scores.clear();
+        if !matches!(
+            self.state,
+            GameState::Start | GameState::Selection | GameState::GameEnd
+        ) {
+            self.set_state(GameState::Selection);
+        }
+
+        RoomResponse::broadcast_state(WsMsg::SelectionCleared {})
+            .merge(self.broadcast_game_state())
+            .merge(self.build_all_player_states())
+    }
+
+    fn handle_action(&mut self, msg: &WsMsg, sender_id: Option<PlayerId>) -> RoomResponse {
         match msg {
             WsMsg::StartGame {} => {
                 tracing::info!("Game started");
-                self.state = GameState::Selection;
-                RoomResponse::broadcast_state(self.build_game_state_msg())
+                if self.settings.auto_lock {
+                    self.locked = true;
+                }
+                self.set_state(GameState::Selection);
+                self.broadcast_game_state()
                     .merge(self.build_all_player_states())
             }
 
             WsMsg::HostChoice {
                 category_index,
                 question_index,
-            } => {
-                tracing::debug!(category_index, question_index, "Host selected question");
-                self.current_question = Some((*category_index, *question_index));
-                self.current_buzzer = None;
-                for player in &mut self.players {
-                    player.player.buzzed = false;
-                }
-                self.state = GameState::QuestionReading;
-                RoomResponse::broadcast_state(self.build_game_state_msg())
-                    .merge(self.build_all_player_states())
+            } => self.handle_host_choice(*category_index, *question_index),
+
+            WsMsg::RandomQuestion { seed } if sender_id.is_none() => {
+                self.handle_random_question(*seed)
             }
 
-            WsMsg::Buzz {} => {
-                if self.state == GameState::WaitingForBuzz
-                    && let Some(player_id) = sender_id
-                    && let Some(player_entry) =
-                        self.players.iter_mut().find(|p| p.player.pid == player_id)
-                    && !player_entry.player.buzzed
+            WsMsg::Buzz { seq } => {
+                if let (Some(player_id), Some(seq)) = (sender_id, *seq) {
+                    if self
+                        .last_buzz_seq
+                        .get(&player_id)
+                        .is_some_and(|last| seq <= *last)
+                    {
+                        tracing::debug!(player_id, seq, "Ignoring retransmitted buzz");
+                        return RoomResponse::new();
+                    }
+                    self.last_buzz_seq.insert(player_id, seq);
+                }
+                if let Some(player_id) = sender_id
+                    && !self.confirm_double_buzz(player_id)
                 {
-                    tracing::info!(
+                    if self.state != GameState::WaitingForBuzz {
+                        return self.build_player_state_delta(player_id);
+                    }
+                    return RoomResponse::to_player(
                         player_id,
-                        player_name = %player_entry.player.name,
-                        "Player buzzed in"
+                        WsMsg::Notice {
+                            message: "Tap again to buzz in".to_string(),
+                        },
                     );
-                    player_entry.player.buzzed = true;
-                    self.current_buzzer = Some(player_id);
-                    self.state = GameState::Answer;
+                }
+                let order = self.record_buzz(sender_id);
+                self.handle_buzz(sender_id).merge(order)
+            }
 
-                    let buzzed_msg = WsMsg::Buzzed {
-                        pid: player_id,
-                        name: player_entry.player.name.clone(),
-                    };
+            WsMsg::HostReady {} => self.handle_host_ready(),
 
-                    return RoomResponse::to_host(buzzed_msg)
-                        .merge(RoomResponse::broadcast_state(self.build_game_state_msg()))
-                        .merge(self.build_all_player_states());
+            WsMsg::QuestionLoaded {} => {
+                let reading = self.state == GameState::QuestionReading
+                    || self.paused_from == Some(GameState::QuestionReading);
+                if let Some(player_id) = sender_id
+                    && reading
+                    && self.players.iter().any(|p| p.player.pid == player_id)
+                {
+                    self.ready_acks.insert(player_id);
+                    if self.awaiting_acks
+                        && self.state == GameState::QuestionReading
+                        && self.all_players_loaded()
+                    {
+                        return self.open_buzzing();
+                    }
                 }
                 RoomResponse::new()
             }
 
-            WsMsg::HostReady {} => {
-                self.state = GameState::WaitingForBuzz;
-                RoomResponse::broadcast_state(self.build_game_state_msg())
+            WsMsg::HostChecked {
+                correct,
+                turn_token,
+            } => {
+                if self.is_stale_turn(*turn_token, "HostChecked") {
+                    return RoomResponse::to_host(self.build_game_state_msg());
+                }
+                self.handle_host_checked(*correct)
+            }
+
+            WsMsg::AwardQuestion {
+                pid,
+                correct,
+                turn_token,
+            } if sender_id.is_none() => {
+                if self.is_stale_turn(*turn_token, "AwardQuestion") {
+                    return RoomResponse::to_host(self.build_game_state_msg());
+                }
+                self.handle_award_question(*pid, *correct)
+            }
+
+            WsMsg::SubmitAnswer { text } => match sender_id {
+                Some(player_id) => self.handle_submit_answer(player_id, text),
+                None => RoomResponse::new(),
+            },
+
+            WsMsg::PollChecked { pid, correct } if sender_id.is_none() => {
+                self.handle_poll_checked(*pid, *correct)
+            }
+
+            WsMsg::HostSkip { turn_token } => {
+                if self.is_stale_turn(*turn_token, "HostSkip") {
+                    return RoomResponse::to_host(self.build_game_state_msg());
+                }
+                self.handle_host_skip()
+            }
+
+            WsMsg::VoidQuestion {} if sender_id.is_none() => self.handle_void_question(),
+
+            WsMsg::HostContinue { turn_token } => {
+                if self.is_stale_turn(*turn_token, "HostContinue") {
+                    return RoomResponse::to_host(self.build_game_state_msg());
+                }
+                self.handle_host_continue()
+            }
+
+            WsMsg::Undo {} if sender_id.is_none() => self.handle_undo(),
+
+            WsMsg::NextBoard {} if sender_id.is_none() => self.handle_next_board(),
+
+            WsMsg::ResetGame {} if sender_id.is_none() => self.handle_reset_game(),
+
+            WsMsg::ResetSeries {} if sender_id.is_none() => {
+                tracing::info!("Host reset the series standings");
+                self.wins.clear();
+                self.broadcast_game_state()
+            }
+
+            WsMsg::LockRoom { locked } if sender_id.is_none() => {
+                tracing::info!(locked, "Host changed room lock");
+                self.locked = *locked;
+                RoomResponse::to_host(WsMsg::Notice {
+                    message: if *locked {
+                        "Room locked to new players".to_string()
+                    } else {
+                        "Room open to new players".to_string()
+                    },
+                })
+            }
+
+            WsMsg::SetRoomTtl { seconds } if sender_id.is_none() => {
+                let ttl = Duration::from_secs(*seconds);
+                if ttl > MAX_ROOM_TTL {
+                    return RoomResponse::to_host(WsMsg::Notice {
+                        message: format!(
+                            "Rooms can be kept for at most {} hours",
+                            MAX_ROOM_TTL.as_secs() / 3600
+                        ),
+                    });
+                }
+                tracing::info!(seconds, "Host changed room TTL");
+                self.ttl_override = (*seconds > 0).then_some(ttl);
+                RoomResponse::to_host(WsMsg::Notice {
+                    message: match self.ttl_override {
+                        Some(_) => format!("Room kept for {} seconds without activity", seconds),
+                        None => "Room kept for the server's default time".to_string(),
+                    },
+                })
+            }
+
+            WsMsg::TransferHost { new_host_token } if sender_id.is_none() => {
+                if *new_host_token {
+                    tracing::info!("Host rotated the host token");
+                    self.host_token = crate::generate_host_token();
+                }
+                RoomResponse::to_host(WsMsg::HostToken {
+                    token: self.host_token.clone(),
+                })
+            }
+
+            WsMsg::RevealAll { to_players } if sender_id.is_none() => {
+                if self.state != GameState::GameEnd {
+                    return RoomResponse::to_host(WsMsg::Notice {
+                        message: "Answers can only be revealed once the game is over".to_string(),
+                    });
+                }
+                tracing::info!(to_players, "Host revealed all answers");
+                let all_answers = WsMsg::AllAnswers {
+                    board: self.categories.clone(),
+                };
+                if *to_players {
+                    RoomResponse::broadcast_state(all_answers)
+                } else {
+                    RoomResponse::to_host(all_answers)
+                }
+            }
+
+            WsMsg::ShuffleBoard {
+                seed,
+                shuffle_questions,
+            } if sender_id.is_none() => self.handle_shuffle_board(*seed, *shuffle_questions),
+
+            WsMsg::SetMultiplier { factor } if sender_id.is_none() => {
+                if *factor == 0 {
+                    return RoomResponse::to_host(WsMsg::Notice {
+                        message: "The multiplier must be at least 1".to_string(),
+                    });
+                }
+                tracing::info!(factor, "Host set value multiplier");
+                self.value_multiplier = *factor;
+                // Players' stakes changed too, so everyone hears about it
+                RoomResponse::broadcast_state(WsMsg::Notice {
+                    message: format!("Questions are now worth {}x", factor),
+                })
+            }
+
+            WsMsg::ForceState { state } if sender_id.is_none() => {
+                tracing::warn!(from = ?self.state, to = ?state, "FORCING game state, bypassing transitions");
+                if *state != GameState::Paused {
+                    self.paused_from = None;
+                }
+                self.set_state(state.clone());
+                self.broadcast_game_state()
+                    .merge(self.build_all_player_states())
+            }
+
+            WsMsg::RestoreBoardState { answered } if sender_id.is_none() => {
+                self.handle_restore_board_state(answered)
+            }
+
+            WsMsg::EditQuestion {
+                category_index,
+                question_index,
+                question,
+                answer,
+                value,
+                force,
+            } if sender_id.is_none() => self.handle_edit_question(
+                *category_index,
+                *question_index,
+                question.as_deref(),
+                answer.as_deref(),
+                *value,
+                *force,
+            ),
+
+            WsMsg::Pause {} if sender_id.is_none() && self.state != GameState::Paused => {
+                tracing::info!(state = ?self.state, "Game paused");
+                self.paused_from = Some(std::mem::replace(&mut self.state, GameState::Paused));
+                self.broadcast_game_state()
                     .merge(self.build_all_player_states())
             }
 
-            WsMsg::HostChecked { correct } => self.handle_host_checked(*correct),
+            WsMsg::Resume {} if sender_id.is_none() => {
+                let Some(previous) = self.paused_from.take() else {
+                    return RoomResponse::new();
+                };
+                tracing::info!(state = ?previous, "Game resumed");
+                self.set_state(previous);
+                if self.state == GameState::QuestionReading && self.awaiting_acks {
+                    // The pause gave everyone time to load the question
+                    return self.open_buzzing();
+                }
+                let timers = self.rearm_timers();
+                self.broadcast_game_state()
+                    .merge(self.build_all_player_states())
+                    .merge(timers)
+            }
+
+            WsMsg::BenchPlayer { pid, duration_ms } if sender_id.is_none() => {
+                let Some(player) = self.players.iter_mut().find(|p| p.player.pid == *pid) else {
+                    return RoomResponse::new();
+                };
+                tracing::info!(player_id = pid, duration_ms, "Host benched player");
+                player.benched_until = Some(Instant::now() + Duration::from_millis(*duration_ms));
+
+                let notice = WsMsg::Notice {
+                    message: format!(
+                        "The host benched your buzzer for {} seconds",
+                        duration_ms.div_ceil(1000)
+                    ),
+                };
+                RoomResponse::to_player(*pid, notice).merge(self.build_player_state_delta(*pid))
+            }
+
+            WsMsg::RenamePlayer { name } => self.handle_rename(name, sender_id),
+
+            WsMsg::LeaveRoom {} => match sender_id {
+                Some(player_id) => self.handle_leave(player_id),
+                None => RoomResponse::new(),
+            },
 
-            WsMsg::HostSkip {} => self.handle_host_skip(),
+            WsMsg::Chat { text } => self.handle_chat(text, sender_id),
+
+            WsMsg::ResumeFrom { last_seq } => match sender_id {
+                Some(player_id) => {
+                    let Some(entry) = self.players.iter().find(|p| p.player.pid == player_id)
+                    else {
+                        return RoomResponse::new();
+                    };
+                    let replay = WsMsg::Replay {
+                        events: entry.events.since(*last_seq),
+                    };
+                    RoomResponse::to_player(player_id, replay)
+                }
+                None => RoomResponse::to_host(WsMsg::Replay {
+                    events: self.host_events.since(*last_seq),
+                }),
+            },
+
+            WsMsg::RequestState {} => match sender_id {
+                None => RoomResponse::to_host(self.build_game_state_msg()),
+                Some(player_id) if self.players.iter().any(|p| p.player.pid == player_id) => {
+                    RoomResponse::to_player(player_id, self.build_player_game_state_msg())
+                        .merge(self.build_player_state_delta(player_id))
+                }
+                Some(_) => RoomResponse::new(),
+            },
 
-            WsMsg::HostContinue {} => self.handle_host_continue(),
+            WsMsg::RaiseHand {} => {
+                // Raising a hand is a soft signal for the host; it never touches buzz state
+                if let Some(player_id) = sender_id
+                    && self.players.iter().any(|p| p.player.pid == player_id)
+                    && !self.raised_hands.contains(&player_id)
+                {
+                    tracing::debug!(player_id, "Player raised hand");
+                    self.raised_hands.push(player_id);
+                    return RoomResponse::to_host(WsMsg::HandRaised { pid: player_id });
+                }
+                RoomResponse::new()
+            }
 
             WsMsg::Heartbeat { hbid, t_dohb_recv } => {
                 if let Some(sender_id) = sender_id
@@ -272,23 +1101,34 @@ impl Room {
                     && let Some(entry) = self.players.iter_mut().find(|p| p.player.pid == sender_id)
                 {
                     let t_lat_u32 = (*t_lat).try_into().unwrap_or(u32::MAX);
-                    entry.on_latencyhb(*hbid, t_lat_u32);
+                    if entry.on_latencyhb(*hbid, t_lat_u32) {
+                        // Lets the host flag shaky connections
+                        return RoomResponse::to_host(WsMsg::PlayerLatency {
+                            pid: sender_id,
+                            latency: entry.latency().unwrap_or(0),
+                            jitter: entry.jitter(),
+                        });
+                    }
                 }
                 RoomResponse::new()
             }
 
-            WsMsg::EndGame {} => {
-                self.determine_winner();
-                tracing::info!(?self.winner, "Game ended");
-                self.state = GameState::GameEnd;
-                RoomResponse::broadcast_state(self.build_game_state_msg())
-                    .merge(self.build_all_player_states())
-            }
+            WsMsg::EndGame {} if sender_id.is_none() => self.end_game(),
 
             _ => RoomResponse::new(),
         }
     }
 
+    /// Finish the game now, whatever is left on the board.
+    fn end_game(&mut self) -> RoomResponse {
+        self.determine_winner();
+        tracing::info!(?self.winner, "Game ended");
+        self.paused_from = None;
+        self.set_state(GameState::GameEnd);
+        self.broadcast_game_state()
+            .merge(self.build_all_player_states())
+    }
+
     fn build_all_player_states(&self) -> RoomResponse {
         let mut response = RoomResponse::new();
         for player in &self.players {
@@ -299,789 +1139,5283 @@ impl Room {
         response
     }
 
-    fn handle_host_checked(&mut self, correct: bool) -> RoomResponse {
-        let Some((cat_idx, q_idx)) = self.current_question else {
+    /// The current question's answer, privately to the host, so they can rule
+    /// without reading it off the board.
+    fn answer_hint(&self) -> RoomResponse {
+        let Some((category_index, question_index)) = self.current_question else {
             return RoomResponse::new();
         };
+        self.categories
+            .get(category_index)
+            .and_then(|cat| cat.questions.get(question_index))
+            .map(|question| {
+                RoomResponse::to_host(WsMsg::AnswerHint {
+                    answer: question.answer.clone(),
+                })
+            })
+            .unwrap_or_default()
+    }
 
+    /// The cue for players' devices to read the current question aloud.
+    /// Only while the question is being read.
+    pub fn read_question_cue(&self) -> Option<WsMsg> {
+        if self.state != GameState::QuestionReading {
+            return None;
+        }
+        let (category_index, question_index) = self.current_question?;
         let question = self
             .categories
-            .get_mut(cat_idx)
-            .and_then(|cat| cat.questions.get_mut(q_idx));
+            .get(category_index)?
+            .questions
+            .get(question_index)?;
+        Some(WsMsg::ReadQuestionNow {
+            text: question.question.clone(),
+            estimated_duration_ms: estimate_reading_ms(&question.question),
+        })
+    }
 
-        let question_value = question.as_ref().map(|q| q.value as i32);
-        let Some(question) = question else {
+    /// Announce the current question's answer to everyone on entering `AnswerReveal`.
+    fn build_answer_revealed(&self) -> RoomResponse {
+        let Some((category_index, question_index)) = self.current_question else {
             return RoomResponse::new();
         };
-
-        let Some(question_value) = question_value else {
+        let Some(question) = self
+            .categories
+            .get(category_index)
+            .and_then(|cat| cat.questions.get(question_index))
+        else {
             return RoomResponse::new();
         };
 
-        if let Some(buzzer_id) = self.current_buzzer
-            && let Some(player) = self.players.iter_mut().find(|p| p.player.pid == buzzer_id)
-        {
-            if correct {
-                player.player.score += question_value;
-            } else {
-                player.player.score -= question_value;
+        let stats = self
+            .question_stats
+            .iter()
+            .rev()
+            .find(|s| {
+                (s.board, s.category_index, s.question_index)
+                    == (self.current_board, category_index, question_index)
+            })
+            .cloned()
+            .unwrap_or_else(|| {
+                QuestionStats::new(self.current_board, category_index, question_index)
+            });
+
+        RoomResponse::broadcast_state(WsMsg::AnswerRevealed {
+            category_index,
+            question_index,
+            answer: question.answer.clone(),
+        })
+        .merge(RoomResponse::to_host(WsMsg::QuestionStats { stats }))
+    }
+
+    /// Stats for the question in play, started on first use.
+    fn current_question_stats(&mut self) -> Option<&mut QuestionStats> {
+        let (category_index, question_index) = self.current_question?;
+        let key = (self.current_board, category_index, question_index);
+        let position = self
+            .question_stats
+            .iter()
+            .rposition(|s| (s.board, s.category_index, s.question_index) == key);
+        let position = match position {
+            Some(position) => position,
+            None => {
+                self.question_stats.push(QuestionStats::new(
+                    self.current_board,
+                    category_index,
+                    question_index,
+                ));
+                self.question_stats.len() - 1
             }
-        }
+        };
+        self.question_stats.get_mut(position)
+    }
 
-        let any_can_buzz = self.players.iter().any(|p| !p.player.buzzed);
+    fn connected_players(&self) -> impl Iterator<Item = &PlayerEntry> {
+        self.players
+            .iter()
+            .filter(|p| matches!(p.status, ConnectionStatus::Connected))
+    }
 
-        if correct {
-            question.answered = true;
-            self.state = GameState::AnswerReveal;
-        } else if any_can_buzz {
-            self.current_buzzer = None;
-            self.state = GameState::WaitingForBuzz;
-        } else {
-            question.answered = true;
-            self.state = GameState::AnswerReveal;
+    fn handle_host_ready(&mut self) -> RoomResponse {
+        self.cancel_timer(TimerKind::AutoReady);
+        if self.state != GameState::QuestionReading || self.all_players_loaded() {
+            return self.open_buzzing();
         }
 
-        RoomResponse::broadcast_state(self.build_game_state_msg())
-            .merge(self.build_all_player_states())
+        // Give slow clients a chance to render the question first
+        // Acks from players who have since dropped don't count
+        let waiting = self
+            .connected_players()
+            .filter(|p| !self.ready_acks.contains(&p.player.pid))
+            .count();
+        tracing::debug!(waiting, "Waiting for players to load question");
+        self.awaiting_acks = true;
+        let timeout = self.settings.question_load_timeout();
+        RoomResponse::to_host(WsMsg::Notice {
+            message: format!("Waiting for {} player(s) to load the question", waiting),
+        })
+        .merge(self.schedule_timer(TimerKind::OpenBuzzing, timeout))
     }
 
-    fn handle_host_skip(&mut self) -> RoomResponse {
-        let Some((cat_idx, q_idx)) = self.current_question else {
-            return RoomResponse::new();
-        };
-
-        tracing::info!(
-            category_index = cat_idx,
-            question_index = q_idx,
-            "Host skipped question"
-        );
+    fn all_players_loaded(&self) -> bool {
+        self.connected_players()
+            .all(|p| self.ready_acks.contains(&p.player.pid))
+    }
 
-        // Mark question as answered
-        if let Some(question) = self
-            .categories
-            .get_mut(cat_idx)
-            .and_then(|cat| cat.questions.get_mut(q_idx))
+    fn open_buzzing(&mut self) -> RoomResponse {
+        self.awaiting_acks = false;
+        // Taps from before buzzing opened don't count toward a double buzz
+        self.first_taps.clear();
+        self.cancel_timer(TimerKind::OpenBuzzing);
+        // Poll questions skip buzzing and take answers from everyone
+        if self
+            .current_question_mut()
+            .is_some_and(|q| q.mode == QuestionMode::Poll)
         {
-            question.answered = true;
+            self.set_state(GameState::Polling);
+            return self
+                .broadcast_game_state()
+                .merge(self.build_all_player_states());
         }
+        self.set_state(GameState::WaitingForBuzz);
+        let now = Instant::now();
+        self.buzz_opened_at = Some(now);
+        self.first_opened_at.get_or_insert(now);
+
+        let response = self
+            .broadcast_game_state()
+            .merge(self.build_all_player_states());
+        if self.early_buzz.is_empty() {
+            return response;
+        }
+        response.merge(self.schedule_timer(TimerKind::EarlyBuzzLockout, EARLY_BUZZ_LOCKOUT))
+    }
 
-        self.state = GameState::AnswerReveal;
-
-        RoomResponse::broadcast_state(self.build_game_state_msg())
-            .merge(self.build_all_player_states())
+    /// Timers that came due during a pause were ignored. Start the ones the
+    /// resumed state relies on again, with their full duration.
+    fn rearm_timers(&mut self) -> RoomResponse {
+        match self.state {
+            GameState::QuestionReading => match self.settings.auto_ready_delay() {
+                Some(delay) => self.schedule_timer(TimerKind::AutoReady, delay),
+                None => RoomResponse::new(),
+            },
+            GameState::WaitingForBuzz => {
+                let mut response = RoomResponse::new();
+                if let Some(window) = self.settings.buzz_window()
+                    && !self.held_buzzes.is_empty()
+                {
+                    response = response.merge(self.schedule_timer(TimerKind::BuzzWindow, window));
+                }
+                if !self.early_buzz.is_empty() {
+                    response = response.merge(
+                        self.schedule_timer(TimerKind::EarlyBuzzLockout, EARLY_BUZZ_LOCKOUT),
+                    );
+                }
+                // Someone already answered wrong, so the others are stealing
+                if self.players.iter().any(|p| p.player.buzzed) {
+                    response = response.merge(self.open_steal_window());
+                }
+                response
+            }
+            GameState::Answer => self.schedule_answer_timeout(),
+            _ => RoomResponse::new(),
+        }
     }
 
-    fn handle_host_continue(&mut self) -> RoomResponse {
-        tracing::info!("Host continuing from answer reveal");
-
-        // Clear current question and buzzer
-        self.current_question = None;
-        self.current_buzzer = None;
-
-        for player in &mut self.players {
-            player.player.buzzed = false;
+    /// Start the answer clock for the current buzzer, if the room uses one.
+    fn schedule_answer_timeout(&mut self) -> RoomResponse {
+        match self.settings.answer_timeout() {
+            Some(timeout) => self.schedule_timer(TimerKind::AnswerTimeout, timeout),
+            None => RoomResponse::new(),
         }
+    }
 
-        // Transition to Selection or GameEnd
-        self.state = if self.has_remaining_questions() {
-            GameState::Selection
-        } else {
-            self.determine_winner();
-            GameState::GameEnd
+    /// Limit how long the others have to steal after a wrong answer, if the
+    /// room plays with a steal window.
+    fn open_steal_window(&mut self) -> RoomResponse {
+        let Some(window) = self.settings.steal_window() else {
+            return RoomResponse::new();
         };
+        RoomResponse::broadcast_state(WsMsg::StealWindowOpen {
+            duration_ms: window.as_millis().try_into().unwrap_or(u64::MAX),
+        })
+        .merge(self.schedule_timer(TimerKind::StealWindow, window))
+    }
 
-        RoomResponse::broadcast_state(self.build_game_state_msg())
-            .merge(self.build_all_player_states())
+    fn in_early_buzz_lockout(&self, player_id: PlayerId) -> bool {
+        self.early_buzz.contains(&player_id)
+            && self
+                .buzz_opened_at
+                .is_some_and(|opened| opened.elapsed() < EARLY_BUZZ_LOCKOUT)
     }
 
-    #[tracing::instrument(skip(self, msg), fields(room_code = %self.code))]
-    pub async fn update(&mut self, msg: &WsMsg, pid: Option<PlayerId>) -> anyhow::Result<()> {
-        tracing::trace!(?msg, ?pid, "Processing message");
+    fn schedule_timer(&mut self, kind: TimerKind, after: Duration) -> RoomResponse {
+        self.next_timer_id += 1;
+        self.timers.insert(kind, self.next_timer_id);
+        RoomResponse {
+            timers: vec![ScheduledTimer {
+                kind,
+                id: self.next_timer_id,
+                after,
+            }],
+            ..RoomResponse::new()
+        }
+    }
 
-        let response = self.handle_message(msg, pid);
+    fn cancel_timer(&mut self, kind: TimerKind) {
+        self.timers.remove(&kind);
+    }
 
-        for msg in response.messages_to_host {
-            if let Some(host) = &self.host {
-                let _ = host.sender.send(msg).await;
-            }
+    /// React to a timer this room scheduled earlier. Cancelled or replaced
+    /// timers do nothing.
+    #[tracing::instrument(skip(self), fields(room_code = %self.code))]
+    pub fn handle_timer(&mut self, timer: &ScheduledTimer) -> RoomResponse {
+        if self.timers.get(&timer.kind) != Some(&timer.id) {
+            return RoomResponse::new();
         }
+        self.timers.remove(&timer.kind);
 
-        for msg in response.messages_to_players {
-            for player in &self.players {
-                let _ = player.sender.send(msg.clone()).await;
+        match timer.kind {
+            TimerKind::AutoReady => {
+                if self.state != GameState::QuestionReading || self.awaiting_acks {
+                    return RoomResponse::new();
+                }
+                tracing::debug!("Readying the question for the host");
+                self.handle_host_ready()
             }
-        }
-
-        for (player_id, msg) in response.messages_to_specific {
-            if let Some(player) = self.players.iter().find(|p| p.player.pid == player_id) {
-                let _ = player.sender.send(msg).await;
+            TimerKind::OpenBuzzing => {
+                if self.state != GameState::QuestionReading || !self.awaiting_acks {
+                    return RoomResponse::new();
+                }
+                let missing: Vec<PlayerId> = self
+                    .connected_players()
+                    .map(|p| p.player.pid)
+                    .filter(|pid| !self.ready_acks.contains(pid))
+                    .collect();
+                tracing::info!(?missing, "Opening buzzing without every question ack");
+                self.open_buzzing()
+            }
+            TimerKind::AnswerTimeout => {
+                let (GameState::Answer, Some(pid)) = (&self.state, self.current_buzzer) else {
+                    return RoomResponse::new();
+                };
+                tracing::info!(player_id = pid, "Host didn't rule in time");
+                let timeout = RoomResponse::broadcast_state(WsMsg::AnswerTimeout { pid });
+                let ruling = match self.settings.answer_timeout_action {
+                    AnswerTimeoutAction::Incorrect => self.handle_host_checked(false),
+                    AnswerTimeoutAction::Skip => self.handle_host_skip(),
+                };
+                timeout.merge(ruling)
+            }
+            TimerKind::HostGrace => {
+                let playing = !matches!(
+                    self.state,
+                    GameState::Start | GameState::GameEnd | GameState::Paused
+                );
+                if !self.host_away || !playing {
+                    return RoomResponse::new();
+                }
+                tracing::info!(state = ?self.state, "Host didn't return, pausing game");
+                self.paused_from = Some(std::mem::replace(&mut self.state, GameState::Paused));
+                self.broadcast_game_state()
+                    .merge(self.build_all_player_states())
+                    .merge(RoomResponse::to_players_and_observers(WsMsg::Notice {
+                        message: "The host lost connection. The game is paused until they return"
+                            .to_string(),
+                    }))
+            }
+            TimerKind::BuzzWindow => {
+                if self.state == GameState::Paused {
+                    // Keep the held buzzes; resuming starts the window again
+                    return RoomResponse::new();
+                }
+                let held = std::mem::take(&mut self.held_buzzes);
+                if self.state != GameState::WaitingForBuzz {
+                    return RoomResponse::new();
+                }
+                // Players who left or were ruled on meanwhile can't win
+                let eligible: Vec<HeldBuzz> = held
+                    .into_iter()
+                    .filter(|(pid, _, _)| {
+                        self.players
+                            .iter()
+                            .any(|p| p.player.pid == *pid && !p.player.buzzed)
+                    })
+                    .collect();
+                match earliest_buzz(&eligible) {
+                    Some(winner) => {
+                        tracing::debug!(held = eligible.len(), winner, "Buzz window closed");
+                        self.accept_buzz(winner)
+                    }
+                    None => RoomResponse::new(),
+                }
+            }
+            TimerKind::StealWindow => {
+                if self.state != GameState::WaitingForBuzz {
+                    return RoomResponse::new();
+                }
+                let Some(question) = self.current_question.and_then(|(cat_idx, q_idx)| {
+                    self.categories
+                        .get_mut(cat_idx)
+                        .and_then(|cat| cat.questions.get_mut(q_idx))
+                }) else {
+                    return RoomResponse::new();
+                };
+                tracing::info!("Nobody stole, revealing answer");
+                question.answered = true;
+                self.set_state(GameState::AnswerReveal);
+                self.broadcast_game_state()
+                    .merge(self.build_all_player_states())
+                    .merge(self.build_answer_revealed())
+            }
+            TimerKind::EarlyBuzzLockout => {
+                let released: Vec<PlayerId> = self.early_buzz.drain().collect();
+                if self.state != GameState::WaitingForBuzz {
+                    return RoomResponse::new();
+                }
+                released
+                    .into_iter()
+                    .fold(RoomResponse::new(), |response, pid| {
+                        response.merge(self.build_player_state_delta(pid))
+                    })
             }
         }
-
-        Ok(())
     }
 
-    fn has_remaining_questions(&self) -> bool {
-        self.categories
-            .iter()
-            .any(|cat| cat.questions.iter().any(|q| !q.answered))
+    /// `PlayerState` for a single player whose buzz/score changed.
+    fn build_player_state_delta(&self, player_id: PlayerId) -> RoomResponse {
+        self.build_player_state_msg(player_id)
+            .map(|msg| RoomResponse::to_player(player_id, msg))
+            .unwrap_or_default()
     }
-}
 
-#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Default)]
-#[serde(rename_all = "camelCase")]
-pub enum GameState {
-    #[default]
-    Start,
-    Selection,
-    QuestionReading,
-    Answer,
-    WaitingForBuzz,
-    AnswerReveal,
-    GameEnd,
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_winner_determined_on_game_end() {
-        let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Winner");
-        add_test_player(&mut room, 2, "Loser");
+    /// Whether a host command was sent for an earlier turn than this one.
+    /// That's most likely a double click that already took effect, so the
+    /// caller resends the state for the host to catch up.
+    fn is_stale_turn(&self, turn_token: Option<u64>, command: &str) -> bool {
+        let stale = turn_token.is_some_and(|token| token != self.turn_token);
+        if stale {
+            tracing::debug!(
+                ?turn_token,
+                current = self.turn_token,
+                command,
+                "Ignoring stale host command"
+            );
+        }
+        stale
+    }
 
-        room.players[0].player.score = 1000;
-        room.players[1].player.score = 500;
+    /// Every change of state moves the game to a new turn, even back to the
+    /// same state, so commands sent for an earlier turn can be told apart.
+    fn set_state(&mut self, state: GameState) {
+        self.state = state;
+        self.turn_token += 1;
+    }
 
-        room.state = GameState::Answer;
-        room.current_question = Some((0, 1));
-        room.current_buzzer = Some(1);
-        room.categories[0].questions[0].answered = true;
+    fn handle_host_checked(&mut self, correct: bool) -> RoomResponse {
+        if self.state == GameState::Answer && self.current_buzzer.is_none() {
+            // Nobody to rule on, so the ruling would do nothing and the game
+            // would hang here; reopen buzzing instead
+            tracing::error!(
+                current_question = ?self.current_question,
+                "In Answer with no current buzzer, reopening buzzing"
+            );
+            self.cancel_timer(TimerKind::AnswerTimeout);
+            self.set_state(GameState::WaitingForBuzz);
+            return RoomResponse::to_host(WsMsg::Notice {
+                message: "Nobody had buzzed in, so buzzing is open again".to_string(),
+            })
+            .merge(self.broadcast_game_state())
+            .merge(self.build_all_player_states());
+        }
+        self.rule_on_answer(self.current_buzzer, correct)
+    }
 
-        room.handle_message(&WsMsg::HostChecked { correct: true }, None);
+    /// Credit the question to someone who answered out loud without buzzing
+    /// (or dock them for a wrong answer), then carry on as `HostChecked` would.
+    fn handle_award_question(&mut self, player_id: PlayerId, correct: bool) -> RoomResponse {
+        // In a poll, awarding grades the player's answer, or regrades it
+        if self.state == GameState::Polling || self.poll_graded.contains_key(&player_id) {
+            return self.handle_poll_checked(player_id, correct);
+        }
+        let active = matches!(
+            self.state,
+            GameState::QuestionReading | GameState::WaitingForBuzz | GameState::Answer
+        ) && self.current_question_mut().is_some_and(|q| !q.answered);
+        let reason = if !active {
+            Some("No question is in play")
+        } else if !self.players.iter().any(|p| p.player.pid == player_id) {
+            Some("That player is not in the room")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            tracing::warn!(player_id, reason, "Rejecting AwardQuestion");
+            return RoomResponse::to_host(WsMsg::Notice {
+                message: reason.to_string(),
+            });
+        }
 
-        assert_eq!(room.state, GameState::AnswerReveal);
+        tracing::info!(player_id, correct, "Host awarded question");
+        self.cancel_timer(TimerKind::OpenBuzzing);
+        self.cancel_timer(TimerKind::BuzzWindow);
+        self.cancel_timer(TimerKind::StealWindow);
+        self.held_buzzes.clear();
+        self.rule_on_answer(Some(player_id), correct)
+    }
 
-        room.handle_message(&WsMsg::HostContinue {}, None);
+    /// Score `answerer` for the current question and move the game on: to
+    /// the reveal when it's answered, or back to buzzing after a miss.
+    fn rule_on_answer(&mut self, answerer: Option<PlayerId>, correct: bool) -> RoomResponse {
+        let Some((cat_idx, q_idx)) = self.current_question else {
+            return RoomResponse::new();
+        };
+        // Before the clocks stop, so undo can restart them
+        self.save_snapshot();
+        self.cancel_timer(TimerKind::AnswerTimeout);
+
+        // An awarded player has had their turn just as if they'd buzzed
+        self.current_buzzer = answerer;
+        if let Some(player) =
+            answerer.and_then(|pid| self.players.iter_mut().find(|p| p.player.pid == pid))
+        {
+            player.player.buzzed = true;
+        }
+        if let Some(pid) = answerer
+            && let Some(stats) = self.current_question_stats()
+        {
+            stats.rulings.push(Ruling { pid, correct });
+        }
 
-        assert_eq!(room.state, GameState::GameEnd);
-        assert_eq!(room.winner, Some(1), "Player 1 should be winner");
-    }
+        let question = self
+            .categories
+            .get_mut(cat_idx)
+            .and_then(|cat| cat.questions.get_mut(q_idx));
 
-    #[test]
-    fn test_tie_results_in_no_winner() {
-        let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Player1");
-        add_test_player(&mut room, 2, "Player2");
+        let question_value = question
+            .as_ref()
+            .map(|q| scaled_value(q.value, self.value_multiplier));
+        let Some(question) = question else {
+            return RoomResponse::new();
+        };
 
-        room.players[0].player.score = 1000;
-        room.players[1].player.score = 1000;
+        let Some(question_value) = question_value else {
+            return RoomResponse::new();
+        };
 
-        room.determine_winner();
+        let buzzer = answerer;
+        if let Some(buzzer_id) = buzzer
+            && let Some(player) = self.players.iter_mut().find(|p| p.player.pid == buzzer_id)
+        {
+            let score = self
+                .scoring
+                .apply(player.player.score, question_value, correct);
+            player.player.score = self.settings.floor_score(player.player.score, score);
+            if correct {
+                player.player.streak += 1;
+                let bonus = self.settings.streak_bonus_for(player.player.streak);
+                player.player.score = player.player.score.saturating_add(bonus);
+            } else {
+                player.player.streak = 0;
+            }
+        }
 
-        assert_eq!(room.winner, None, "Tie should result in no winner");
-    }
+        let any_can_buzz = self.players.iter().any(|p| !p.player.buzzed);
 
-    #[test]
-    fn test_manual_end_game_determines_winner() {
-        let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Winner");
-        add_test_player(&mut room, 2, "Loser");
+        if correct {
+            question.answered = true;
+            self.set_state(GameState::AnswerReveal);
+        } else if any_can_buzz {
+            self.current_buzzer = None;
+            self.set_state(GameState::WaitingForBuzz);
+        } else {
+            question.answered = true;
+            self.set_state(GameState::AnswerReveal);
+        }
 
-        room.players[0].player.score = 800;
-        room.players[1].player.score = 200;
+        if let Some(target) = self.settings.target_score
+            && self.players.iter().any(|p| p.player.score >= target)
+        {
+            tracing::info!(target, "Target score reached");
+            self.determine_winner();
+            self.set_state(GameState::GameEnd);
+            return self
+                .broadcast_game_state()
+                .merge(self.build_all_player_states());
+        }
 
-        room.handle_message(&WsMsg::EndGame {}, None);
+        // Reopening buzzing changes everyone's can_buzz; otherwise only the
+        // buzzer's score moved
+        let player_states = match buzzer {
+            Some(buzzer_id) if self.state != GameState::WaitingForBuzz => {
+                self.build_player_state_delta(buzzer_id)
+            }
+            _ => self.build_all_player_states(),
+        };
 
-        assert_eq!(room.state, GameState::GameEnd);
-        assert_eq!(room.winner, Some(1));
+        let response = self.broadcast_game_state().merge(player_states);
+        if self.state == GameState::AnswerReveal {
+            response.merge(self.build_answer_revealed())
+        } else {
+            response.merge(self.open_steal_window())
+        }
     }
 
-    #[test]
-    fn test_negative_scores_winner() {
-        let mut room = create_test_room();
-        add_test_player(&mut room, 1, "LeastBad");
-        add_test_player(&mut room, 2, "ReallyBad");
+    fn handle_host_skip(&mut self) -> RoomResponse {
+        let Some((cat_idx, q_idx)) = self.current_question else {
+            return RoomResponse::new();
+        };
+        self.save_snapshot();
+        self.cancel_timer(TimerKind::AnswerTimeout);
+        self.cancel_timer(TimerKind::StealWindow);
 
-        room.players[0].player.score = -200;
-        room.players[1].player.score = -1000;
+        tracing::info!(
+            category_index = cat_idx,
+            question_index = q_idx,
+            "Host skipped question"
+        );
 
-        room.determine_winner();
+        // Mark question as answered
+        if let Some(question) = self
+            .categories
+            .get_mut(cat_idx)
+            .and_then(|cat| cat.questions.get_mut(q_idx))
+        {
+            question.answered = true;
+        }
 
-        assert_eq!(
-            room.winner,
-            Some(1),
-            "Player with higher negative score wins"
-        );
+        self.set_state(GameState::AnswerReveal);
+
+        self.broadcast_game_state()
+            .merge(self.build_all_player_states())
+            .merge(self.build_answer_revealed())
     }
 
-    fn create_test_room() -> Room {
-        let mut room = Room::new("TEST".to_string(), "token".to_string());
+    /// Throw out a question nobody could fairly answer: it counts as played
+    /// but scores go back to what they were when it was picked.
+    fn handle_void_question(&mut self) -> RoomResponse {
+        let active = matches!(
+            self.state,
+            GameState::QuestionReading
+                | GameState::WaitingForBuzz
+                | GameState::Answer
+                | GameState::Polling
+        );
+        let Some((cat_idx, q_idx)) = self.current_question.filter(|_| active) else {
+            return RoomResponse::to_host(WsMsg::Notice {
+                message: "No question is in play".to_string(),
+            });
+        };
+        self.save_snapshot();
+        tracing::info!(
+            category_index = cat_idx,
+            question_index = q_idx,
+            "Host voided question"
+        );
 
-        room.categories = vec![Category {
-            title: "Test Category".to_string(),
-            questions: vec![
-                Question {
-                    question: "What is 2+2?".to_string(),
-                    answer: "4".to_string(),
-                    value: 200,
-                    answered: false,
-                },
-                Question {
-                    question: "What is 6?".to_string(),
-                    answer: "6".to_string(),
-                    value: 400,
-                    answered: false,
-                },
-            ],
-        }];
+        // Start scores stay, so voiding again after an undo still works
+        self.reset_question_state();
+        for (pid, score) in self.question_start_scores.clone() {
+            if let Some(player) = self.players.iter_mut().find(|p| p.player.pid == pid) {
+                player.player.score = score;
+            }
+        }
+        if let Some(question) = self.current_question_mut() {
+            question.answered = true;
+        }
+        self.set_state(GameState::AnswerReveal);
 
-        room
+        RoomResponse::broadcast_state(WsMsg::QuestionVoided {
+            category_index: cat_idx,
+            question_index: q_idx,
+        })
+        .merge(self.broadcast_game_state())
+        .merge(self.build_all_player_states())
+        .merge(self.build_answer_revealed())
     }
 
-    fn add_test_player(room: &mut Room, pid: u32, name: &str) {
-        use tokio_mpmc::channel;
-        let (tx, _rx) = channel(10);
+    fn handle_submit_answer(&mut self, player_id: PlayerId, text: &str) -> RoomResponse {
+        if self.state != GameState::Polling {
+            return RoomResponse::new();
+        }
+        let text = text.trim();
+        if text.is_empty() {
+            return RoomResponse::new();
+        }
+        let text: String = text.chars().take(MAX_POLL_ANSWER_LEN).collect();
+        let auto_correct = self.settings.auto_grade
+            && self
+                .current_question_mut()
+                .is_some_and(|q| answers_match(&text, &q.answer));
+
+        let pending = self
+            .poll_answers
+            .iter()
+            .position(|(pid, _)| *pid == player_id);
+        let Some(entry) = self.players.iter_mut().find(|p| p.player.pid == player_id) else {
+            return RoomResponse::new();
+        };
+        match pending {
+            // Players may change their answer until the host grades it
+            Some(idx) => self.poll_answers[idx].1 = text,
+            None if entry.player.buzzed => {
+                tracing::debug!(player_id, "Poll answer already graded");
+                return RoomResponse::new();
+            }
+            None => {
+                entry.player.buzzed = true;
+                self.poll_answers.push((player_id, text));
+            }
+        }
 
-        let player = PlayerEntry::new(
-            Player::new(pid, name.to_string(), 0, false, "token".to_string()),
-            tx,
-        );
-        room.players.push(player);
+        tracing::debug!(player_id, "Poll answer submitted");
+        if auto_correct {
+            tracing::debug!(player_id, "Poll answer auto-graded correct");
+            return self.handle_poll_checked(player_id, true);
+        }
+        self.poll_results()
+            .merge(self.build_player_state_delta(player_id))
     }
 
-    #[test]
-    fn test_game_state_transitions() {
-        struct TestCase {
-            name: &'static str,
-            initial_state: GameState,
-            setup: fn(&mut Room),
+    /// Grade a poll answer, or overrule one already graded (say, auto-graded)
+    /// until the next question is picked.
+    fn handle_poll_checked(&mut self, player_id: PlayerId, correct: bool) -> RoomResponse {
+        let previous = self.poll_graded.get(&player_id).copied();
+        if self.state != GameState::Polling && previous.is_none() {
+            return RoomResponse::new();
+        }
+        let pending = self
+            .poll_answers
+            .iter()
+            .position(|(pid, _)| *pid == player_id);
+        let reason = match (pending, previous) {
+            (None, None) => Some("That player has no answer to grade"),
+            (None, Some((was_correct, _))) if was_correct == correct => {
+                Some("That answer is already graded that way")
+            }
+            _ => None,
+        };
+        if let Some(reason) = reason {
+            return RoomResponse::to_host(WsMsg::Notice {
+                message: reason.to_string(),
+            });
+        }
+        let multiplier = self.value_multiplier;
+        let Some(question_value) = self
+            .current_question_mut()
+            .map(|q| scaled_value(q.value, multiplier))
+        else {
+            return RoomResponse::new();
+        };
+        self.save_snapshot();
+        if let Some(idx) = pending {
+            self.poll_answers.remove(idx);
+        }
+        if let Some(player) = self.players.iter_mut().find(|p| p.player.pid == player_id) {
+            // Take back the earlier ruling before applying the new one
+            let base = player.player.score - previous.map_or(0, |(_, delta)| delta);
+            let score = self.scoring.apply(base, question_value, correct);
+            let score = self.settings.floor_score(base, score);
+            self.poll_graded.insert(player_id, (correct, score - base));
+            player.player.score = score;
+        }
+        if let Some(stats) = self.current_question_stats() {
+            match stats.rulings.iter_mut().rfind(|r| r.pid == player_id) {
+                Some(ruling) if previous.is_some() => ruling.correct = correct,
+                _ => stats.rulings.push(Ruling {
+                    pid: player_id,
+                    correct,
+                }),
+            }
+        }
+        if previous.is_some() {
+            tracing::info!(player_id, correct, "Host overruled poll grade");
+        } else {
+            tracing::info!(player_id, correct, "Host graded poll answer");
+        }
+
+        // Reveal once every connected player's answer is graded
+        let everyone_graded = self.state == GameState::Polling
+            && self.poll_answers.is_empty()
+            && self.connected_players().all(|p| p.player.buzzed);
+        if everyone_graded && let Some(question) = self.current_question_mut() {
+            question.answered = true;
+            self.set_state(GameState::AnswerReveal);
+        }
+
+        let response = self
+            .broadcast_game_state()
+            .merge(self.build_player_state_delta(player_id))
+            .merge(self.poll_results());
+        if self.state == GameState::AnswerReveal {
+            response.merge(self.build_answer_revealed())
+        } else {
+            response
+        }
+    }
+
+    fn poll_results(&self) -> RoomResponse {
+        RoomResponse::to_host(WsMsg::PollResults {
+            answers: self.poll_answers.clone(),
+        })
+    }
+
+    fn current_question_mut(&mut self) -> Option<&mut Question> {
+        let (cat_idx, q_idx) = self.current_question?;
+        self.categories
+            .get_mut(cat_idx)
+            .and_then(|cat| cat.questions.get_mut(q_idx))
+    }
+
+    fn save_snapshot(&mut self) {
+        let answered = self.current_question_mut().is_some_and(|q| q.answered);
+        self.history.push(RoomSnapshot {
+            state: self.state.clone(),
+            scores: self
+                .players
+                .iter()
+                .map(|p| (p.player.pid, p.player.score))
+                .collect(),
+            streaks: self
+                .players
+                .iter()
+                .map(|p| (p.player.pid, p.player.streak))
+                .collect(),
+            buzzed: self
+                .players
+                .iter()
+                .filter(|p| p.player.buzzed)
+                .map(|p| p.player.pid)
+                .collect(),
+            current_question: self.current_question,
+            current_buzzer: self.current_buzzer,
+            winner: self.winner,
+            wins: self.wins.clone(),
+            question_stats: self.question_stats.clone(),
+            answered,
+            buzz_queue: self.buzz_queue.clone(),
+            held_buzzes: self.held_buzzes.clone(),
+            poll_answers: self.poll_answers.clone(),
+            poll_graded: self.poll_graded.clone(),
+            timers: QUESTION_TIMERS
+                .into_iter()
+                .filter(|kind| self.timers.contains_key(kind))
+                .collect(),
+        });
+        if self.history.len() > UNDO_DEPTH {
+            self.history.remove(0);
+        }
+    }
+
+    fn handle_undo(&mut self) -> RoomResponse {
+        let Some(snapshot) = self.history.pop() else {
+            return RoomResponse::to_host(WsMsg::Notice {
+                message: "Nothing to undo".to_string(),
+            });
+        };
+        tracing::info!(state = ?snapshot.state, "Host undid last ruling");
+
+        self.set_state(snapshot.state);
+        for (pid, score) in snapshot.scores {
+            if let Some(player) = self.players.iter_mut().find(|p| p.player.pid == pid) {
+                player.player.score = score;
+            }
+        }
+        for (pid, streak) in snapshot.streaks {
+            if let Some(player) = self.players.iter_mut().find(|p| p.player.pid == pid) {
+                player.player.streak = streak;
+            }
+        }
+        for player in &mut self.players {
+            player.player.buzzed = snapshot.buzzed.contains(&player.player.pid);
+        }
+        self.current_question = snapshot.current_question;
+        self.current_buzzer = snapshot.current_buzzer;
+        self.winner = snapshot.winner;
+        self.wins = snapshot.wins;
+        self.question_stats = snapshot.question_stats;
+        if let Some(question) = self.current_question_mut() {
+            question.answered = snapshot.answered;
+        }
+        self.buzz_queue = snapshot.buzz_queue;
+        self.held_buzzes = snapshot.held_buzzes;
+        self.poll_answers = snapshot.poll_answers;
+        self.poll_graded = snapshot.poll_graded;
+
+        for kind in QUESTION_TIMERS {
+            self.cancel_timer(kind);
+        }
+        let timers = snapshot
+            .timers
+            .into_iter()
+            .fold(RoomResponse::new(), |response, kind| {
+                response.merge(self.restart_timer(kind))
+            });
+        self.broadcast_game_state()
+            .merge(self.build_all_player_states())
+            .merge(timers)
+    }
+
+    /// Start a timer of `kind` over with its full duration, if the room's
+    /// settings still call for it.
+    fn restart_timer(&mut self, kind: TimerKind) -> RoomResponse {
+        let after = match kind {
+            TimerKind::AnswerTimeout => return self.schedule_answer_timeout(),
+            TimerKind::StealWindow => return self.open_steal_window(),
+            TimerKind::AutoReady => self.settings.auto_ready_delay(),
+            TimerKind::OpenBuzzing => Some(self.settings.question_load_timeout()),
+            TimerKind::EarlyBuzzLockout => Some(EARLY_BUZZ_LOCKOUT),
+            TimerKind::BuzzWindow => self.settings.buzz_window(),
+            TimerKind::HostGrace => Some(self.settings.host_grace()),
+        };
+        match after {
+            Some(after) => self.schedule_timer(kind, after),
+            None => RoomResponse::new(),
+        }
+    }
+
+    fn handle_edit_question(
+        &mut self,
+        category_index: usize,
+        question_index: usize,
+        text: Option<&str>,
+        answer: Option<&str>,
+        value: Option<u32>,
+        force: bool,
+    ) -> RoomResponse {
+        let Some(question) = self
+            .categories
+            .get_mut(category_index)
+            .and_then(|cat| cat.questions.get_mut(question_index))
+        else {
+            return RoomResponse::to_host(WsMsg::InvalidSelection {
+                category_index,
+                question_index,
+                reason: "That question does not exist".to_string(),
+            });
+        };
+        if question.answered && !force {
+            return RoomResponse::to_host(WsMsg::InvalidSelection {
+                category_index,
+                question_index,
+                reason: "That question was already played; set force to edit it".to_string(),
+            });
+        }
+        if [text, answer]
+            .into_iter()
+            .flatten()
+            .any(|t| t.chars().count() > self.max_text_chars)
+        {
+            return RoomResponse::to_host(WsMsg::InvalidSelection {
+                category_index,
+                question_index,
+                reason: format!(
+                    "Questions and answers can be at most {} characters",
+                    self.max_text_chars
+                ),
+            });
+        }
+
+        tracing::info!(category_index, question_index, "Host edited question");
+        if let Some(text) = text {
+            question.question = text.to_string();
+        }
+        if let Some(answer) = answer {
+            question.answer = answer.to_string();
+        }
+        if let Some(value) = value {
+            question.value = value;
+        }
+        self.broadcast_game_state()
+    }
+
+    fn handle_restore_board_state(&mut self, answered: &[(usize, usize)]) -> RoomResponse {
+        let mut restored = 0;
+        for &(category_index, question_index) in answered {
+            match self
+                .categories
+                .get_mut(category_index)
+                .and_then(|cat| cat.questions.get_mut(question_index))
+            {
+                Some(question) => {
+                    question.answered = true;
+                    restored += 1;
+                }
+                None => tracing::warn!(
+                    category_index,
+                    question_index,
+                    "Ignoring out-of-range question in restored board state"
+                ),
+            }
+        }
+
+        tracing::info!(restored, "Host restored board state");
+        self.broadcast_game_state()
+    }
+
+    fn handle_host_continue(&mut self) -> RoomResponse {
+        tracing::info!("Host continuing from answer reveal");
+
+        // Rulings on a finished question can't be undone from the board
+        self.history.clear();
+        // Clear current question and buzzer
+        self.current_question = None;
+        self.current_buzzer = None;
+        self.raised_hands.clear();
+
+        for player in &mut self.players {
+            player.player.buzzed = false;
+        }
+
+        // Transition to Selection or GameEnd
+        let mut response = RoomResponse::new();
+        let next = if self.has_remaining_questions() {
+            GameState::Selection
+        } else if self.has_next_board() {
+            // The host decides when the next round starts
+            response = RoomResponse::to_host(WsMsg::Notice {
+                message: "Board cleared. Send NextBoard to start the next round".to_string(),
+            });
+            GameState::Selection
+        } else {
+            self.determine_winner();
+            GameState::GameEnd
+        };
+        self.set_state(next);
+
+        self.broadcast_game_state()
+            .merge(self.build_all_player_states())
+            .merge(response)
+    }
+
+    /// Replay with the same people: players keep their id, name and token,
+    /// but scores go back to `starting_score` and the board is unplayed.
+    fn handle_reset_game(&mut self) -> RoomResponse {
+        tracing::info!("Host reset the game");
+        if let Some(first) = self.boards.first() {
+            self.categories = first.clone();
+        }
+        self.current_board = 0;
+        self.value_multiplier = 1;
+        for question in self
+            .categories
+            .iter_mut()
+            .flat_map(|cat| &mut cat.questions)
+        {
+            question.answered = false;
+        }
+        for player in &mut self.players {
+            player.player.score = self.settings.starting_score;
+            player.player.buzzed = false;
+            player.player.streak = 0;
+        }
+
+        self.current_question = None;
+        self.winner = None;
+        self.paused_from = None;
+        self.reset_question_state();
+        self.question_start_scores.clear();
+        self.question_stats.clear();
+        self.history.clear();
+
+        self.set_state(GameState::Start);
+        self.broadcast_game_state()
+            .merge(self.build_all_player_states())
+    }
+
+    fn handle_host_choice(&mut self, category_index: usize, question_index: usize) -> RoomResponse {
+        let question = self
+            .categories
+            .get(category_index)
+            .and_then(|cat| cat.questions.get(question_index));
+        let rejection = match question {
+            None => Some("That question does not exist"),
+            Some(q) if q.answered => Some("That question was already played"),
+            Some(_) => None,
+        };
+        if let Some(reason) = rejection {
+            tracing::warn!(category_index, question_index, reason, "Invalid selection");
+            return RoomResponse::to_host(WsMsg::InvalidSelection {
+                category_index,
+                question_index,
+                reason: reason.to_string(),
+            });
+        }
+
+        tracing::debug!(category_index, question_index, "Host selected question");
+        self.history.clear();
+        self.current_question = Some((category_index, question_index));
+        self.reset_question_state();
+        self.question_start_scores = self
+            .players
+            .iter()
+            .map(|p| (p.player.pid, p.player.score))
+            .collect();
+        self.set_state(GameState::QuestionReading);
+        let auto_ready = match self.settings.auto_ready_delay() {
+            Some(delay) => self.schedule_timer(TimerKind::AutoReady, delay),
+            None => RoomResponse::new(),
+        };
+        self.broadcast_game_state()
+            .merge(self.build_all_player_states())
+            .merge(self.answer_hint())
+            .merge(auto_ready)
+    }
+
+    /// Play a random unanswered question, for games where the board picks.
+    fn handle_random_question(&mut self, seed: Option<u64>) -> RoomResponse {
+        if self.state != GameState::Selection {
+            return RoomResponse::to_host(WsMsg::Notice {
+                message: "A random question can only be picked during selection".to_string(),
+            });
+        }
+        let unanswered: Vec<(usize, usize)> = self
+            .categories
+            .iter()
+            .enumerate()
+            .flat_map(|(c, cat)| {
+                cat.questions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, q)| !q.answered)
+                    .map(move |(q, _)| (c, q))
+            })
+            .collect();
+
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let Some(&(category_index, question_index)) = unanswered.choose(&mut rng) else {
+            if self.has_next_board() {
+                tracing::info!("Board cleared, picking from the next one");
+                return self
+                    .handle_next_board()
+                    .merge(self.handle_random_question(seed));
+            }
+            tracing::info!("No questions left to pick");
+            return self.end_game();
+        };
+        tracing::info!(
+            ?seed,
+            category_index,
+            question_index,
+            "Picked a random question"
+        );
+        self.handle_host_choice(category_index, question_index)
+    }
+
+    fn handle_shuffle_board(&mut self, seed: Option<u64>, shuffle_questions: bool) -> RoomResponse {
+        if self.state != GameState::Start {
+            return RoomResponse::to_host(WsMsg::Notice {
+                message: "The board can only be shuffled before the game starts".to_string(),
+            });
+        }
+        tracing::info!(?seed, shuffle_questions, "Host shuffled the board");
+
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        self.categories.shuffle(&mut rng);
+        if shuffle_questions {
+            for category in &mut self.categories {
+                category.questions.shuffle(&mut rng);
+            }
+        }
+        // Keep the shuffle when the game is reset
+        if let Some(first) = self.boards.first_mut() {
+            *first = self.categories.clone();
+        }
+
+        self.broadcast_game_state()
+    }
+
+    fn handle_next_board(&mut self) -> RoomResponse {
+        if !self.has_next_board() {
+            tracing::info!("No boards left, ending game");
+            return self.end_game();
+        }
+
+        self.current_board += 1;
+        tracing::info!(board = self.current_board, "Advancing to next board");
+        self.categories = self.boards[self.current_board].clone();
+        for question in self
+            .categories
+            .iter_mut()
+            .flat_map(|cat| &mut cat.questions)
+        {
+            question.answered = false;
+        }
+
+        self.current_question = None;
+        self.reset_question_state();
+        self.question_start_scores.clear();
+        self.history.clear();
+        self.set_state(GameState::Selection);
+        self.broadcast_game_state()
+            .merge(self.build_all_player_states())
+    }
+
+    fn handle_buzz(&mut self, sender_id: Option<PlayerId>) -> RoomResponse {
+        if let Some(player_id) = sender_id
+            && let Some(remaining) = self
+                .players
+                .iter()
+                .find(|p| p.player.pid == player_id)
+                .and_then(|p| p.bench_remaining())
+        {
+            tracing::debug!(player_id, ?remaining, "Benched player tried to buzz");
+            return RoomResponse::to_player(
+                player_id,
+                WsMsg::Notice {
+                    message: format!(
+                        "Your buzzer is benched for {} more seconds",
+                        remaining.as_secs() + 1
+                    ),
+                },
+            );
+        }
+
+        if self.state == GameState::QuestionReading
+            && let Some(player_id) = sender_id
+            && self.players.iter().any(|p| p.player.pid == player_id)
+        {
+            tracing::debug!(player_id, "Player buzzed before buzzing opened");
+            self.early_buzz.insert(player_id);
+            return RoomResponse::new();
+        }
+
+        if let Some(player_id) = sender_id
+            && self.in_early_buzz_lockout(player_id)
+        {
+            tracing::debug!(player_id, "Early buzzer still locked out");
+            return self.build_player_state_delta(player_id);
+        }
+
+        let Some(player_id) = sender_id else {
+            return RoomResponse::new();
+        };
+        let Some(player_entry) = self.players.iter().find(|p| p.player.pid == player_id) else {
+            return RoomResponse::new();
+        };
+        if self.state != GameState::WaitingForBuzz || player_entry.player.buzzed {
+            // Tell the player where they stand so their buzzer doesn't just
+            // look broken
+            let reason = if player_entry.player.buzzed {
+                "already buzzed on this question"
+            } else {
+                "buzzing is not open"
+            };
+            tracing::debug!(player_id, state = ?self.state, reason, "Rejected buzz");
+            return self.build_player_state_delta(player_id);
+        }
+
+        let Some(window) = self.settings.buzz_window() else {
+            return self.accept_buzz(player_id);
+        };
+        if self.held_buzzes.iter().any(|(pid, _, _)| *pid == player_id) {
+            return RoomResponse::new();
+        }
+        let latency = player_entry.latency().unwrap_or(0).into();
+        tracing::debug!(player_id, latency, "Holding buzz for the buzz window");
+        self.held_buzzes
+            .push((player_id, PlayerEntry::time_ms(), latency));
+        // The first buzz starts the window
+        if self.held_buzzes.len() == 1 {
+            return self.schedule_timer(TimerKind::BuzzWindow, window);
+        }
+        RoomResponse::new()
+    }
+
+    /// In double buzz rooms, whether this tap confirms one just before it.
+    /// A lone tap is remembered and otherwise ignored.
+    fn confirm_double_buzz(&mut self, player_id: PlayerId) -> bool {
+        let Some(window) = self.settings.double_buzz_window() else {
+            return true;
+        };
+        let now = Instant::now();
+        match self.first_taps.remove(&player_id) {
+            Some(first) if now.duration_since(first) <= window => true,
+            _ => {
+                tracing::debug!(player_id, "Waiting for a second tap to confirm buzz");
+                self.first_taps.insert(player_id, now);
+                false
+            }
+        }
+    }
+
+    /// Give `player_id` the floor.
+    fn accept_buzz(&mut self, player_id: PlayerId) -> RoomResponse {
+        let Some(player_entry) = self.players.iter_mut().find(|p| p.player.pid == player_id) else {
+            return RoomResponse::new();
+        };
+        tracing::info!(
+            player_id,
+            player_name = %player_entry.player.name,
+            "Player buzzed in"
+        );
+        player_entry.player.buzzed = true;
+        let buzzed_msg = WsMsg::Buzzed {
+            pid: player_id,
+            name: player_entry.player.name.clone(),
+        };
+        let ms_to_buzz = self
+            .first_opened_at
+            .map(|opened| opened.elapsed().as_millis() as u64);
+        if let Some(stats) = self.current_question_stats() {
+            stats.buzzes.push(BuzzStat {
+                pid: player_id,
+                ms_to_buzz,
+            });
+        }
+        self.current_buzzer = Some(player_id);
+        self.set_state(GameState::Answer);
+
+        self.cancel_timer(TimerKind::StealWindow);
+
+        // Only the buzzer's own state changed; everyone else learns
+        // buzzing closed from the GameState broadcast
+        RoomResponse::to_host(buzzed_msg.clone())
+            .merge(RoomResponse::to_observers(buzzed_msg))
+            .merge(self.broadcast_game_state())
+            .merge(self.build_player_state_delta(player_id))
+            .merge(self.answer_hint())
+            .merge(self.schedule_answer_timeout())
+    }
+
+    /// Note the arrival of a buzz for the host's "who was second" view.
+    /// Sends the host the new order when a player buzzes for the first time.
+    fn record_buzz(&mut self, sender_id: Option<PlayerId>) -> RoomResponse {
+        let Some(player_id) = sender_id else {
+            return RoomResponse::new();
+        };
+        if self.current_question.is_none()
+            || !self.players.iter().any(|p| p.player.pid == player_id)
+        {
+            return RoomResponse::new();
+        }
+
+        let first_buzz = !self.buzz_queue.iter().any(|(pid, _)| *pid == player_id);
+        self.buzz_queue.push((player_id, PlayerEntry::time_ms()));
+        if !first_buzz {
+            return RoomResponse::new();
+        }
+        RoomResponse::to_host(WsMsg::BuzzOrder {
+            order: self.buzz_order(),
+        })
+    }
+
+    /// Players in the order they first buzzed on the current question.
+    pub fn buzz_order(&self) -> Vec<PlayerId> {
+        let mut order: Vec<PlayerId> = Vec::new();
+        for (pid, _) in &self.buzz_queue {
+            if !order.contains(pid) {
+                order.push(*pid);
+            }
+        }
+        order
+    }
+
+    fn handle_chat(&mut self, text: &str, sender_id: Option<PlayerId>) -> RoomResponse {
+        let text = text.trim();
+        if text.is_empty() {
+            return RoomResponse::new();
+        }
+        let text: String = text.chars().take(MAX_CHAT_LEN).collect();
+
+        let (pid, name) = match sender_id {
+            None => (0, "Host".to_string()),
+            Some(player_id) => {
+                let Some(entry) = self.players.iter_mut().find(|p| p.player.pid == player_id)
+                else {
+                    return RoomResponse::new();
+                };
+                if !entry.try_chat(CHAT_RATE_LIMIT, CHAT_RATE_WINDOW) {
+                    tracing::debug!(player_id, "Chat rate limited");
+                    return RoomResponse::to_player(
+                        player_id,
+                        WsMsg::Notice {
+                            message: "You're sending messages too quickly".to_string(),
+                        },
+                    );
+                }
+                (player_id, entry.player.name.clone())
+            }
+        };
+
+        let chat = WsMsg::ChatMessage {
+            pid,
+            name,
+            text,
+            timestamp: PlayerEntry::time_ms(),
+        };
+        // Spectators follow the chat too; displays leave it off the big screen
+        RoomResponse::broadcast_state(chat)
+    }
+
+    fn handle_rename(&mut self, name: &str, sender_id: Option<PlayerId>) -> RoomResponse {
+        let Some(player_id) = sender_id else {
+            return RoomResponse::new();
+        };
+
+        let cooldown = self.settings.rename_cooldown();
+        if let Some(remaining) = self
+            .players
+            .iter()
+            .find(|p| p.player.pid == player_id)
+            .and_then(|p| p.rename_cooldown_remaining(cooldown))
+        {
+            tracing::debug!(player_id, ?remaining, "Rename rate limited");
+            return RoomResponse::to_player(
+                player_id,
+                WsMsg::Notice {
+                    message: format!(
+                        "Please wait {} more seconds before renaming again",
+                        remaining.as_secs() + 1
+                    ),
+                },
+            );
+        }
+
+        let name = match self.validate_player_name(name, player_id) {
+            Ok(name) => name,
+            Err(reason) => {
+                tracing::debug!(player_id, %reason, "Rename rejected");
+                return RoomResponse::to_player(player_id, WsMsg::NameRejected { reason });
+            }
+        };
+
+        let Some(entry) = self.players.iter_mut().find(|p| p.player.pid == player_id) else {
+            return RoomResponse::new();
+        };
+
+        tracing::info!(
+            player_id,
+            old_name = %entry.player.name,
+            new_name = %name,
+            "Player renamed"
+        );
+        entry.player.name = name;
+        entry.last_renamed = Some(Instant::now());
+
+        let mut response = RoomResponse::to_host(self.build_player_list_msg());
+        if let Some(msg) = self.build_player_state_msg(player_id) {
+            response.messages_to_specific.push((player_id, msg));
+        }
+        response
+    }
+
+    /// A player quit on purpose. Unlike a dropped socket they're gone for
+    /// good, and the game mustn't be left waiting on them.
+    fn handle_leave(&mut self, player_id: PlayerId) -> RoomResponse {
+        let Some(idx) = self.players.iter().position(|p| p.player.pid == player_id) else {
+            return RoomResponse::new();
+        };
+        let entry = self.players.remove(idx);
+        tracing::info!(player_id, player_name = %entry.player.name, "Player left the room");
+
+        self.ready_acks.remove(&player_id);
+        self.early_buzz.remove(&player_id);
+        self.last_buzz_seq.remove(&player_id);
+        self.first_taps.remove(&player_id);
+        self.raised_hands.retain(|pid| *pid != player_id);
+        self.buzz_queue.retain(|(pid, _)| *pid != player_id);
+        self.held_buzzes.retain(|(pid, _, _)| *pid != player_id);
+        self.poll_answers.retain(|(pid, _)| *pid != player_id);
+        self.poll_graded.remove(&player_id);
+        self.question_start_scores
+            .retain(|(pid, _)| *pid != player_id);
+
+        let mut response = RoomResponse::to_host(self.build_player_list_msg());
+        if self.current_buzzer == Some(player_id) {
+            // Nobody is answering any more: reopen buzzing, or reveal the
+            // answer if no one else may buzz
+            self.cancel_timer(TimerKind::AnswerTimeout);
+            self.current_buzzer = None;
+            if self.players.iter().any(|p| !p.player.buzzed) {
+                self.set_state(GameState::WaitingForBuzz);
+            } else {
+                if let Some(question) = self.current_question_mut() {
+                    question.answered = true;
+                }
+                self.set_state(GameState::AnswerReveal);
+                response = response.merge(self.build_answer_revealed());
+            }
+        } else if self.awaiting_acks
+            && self.state == GameState::QuestionReading
+            && self.all_players_loaded()
+        {
+            return response.merge(self.open_buzzing());
+        }
+
+        response
+            .merge(self.broadcast_game_state())
+            .merge(self.build_all_player_states())
+    }
+
+    /// Check a requested display name, returning it trimmed or a reason it was rejected.
+    fn validate_player_name(&self, name: &str, player_id: PlayerId) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Name cannot be empty".to_string());
+        }
+        if name.chars().count() > MAX_NAME_LEN {
+            return Err(format!(
+                "Name cannot be longer than {MAX_NAME_LEN} characters"
+            ));
+        }
+
+        let lowered = name.to_lowercase();
+        if self
+            .players
+            .iter()
+            .any(|p| p.player.pid != player_id && p.player.name.to_lowercase() == lowered)
+        {
+            return Err(format!("Name \"{name}\" is already taken"));
+        }
+
+        Ok(name.to_string())
+    }
+
+    #[tracing::instrument(skip(self, msg), fields(room_code = %self.code))]
+    /// Handle a client message and deliver the resulting messages. Returns any
+    /// timers the caller must fire later through [`Room::fire_timer`].
+    pub async fn update(
+        &mut self,
+        msg: &WsMsg,
+        pid: Option<PlayerId>,
+    ) -> anyhow::Result<Vec<ScheduledTimer>> {
+        tracing::trace!(?msg, ?pid, "Processing message");
+
+        if self.settings.transcript {
+            self.transcript.record_command(pid, msg);
+        }
+        let response = self.handle_message(msg, pid);
+        Ok(self.dispatch(response).await)
+    }
+
+    /// Handle an elapsed timer and deliver the resulting messages.
+    pub async fn fire_timer(&mut self, timer: &ScheduledTimer) -> Vec<ScheduledTimer> {
+        let response = self.handle_timer(timer);
+        self.dispatch(response).await
+    }
+
+    /// The last host socket dropped: tell players the game is waiting on them.
+    pub async fn disconnect_host(&mut self) -> Vec<ScheduledTimer> {
+        tracing::info!("Host disconnected");
+        self.hosts.clear();
+        self.host_away = true;
+        let grace = self.settings.host_grace();
+        let response = RoomResponse::to_players_and_observers(WsMsg::HostDisconnected {})
+            .merge(self.schedule_timer(TimerKind::HostGrace, grace));
+        self.dispatch(response).await
+    }
+
+    /// Tell everyone the room is about to be cleaned up for inactivity.
+    pub async fn warn_expiry(&mut self, remaining: Duration) {
+        tracing::info!(?remaining, "Room expiring soon");
+        self.expiry_warned = true;
+        let warning = WsMsg::RoomExpiringSoon {
+            seconds_remaining: remaining.as_secs(),
+        };
+        self.dispatch(RoomResponse::broadcast_state(warning)).await;
+    }
+
+    /// Call once a returning host is registered again.
+    pub async fn reconnect_host(&mut self) -> Vec<ScheduledTimer> {
+        if !std::mem::take(&mut self.host_away) {
+            return Vec::new();
+        }
+        // Back within the grace period, so the game carries on untouched
+        self.cancel_timer(TimerKind::HostGrace);
+        tracing::info!("Host reconnected");
+        let response = RoomResponse::to_players_and_observers(WsMsg::HostReconnected {});
+        self.dispatch(response).await
+    }
+
+    async fn dispatch(&mut self, response: RoomResponse) -> Vec<ScheduledTimer> {
+        for msg in response.messages_to_host {
+            let event = self.host_events.stamp(msg);
+            self.transcribe(Audience::Host, Some(event.seq), &event);
+            if EventLog::is_replayable(&event.event) {
+                self.host_events.record(event.clone());
+            }
+            let msg = event.into_envelope();
+            for host in &self.hosts {
+                // Waiting on one stalled co-host would hold the room lock
+                if host.sender.is_full() {
+                    tracing::warn!(
+                        host_id = host.pid,
+                        "Host isn't reading messages, dropping one"
+                    );
+                } else if let Err(e) = host.sender.send(msg.clone()).await {
+                    tracing::warn!(host_id = host.pid, error = %e, "Dropped message to host");
+                }
+            }
+        }
+
+        for msg in response.messages_to_players {
+            let msg = redact_for_players(msg);
+            if self.settings.transcript {
+                self.transcript
+                    .record_event(None, PlayerEntry::time_ms(), Audience::Players, &msg);
+            }
+            for player in &mut self.players {
+                // Numbered even while disconnected, so the player can resume
+                let msg = stamp_for(player, msg.clone());
+                send_to_player(player, msg).await;
+            }
+        }
+
+        for (player_id, msg) in response.messages_to_specific {
+            let msg = redact_for_players(msg);
+            let Some(player) = self.players.iter_mut().find(|p| p.player.pid == player_id) else {
+                continue;
+            };
+            let msg = stamp_for(player, msg);
+            if self.settings.transcript
+                && let WsMsg::Envelope { seq, ts, event } = &msg
+            {
+                self.transcript
+                    .record_event(Some(*seq), *ts, Audience::Player(player_id), event);
+            }
+            send_to_player(player, msg).await;
+        }
+
+        for msg in response.messages_to_observers {
+            let msg = redact_for_players(msg);
+            let event = self.spectator_events.stamp(msg.clone());
+            self.transcribe(Audience::Observers, Some(event.seq), &event);
+            let envelope = event.into_envelope();
+            let mut deliveries: Vec<_> = self
+                .spectators
+                .iter()
+                .map(|s| (s, envelope.clone()))
+                .collect();
+            if shown_on_display(&msg) {
+                let envelope = self.display_events.stamp(msg.clone()).into_envelope();
+                deliveries.extend(self.displays.iter().map(|d| (d, envelope.clone())));
+            }
+            for (spectator, envelope) in deliveries {
+                // A stalled spectator must never hold up the game
+                if spectator.is_full() {
+                    tracing::warn!("Spectator isn't reading messages, dropping one");
+                } else if let Err(e) = spectator.send(envelope).await {
+                    tracing::warn!(error = %e, "Dropped message to spectator");
+                }
+            }
+            // Errors only mean nobody is watching right now
+            let _ = self.observers.send(msg);
+        }
+
+        response.timers
+    }
+
+    fn transcribe(&mut self, to: Audience, seq: Option<u64>, event: &SequencedEvent) {
+        if self.settings.transcript {
+            self.transcript
+                .record_event(seq, event.ts, to, &event.event);
+        }
+    }
+
+    /// Number `msg` in each of `targets`' own sequence, for messages sent
+    /// outside [`Room::dispatch`] like witnesses. They aren't replayed.
+    pub fn stamp_for_each(
+        &mut self,
+        targets: Vec<(PlayerId, tokio_mpmc::Sender<WsMsg>, Duration)>,
+        msg: &WsMsg,
+    ) -> Vec<(tokio_mpmc::Sender<WsMsg>, Duration, WsMsg)> {
+        targets
+            .into_iter()
+            .filter_map(|(pid, sender, delay)| {
+                let player = self.players.iter_mut().find(|p| p.player.pid == pid)?;
+                let envelope = player.events.stamp(msg.clone()).into_envelope();
+                Some((sender, delay, envelope))
+            })
+            .collect()
+    }
+
+    /// Everyone who should see a witness of `sender_id`'s message, with how
+    /// long to wait before delivering it.
+    pub fn witness_targets(
+        &self,
+        sender_id: Option<PlayerId>,
+    ) -> Vec<(PlayerId, tokio_mpmc::Sender<WsMsg>, Duration)> {
+        let latencies: Vec<u64> = self
+            .players
+            .iter()
+            .map(|p| p.latency().unwrap_or(0).into())
+            .collect();
+        let delays = witness_delays(self.settings.witness_base_delay(), &latencies);
+
+        self.players
+            .iter()
+            .zip(delays)
+            .filter(|(p, _)| Some(p.player.pid) != sender_id)
+            .map(|(p, delay)| (p.player.pid, p.sender.clone(), delay))
+            .collect()
+    }
+
+    fn has_next_board(&self) -> bool {
+        self.current_board + 1 < self.boards.len()
+    }
+
+    /// Only the board in play counts; later boards start with `NextBoard`.
+    fn has_remaining_questions(&self) -> bool {
+        self.categories
+            .iter()
+            .any(|cat| cat.questions.iter().any(|q| !q.answered))
+    }
+}
+
+/// Why a player can't buzz while buzzing is open for everyone else.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BuzzBlockedReason {
+    /// Already buzzed on this question (e.g. answered wrong)
+    AlreadyAnswered,
+    Benched,
+    EarlyBuzz,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum GameState {
+    #[default]
+    Start,
+    Selection,
+    QuestionReading,
+    Answer,
+    WaitingForBuzz,
+    /// Everyone is answering a poll question
+    Polling,
+    AnswerReveal,
+    GameEnd,
+    Paused,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_winner_determined_on_game_end() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Winner");
+        add_test_player(&mut room, 2, "Loser");
+
+        room.players[0].player.score = 1000;
+        room.players[1].player.score = 500;
+
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 1));
+        room.current_buzzer = Some(1);
+        room.categories[0].questions[0].answered = true;
+
+        room.handle_message(&WsMsg::host_checked(true), None);
+
+        assert_eq!(room.state, GameState::AnswerReveal);
+
+        room.handle_message(&WsMsg::host_continue(), None);
+
+        assert_eq!(room.state, GameState::GameEnd);
+        assert_eq!(room.winner, Some(1), "Player 1 should be winner");
+    }
+
+    #[test]
+    fn test_tie_results_in_no_winner() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Player1");
+        add_test_player(&mut room, 2, "Player2");
+
+        room.players[0].player.score = 1000;
+        room.players[1].player.score = 1000;
+
+        room.determine_winner();
+
+        assert_eq!(room.winner, None, "Tie should result in no winner");
+    }
+
+    #[test]
+    fn test_manual_end_game_determines_winner() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Winner");
+        add_test_player(&mut room, 2, "Loser");
+
+        room.players[0].player.score = 800;
+        room.players[1].player.score = 200;
+
+        room.handle_message(&WsMsg::EndGame {}, None);
+
+        assert_eq!(room.state, GameState::GameEnd);
+        assert_eq!(room.winner, Some(1));
+    }
+
+    #[test]
+    fn test_series_wins_survive_resets() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+
+        for _ in 0..2 {
+            room.handle_message(&WsMsg::StartGame {}, None);
+            room.players[0].player.score = 800;
+            room.handle_message(&WsMsg::EndGame {}, None);
+            room.handle_message(&WsMsg::EndGame {}, None);
+            room.handle_message(&WsMsg::ResetGame {}, None);
+        }
+
+        assert_eq!(
+            room.wins.get(&1),
+            Some(&2),
+            "An extra EndGame doesn't count"
+        );
+        assert_eq!(room.wins.get(&2), None);
+        match room.build_game_state_msg() {
+            WsMsg::GameState {
+                series_standings, ..
+            } => assert_eq!(series_standings, HashMap::from([(1, 2)])),
+            other => panic!("Expected GameState, got {other:?}"),
+        }
+
+        room.handle_message(&WsMsg::ResetSeries {}, Some(1));
+        assert_eq!(room.wins.len(), 1, "Only the host resets the series");
+        room.handle_message(&WsMsg::ResetSeries {}, None);
+        assert!(room.wins.is_empty());
+    }
+
+    #[test]
+    fn test_negative_scores_winner() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "LeastBad");
+        add_test_player(&mut room, 2, "ReallyBad");
+
+        room.players[0].player.score = -200;
+        room.players[1].player.score = -1000;
+
+        room.determine_winner();
+
+        assert_eq!(
+            room.winner,
+            Some(1),
+            "Player with higher negative score wins"
+        );
+    }
+
+    #[test]
+    fn test_rename_player() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Tpyo");
+        add_test_player(&mut room, 2, "Sam");
+
+        let response = room.handle_message(
+            &WsMsg::RenamePlayer {
+                name: "  Typo ".to_string(),
+            },
+            Some(1),
+        );
+
+        assert_eq!(room.players[0].player.name, "Typo");
+        assert!(
+            matches!(&response.messages_to_host[..], [WsMsg::PlayerList(players)] if players[0].name == "Typo"),
+            "Host should receive an updated PlayerList"
+        );
+        assert!(matches!(
+            &response.messages_to_specific[..],
+            [(1, WsMsg::PlayerState { .. })]
+        ));
+    }
+
+    #[test]
+    fn test_rename_player_rejected() {
+        let cases = [
+            ("", "empty"),
+            ("   ", "whitespace only"),
+            ("ABCDEFGHIJKLMNOPQRSTUVWXYZ", "too long"),
+            ("sAm", "case-insensitive collision"),
+        ];
+
+        for (name, case) in cases {
+            let mut room = create_test_room();
+            add_test_player(&mut room, 1, "AJ");
+            add_test_player(&mut room, 2, "Sam");
+
+            let response = room.handle_message(
+                &WsMsg::RenamePlayer {
+                    name: name.to_string(),
+                },
+                Some(1),
+            );
+
+            assert_eq!(room.players[0].player.name, "AJ", "Name changed: {case}");
+            assert!(
+                response.messages_to_host.is_empty(),
+                "Host notified: {case}"
+            );
+            assert!(
+                matches!(
+                    &response.messages_to_specific[..],
+                    [(1, WsMsg::NameRejected { .. })]
+                ),
+                "Player should get NameRejected: {case}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rename_player_changing_own_case() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "aj");
+
+        room.handle_message(
+            &WsMsg::RenamePlayer {
+                name: "AJ".to_string(),
+            },
+            Some(1),
+        );
+
+        assert_eq!(room.players[0].player.name, "AJ");
+    }
+
+    #[test]
+    fn test_raise_hand_notifies_host_only() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::QuestionReading;
+        room.current_question = Some((0, 0));
+
+        let response = room.handle_message(&WsMsg::RaiseHand {}, Some(1));
+
+        assert!(matches!(
+            &response.messages_to_host[..],
+            [WsMsg::HandRaised { pid: 1 }]
+        ));
+        assert!(response.messages_to_players.is_empty());
+        assert!(response.messages_to_specific.is_empty());
+        assert_eq!(room.state, GameState::QuestionReading);
+        assert_eq!(room.current_buzzer, None);
+        assert!(!room.players[0].player.buzzed);
+        assert_eq!(room.raised_hands, vec![1]);
+
+        let repeat = room.handle_message(&WsMsg::RaiseHand {}, Some(1));
+        assert!(
+            repeat.messages_to_host.is_empty(),
+            "Raising an already raised hand should not notify again"
+        );
+    }
+
+    #[test]
+    fn test_rename_cooldown() {
+        let mut room = create_test_room();
+        room.settings.rename_cooldown_ms = 50;
+        add_test_player(&mut room, 1, "AJ");
+
+        let rename = |name: &str| WsMsg::RenamePlayer {
+            name: name.to_string(),
+        };
+
+        room.handle_message(&rename("First"), Some(1));
+        assert_eq!(room.players[0].player.name, "First");
+
+        let response = room.handle_message(&rename("Second"), Some(1));
+        assert_eq!(
+            room.players[0].player.name, "First",
+            "Rename inside the cooldown should be rejected"
+        );
+        assert!(matches!(
+            &response.messages_to_specific[..],
+            [(1, WsMsg::Notice { .. })]
+        ));
+        assert!(response.messages_to_host.is_empty());
+
+        std::thread::sleep(std::time::Duration::from_millis(60));
+
+        room.handle_message(&rename("Second"), Some(1));
+        assert_eq!(
+            room.players[0].player.name, "Second",
+            "Rename after the cooldown should be allowed"
+        );
+    }
+
+    fn player_state_recipients(response: &RoomResponse) -> Vec<PlayerId> {
+        response
+            .messages_to_specific
+            .iter()
+            .filter(|(_, msg)| matches!(msg, WsMsg::PlayerState { .. }))
+            .map(|(pid, _)| *pid)
+            .collect()
+    }
+
+    #[test]
+    fn test_buzz_sends_only_buzzer_player_state() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        add_test_player(&mut room, 3, "Alex");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+
+        assert_eq!(player_state_recipients(&response), vec![2]);
+        assert_eq!(
+            response.messages_to_players.len(),
+            1,
+            "Structural change is still broadcast as GameState"
+        );
+    }
+
+    #[test]
+    fn test_host_checked_player_state_deltas() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        add_test_player(&mut room, 3, "Alex");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let response = room.handle_message(&WsMsg::host_checked(false), None);
+        assert_eq!(
+            player_state_recipients(&response),
+            vec![1, 2, 3],
+            "Reopening buzzing updates everyone"
+        );
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(3));
+        let response = room.handle_message(&WsMsg::host_checked(true), None);
+        assert_eq!(
+            player_state_recipients(&response),
+            vec![3],
+            "Only the scoring player changed"
+        );
+    }
+
+    #[test]
+    fn test_target_score_ends_game() {
+        let mut room = create_test_room();
+        room.settings.target_score = Some(300);
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.players[0].player.score = 200;
+
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 0));
+        room.current_buzzer = Some(1);
+
+        room.handle_message(&WsMsg::host_checked(true), None);
+
+        assert!(room.has_remaining_questions(), "Clues should remain");
+        assert_eq!(room.state, GameState::GameEnd);
+        assert_eq!(room.winner, Some(1));
+    }
+
+    #[test]
+    fn test_target_score_not_reached_continues() {
+        let mut room = create_test_room();
+        room.settings.target_score = Some(1000);
+        add_test_player(&mut room, 1, "AJ");
+
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 0));
+        room.current_buzzer = Some(1);
+
+        room.handle_message(&WsMsg::host_checked(true), None);
+
+        assert_eq!(room.state, GameState::AnswerReveal);
+        assert_eq!(room.winner, None);
+    }
+
+    #[test]
+    fn test_benched_player_cannot_buzz() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.current_question = Some((0, 0));
+
+        room.handle_message(
+            &WsMsg::BenchPlayer {
+                pid: 1,
+                duration_ms: 50,
+            },
+            None,
+        );
+
+        room.state = GameState::WaitingForBuzz;
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.current_buzzer, None, "Benched player buzz rejected");
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert!(matches!(
+            &response.messages_to_specific[..],
+            [(1, WsMsg::Notice { .. })]
+        ));
+        assert!(matches!(
+            room.build_player_state_msg(1),
+            Some(WsMsg::PlayerState {
+                can_buzz: false,
+                ..
+            })
+        ));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        assert_eq!(room.current_buzzer, Some(2), "Others play normally");
+
+        // Once the bench lapses the player can buzz on the next question
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        room.state = GameState::WaitingForBuzz;
+        room.current_buzzer = None;
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.current_buzzer, Some(1));
+    }
+
+    #[test]
+    fn test_only_host_can_bench() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+
+        room.handle_message(
+            &WsMsg::BenchPlayer {
+                pid: 1,
+                duration_ms: 10_000,
+            },
+            Some(2),
+        );
+
+        assert!(room.players[0].benched_until.is_none());
+    }
+
+    #[test]
+    fn test_buzz_ignored_while_paused() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Pause {}, None);
+        assert_eq!(room.state, GameState::Paused);
+        assert_eq!(room.paused_from, Some(GameState::WaitingForBuzz));
+
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.current_buzzer, None, "Buzz during pause is ignored");
+        assert!(!room.players[0].player.buzzed);
+        assert!(matches!(
+            &response.messages_to_specific[..],
+            [(
+                1,
+                WsMsg::PlayerState {
+                    can_buzz: false,
+                    ..
+                }
+            )]
+        ));
+
+        room.handle_message(&WsMsg::Resume {}, None);
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert_eq!(room.paused_from, None);
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.current_buzzer, Some(1), "Buzzing works after resume");
+    }
+
+    #[test]
+    fn test_resume_rearms_steal_window() {
+        let mut room = create_test_room();
+        room.settings.steal_window_ms = Some(5_000);
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let response = room.handle_message(&WsMsg::host_checked(false), None);
+        let steal = response
+            .timers
+            .iter()
+            .find(|t| t.kind == TimerKind::StealWindow)
+            .cloned()
+            .expect("Steal window should start");
+
+        room.handle_message(&WsMsg::Pause {}, None);
+        room.handle_timer(&steal);
+        let response = room.handle_message(&WsMsg::Resume {}, None);
+        let rearmed = response
+            .timers
+            .iter()
+            .find(|t| t.kind == TimerKind::StealWindow)
+            .cloned()
+            .expect("Resuming should restart the steal window");
+
+        room.handle_timer(&rearmed);
+        assert_eq!(room.state, GameState::AnswerReveal);
+    }
+
+    #[test]
+    fn test_resume_keeps_buzzes_held_during_pause() {
+        let mut room = create_test_room();
+        room.settings.buzz_window_ms = Some(100);
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let window = response.timers[0].clone();
+
+        room.handle_message(&WsMsg::Pause {}, None);
+        room.handle_timer(&window);
+        let response = room.handle_message(&WsMsg::Resume {}, None);
+        let rearmed = response
+            .timers
+            .iter()
+            .find(|t| t.kind == TimerKind::BuzzWindow)
+            .cloned()
+            .expect("Resuming should restart the buzz window");
+
+        room.handle_timer(&rearmed);
+        assert_eq!(room.current_buzzer, Some(1));
+    }
+
+    #[test]
+    fn test_ending_paused_game_stays_ended() {
+        let mut room = create_test_room();
+        room.state = GameState::Selection;
+        room.handle_message(&WsMsg::Pause {}, None);
+
+        room.handle_message(&WsMsg::EndGame {}, None);
+        assert_eq!(room.state, GameState::Paused, "EndGame waits for resume");
+
+        room.handle_message(&WsMsg::Resume {}, None);
+        room.handle_message(&WsMsg::EndGame {}, Some(1));
+        assert_eq!(room.state, GameState::Selection, "Only the host ends games");
+        room.handle_message(&WsMsg::EndGame {}, None);
+        assert_eq!(room.state, GameState::GameEnd);
+        assert_eq!(room.paused_from, None);
+
+        room.handle_message(&WsMsg::Resume {}, None);
+        assert_eq!(room.state, GameState::GameEnd, "Resume can't undo the end");
+    }
+
+    #[test]
+    fn test_host_actions_ignored_while_paused() {
+        let mut room = create_test_room();
+        room.state = GameState::QuestionReading;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Pause {}, None);
+        room.handle_message(&WsMsg::HostReady {}, None);
+
+        assert_eq!(room.state, GameState::Paused);
+        assert_eq!(room.paused_from, Some(GameState::QuestionReading));
+    }
+
+    fn answer_revealed(msgs: &[WsMsg]) -> Option<&str> {
+        msgs.iter().find_map(|m| match m {
+            WsMsg::AnswerRevealed {
+                category_index: 0,
+                question_index: 0,
+                answer,
+            } => Some(answer.as_str()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_answer_revealed_emitted_on_reveal() {
+        let cases = [
+            ("correct", WsMsg::host_checked(true)),
+            ("all wrong", WsMsg::host_checked(false)),
+            ("skip", WsMsg::host_skip()),
+        ];
+
+        for (name, msg) in cases {
+            let mut room = create_test_room();
+            add_test_player(&mut room, 1, "AJ");
+            room.state = GameState::Answer;
+            room.current_question = Some((0, 0));
+            room.current_buzzer = Some(1);
+            room.players[0].player.buzzed = true;
+
+            let response = room.handle_message(&msg, None);
+
+            assert_eq!(room.state, GameState::AnswerReveal, "{name}");
+            assert_eq!(
+                answer_revealed(&response.messages_to_players),
+                Some("4"),
+                "Players should learn the answer: {name}"
+            );
+            assert_eq!(
+                answer_revealed(&response.messages_to_host),
+                Some("4"),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_answer_not_revealed_on_rebuzz() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 0));
+        room.current_buzzer = Some(1);
+        room.players[0].player.buzzed = true;
+
+        let response = room.handle_message(&WsMsg::host_checked(false), None);
+
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert_eq!(answer_revealed(&response.messages_to_players), None);
+    }
+
+    fn create_test_room() -> Room {
+        let mut room = Room::new("TEST".to_string(), "token".to_string());
+
+        room.categories = vec![Category {
+            title: "Test Category".to_string(),
+            questions: vec![
+                Question {
+                    question: "What is 2+2?".to_string(),
+                    answer: "4".to_string(),
+                    value: 200,
+                    answered: false,
+                    mode: QuestionMode::Buzzer,
+                    media_url: None,
+                    media_type: None,
+                },
+                Question {
+                    question: "What is 6?".to_string(),
+                    answer: "6".to_string(),
+                    value: 400,
+                    answered: false,
+                    mode: QuestionMode::Buzzer,
+                    media_url: None,
+                    media_type: None,
+                },
+            ],
+        }];
+
+        room
+    }
+
+    fn add_test_player(room: &mut Room, pid: u32, name: &str) {
+        use tokio_mpmc::channel;
+        let (tx, _rx) = channel(10);
+
+        let player = PlayerEntry::new(
+            Player::new(pid, name.to_string(), 0, false, "token".to_string()),
+            tx,
+        );
+        room.players.push(player);
+    }
+
+    #[test]
+    fn test_game_state_transitions() {
+        struct TestCase {
+            name: &'static str,
+            initial_state: GameState,
+            setup: fn(&mut Room),
             message: WsMsg,
             sender_id: Option<PlayerId>,
             expected_state: GameState,
-            assertions: fn(&Room),
+            assertions: fn(&Room),
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "StartGame transitions to Selection",
+                initial_state: GameState::Start,
+                setup: |_| {},
+                message: WsMsg::StartGame {},
+                sender_id: None,
+                expected_state: GameState::Selection,
+                assertions: |_| {},
+            },
+            TestCase {
+                name: "HostChoice transitions to QuestionReading",
+                initial_state: GameState::Selection,
+                setup: |_| {},
+                message: WsMsg::HostChoice {
+                    category_index: 0,
+                    question_index: 0,
+                },
+                sender_id: None,
+                expected_state: GameState::QuestionReading,
+                assertions: |room| {
+                    assert_eq!(room.current_question, Some((0, 0)));
+                    assert_eq!(room.current_buzzer, None);
+                },
+            },
+            TestCase {
+                name: "HostChoice resets player buzz states",
+                initial_state: GameState::Selection,
+                setup: |room| {
+                    add_test_player(room, 1, "AJ");
+                    add_test_player(room, 1, "Sam");
+                    room.players[0].player.buzzed = true;
+                    room.players[1].player.buzzed = true;
+                },
+                message: WsMsg::HostChoice {
+                    category_index: 0,
+                    question_index: 0,
+                },
+                sender_id: None,
+                expected_state: GameState::QuestionReading,
+                assertions: |room| {
+                    assert!(!room.players[0].player.buzzed);
+                    assert!(!room.players[1].player.buzzed);
+                },
+            },
+            TestCase {
+                name: "HostReady transitions to WaitingForBuzz",
+                initial_state: GameState::QuestionReading,
+                setup: |_| {},
+                message: WsMsg::HostReady {},
+                sender_id: None,
+                expected_state: GameState::WaitingForBuzz,
+                assertions: |_| {},
+            },
+            TestCase {
+                name: "Player buzz transitions to Answer",
+                initial_state: GameState::WaitingForBuzz,
+                setup: |room| {
+                    add_test_player(room, 1, "AJ");
+                },
+                message: WsMsg::Buzz { seq: None },
+                sender_id: Some(1),
+                expected_state: GameState::Answer,
+                assertions: |room| {
+                    assert_eq!(room.current_buzzer, Some(1));
+                    assert!(room.players[0].player.buzzed);
+                },
+            },
+            TestCase {
+                name: "Player cannot buzz twice",
+                initial_state: GameState::WaitingForBuzz,
+                setup: |room| {
+                    add_test_player(room, 1, "AJ");
+                    room.players[0].player.buzzed = true;
+                },
+                message: WsMsg::Buzz { seq: None },
+                sender_id: Some(1),
+                expected_state: GameState::WaitingForBuzz,
+                assertions: |room| {
+                    assert_eq!(room.current_buzzer, None);
+                },
+            },
+        ];
+
+        for tc in test_cases {
+            let mut room = create_test_room();
+            room.state = tc.initial_state;
+            (tc.setup)(&mut room);
+
+            room.handle_message(&tc.message, tc.sender_id);
+
+            assert_eq!(
+                room.state, tc.expected_state,
+                "Test case failed: {}",
+                tc.name
+            );
+            (tc.assertions)(&room)
+        }
+    }
+
+    #[test]
+    fn test_scoring() {
+        struct TestCase {
+            name: &'static str,
+            setup: fn(&mut Room),
+            correct: bool,
+            expected_score: i32,
+            expected_state: GameState,
+            question_answered: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Correct answer awards points",
+                setup: |room| {
+                    add_test_player(room, 1, "AJ");
+                    room.state = GameState::Answer;
+                    room.current_question = Some((0, 0));
+                    room.current_buzzer = Some(1);
+                },
+                correct: true,
+                expected_score: 200,
+                expected_state: GameState::AnswerReveal,
+                question_answered: true,
+            },
+            TestCase {
+                name: "Incorrect answer deducts points",
+                setup: |room| {
+                    add_test_player(room, 1, "AJ");
+                    add_test_player(room, 2, "Sam");
+                    room.state = GameState::Answer;
+                    room.current_question = Some((0, 0));
+                    room.current_buzzer = Some(1);
+                    room.players[0].player.buzzed = true;
+                },
+                correct: false,
+                expected_score: -200,
+                expected_state: GameState::WaitingForBuzz,
+                question_answered: false,
+            },
+            TestCase {
+                name: "All players wrong marks question answered",
+                setup: |room| {
+                    add_test_player(room, 1, "AJ");
+                    add_test_player(room, 2, "Sam");
+                    room.state = GameState::Answer;
+                    room.current_question = Some((0, 0));
+                    room.current_buzzer = Some(1);
+                    room.players[0].player.buzzed = true;
+                    room.players[1].player.buzzed = true;
+                },
+                correct: false,
+                expected_score: -200,
+                expected_state: GameState::AnswerReveal,
+                question_answered: true,
+            },
+            TestCase {
+                name: "Game ends when no questions remain",
+                setup: |room| {
+                    add_test_player(room, 1, "AJ");
+                    room.state = GameState::Answer;
+                    room.categories[0].questions[0].answered = true;
+                    room.current_question = Some((0, 1));
+                    room.current_buzzer = Some(1);
+                },
+                correct: true,
+                expected_score: 400,
+                expected_state: GameState::AnswerReveal,
+                question_answered: true,
+            },
+        ];
+
+        for tc in test_cases {
+            let mut room = create_test_room();
+            (tc.setup)(&mut room);
+
+            let (cat_idx, q_idx) = room
+                .current_question
+                .expect("Failed to get current question");
+
+            room.handle_message(&WsMsg::host_checked(tc.correct), None);
+
+            assert_eq!(
+                room.players[0].player.score, tc.expected_score,
+                "Test case failed (score): {}",
+                tc.name
+            );
+            assert_eq!(
+                room.state, tc.expected_state,
+                "Test case failed (state): {}",
+                tc.name
+            );
+            assert_eq!(
+                room.categories[cat_idx].questions[q_idx].answered, tc.question_answered,
+                "Test case failed (answered): {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_host_skip_marks_question_answered() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Player1");
+
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::host_skip(), None);
+
+        assert!(
+            room.categories[0].questions[0].answered,
+            "Skipped question should be marked as answered"
+        );
+        assert_eq!(
+            room.state,
+            GameState::AnswerReveal,
+            "Should transition to AnswerReveal"
+        );
+    }
+
+    #[test]
+    fn test_host_skip_transitions_to_selection() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Player1");
+
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::host_skip(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::AnswerReveal,
+            "Should first go to AnswerReveal"
+        );
+
+        room.handle_message(&WsMsg::host_continue(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::Selection,
+            "Should return to Selection when questions remain"
+        );
+    }
+
+    #[test]
+    fn test_host_skip_transitions_to_game_end() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Winner");
+        add_test_player(&mut room, 2, "Loser");
+
+        room.players[0].player.score = 500;
+        room.players[1].player.score = 200;
+
+        room.state = GameState::WaitingForBuzz;
+        room.categories[0].questions[0].answered = true;
+        room.current_question = Some((0, 1)); // Last question
+
+        room.handle_message(&WsMsg::host_skip(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::AnswerReveal,
+            "Should first go to AnswerReveal"
+        );
+
+        room.handle_message(&WsMsg::host_continue(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::GameEnd,
+            "Should transition to GameEnd when no questions remain"
+        );
+        assert_eq!(
+            room.winner,
+            Some(1),
+            "Should determine winner when game ends"
+        );
+    }
+
+    #[test]
+    fn test_host_skip_resets_buzz_states() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Player1");
+        add_test_player(&mut room, 2, "Player2");
+
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        room.players[0].player.buzzed = true;
+        room.players[1].player.buzzed = true;
+        room.current_buzzer = Some(1);
+
+        room.handle_message(&WsMsg::host_skip(), None);
+        room.handle_message(&WsMsg::host_continue(), None);
+
+        assert!(
+            !room.players[0].player.buzzed,
+            "Player 1 buzz state should be reset"
+        );
+        assert!(
+            !room.players[1].player.buzzed,
+            "Player 2 buzz state should be reset"
+        );
+    }
+
+    #[test]
+    fn test_host_skip_does_not_affect_scores() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Player1");
+
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        room.players[0].player.score = 100;
+
+        room.handle_message(&WsMsg::host_skip(), None);
+
+        assert_eq!(
+            room.players[0].player.score, 100,
+            "Skipping should not affect player scores"
+        );
+    }
+
+    #[test]
+    fn test_host_skip_without_current_question() {
+        let mut room = create_test_room();
+
+        room.state = GameState::Selection;
+        room.current_question = None;
+
+        let response = room.handle_message(&WsMsg::host_skip(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::Selection,
+            "State should not change when there's no current question"
+        );
+        assert_eq!(
+            response.messages_to_host.len(),
+            0,
+            "Should return empty response when there's no current question"
+        );
+    }
+
+    #[test]
+    fn test_answer_reveal_after_correct() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Player1");
+
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 0));
+        room.current_buzzer = Some(1);
+
+        // Host marks answer correct
+        room.handle_message(&WsMsg::host_checked(true), None);
+
+        assert_eq!(
+            room.state,
+            GameState::AnswerReveal,
+            "Should transition to AnswerReveal after correct answer"
+        );
+        assert_eq!(room.players[0].player.score, 200, "Score should be updated");
+
+        // Host continues
+        room.handle_message(&WsMsg::host_continue(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::Selection,
+            "Should transition to Selection after continue"
+        );
+        assert_eq!(
+            room.current_question, None,
+            "Current question should be cleared"
+        );
+        assert_eq!(
+            room.current_buzzer, None,
+            "Current buzzer should be cleared"
+        );
+    }
+
+    #[test]
+    fn test_answer_reveal_after_all_incorrect() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Player1");
+        add_test_player(&mut room, 2, "Player2");
+
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 0));
+        room.current_buzzer = Some(1);
+        room.players[0].player.buzzed = true;
+        room.players[1].player.buzzed = true; // All players have buzzed
+
+        // Host marks answer incorrect
+        room.handle_message(&WsMsg::host_checked(false), None);
+
+        assert_eq!(
+            room.state,
+            GameState::AnswerReveal,
+            "Should transition to AnswerReveal when all players buzzed incorrectly"
+        );
+        assert_eq!(
+            room.players[0].player.score, -200,
+            "Score should be deducted"
+        );
+
+        // Host continues
+        room.handle_message(&WsMsg::host_continue(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::Selection,
+            "Should transition to Selection after continue"
+        );
+    }
+
+    #[test]
+    fn test_answer_reveal_after_skip() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Player1");
+
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        room.players[0].player.score = 100;
+
+        // Host skips question
+        room.handle_message(&WsMsg::host_skip(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::AnswerReveal,
+            "Should transition to AnswerReveal after skip"
+        );
+        assert_eq!(
+            room.players[0].player.score, 100,
+            "Score should not change after skip"
+        );
+        assert!(
+            room.categories[0].questions[0].answered,
+            "Question should be marked as answered"
+        );
+
+        // Host continues
+        room.handle_message(&WsMsg::host_continue(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::Selection,
+            "Should transition to Selection after continue"
+        );
+    }
+
+    #[test]
+    fn test_answer_reveal_to_game_end() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Winner");
+        add_test_player(&mut room, 2, "Loser");
+
+        room.players[0].player.score = 500;
+        room.players[1].player.score = 200;
+
+        room.state = GameState::Answer;
+        room.categories[0].questions[0].answered = true; // First question already answered
+        room.current_question = Some((0, 1)); // Last question
+        room.current_buzzer = Some(1);
+
+        // Host marks answer correct
+        room.handle_message(&WsMsg::host_checked(true), None);
+
+        assert_eq!(
+            room.state,
+            GameState::AnswerReveal,
+            "Should transition to AnswerReveal"
+        );
+
+        // Host continues from last question
+        room.handle_message(&WsMsg::host_continue(), None);
+
+        assert_eq!(
+            room.state,
+            GameState::GameEnd,
+            "Should transition to GameEnd when no questions remain"
+        );
+        assert_eq!(room.winner, Some(1), "Winner should be determined");
+    }
+
+    #[test]
+    fn test_incorrect_stays_in_waiting_for_buzz() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "Player1");
+        add_test_player(&mut room, 2, "Player2");
+
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 0));
+        room.current_buzzer = Some(1);
+        room.players[0].player.buzzed = true;
+        room.players[1].player.buzzed = false; // Player 2 hasn't buzzed yet
+
+        // Host marks answer incorrect
+        room.handle_message(&WsMsg::host_checked(false), None);
+
+        assert_eq!(
+            room.state,
+            GameState::WaitingForBuzz,
+            "Should stay in WaitingForBuzz when more players can buzz"
+        );
+        assert_eq!(
+            room.current_buzzer, None,
+            "Current buzzer should be cleared"
+        );
+        assert_eq!(
+            room.current_question,
+            Some((0, 0)),
+            "Current question should remain"
+        );
+    }
+
+    #[test]
+    fn test_game_state_wire_names_are_stable() {
+        let expected = [
+            (GameState::Start, "start"),
+            (GameState::Selection, "selection"),
+            (GameState::QuestionReading, "questionReading"),
+            (GameState::Answer, "answer"),
+            (GameState::WaitingForBuzz, "waitingForBuzz"),
+            (GameState::AnswerReveal, "answerReveal"),
+            (GameState::GameEnd, "gameEnd"),
+            (GameState::Paused, "paused"),
+        ];
+
+        for (state, name) in expected {
+            let json = serde_json::to_value(&state).expect("GameState should serialize");
+            assert_eq!(json, serde_json::json!(name));
+            let parsed: GameState =
+                serde_json::from_value(json).expect("GameState should deserialize");
+            assert_eq!(parsed, state);
+        }
+    }
+
+    fn reading_room_with_players() -> Room {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+            None,
+        );
+        room
+    }
+
+    #[test]
+    fn test_answer_without_buzzer_recovers() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 0));
+        room.current_buzzer = None;
+
+        let response = room.handle_message(&WsMsg::host_checked(true), None);
+
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert_eq!(room.players[0].player.score, 0);
+        assert!(!room.categories[0].questions[0].answered);
+        assert!(matches!(
+            response.messages_to_host.first(),
+            Some(WsMsg::Notice { .. })
+        ));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.current_buzzer, Some(1), "The game goes on");
+    }
+
+    #[test]
+    fn test_stale_turn_token_ruling_is_ignored() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let seen = room.turn_token;
+        let wrong = WsMsg::HostChecked {
+            correct: false,
+            turn_token: Some(seen),
+        };
+        room.handle_message(&wrong, None);
+        assert_eq!(room.players[0].player.score, -200);
+
+        // Sam buzzes before the host's double click lands
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        let response = room.handle_message(&wrong, None);
+
+        assert_eq!(room.players[1].player.score, 0, "Sam wasn't ruled on");
+        assert_eq!(room.state, GameState::Answer);
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::GameState { turn_token, .. }] if *turn_token == room.turn_token
+        ));
+
+        room.handle_message(
+            &WsMsg::HostChecked {
+                correct: true,
+                turn_token: Some(room.turn_token),
+            },
+            None,
+        );
+        assert_eq!(room.players[1].player.score, 200);
+    }
+
+    #[test]
+    fn test_stale_skip_and_continue_are_ignored() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        let seen = room.turn_token;
+        let skip = WsMsg::HostSkip {
+            turn_token: Some(seen),
+        };
+        room.handle_message(&skip, None);
+        assert_eq!(room.state, GameState::AnswerReveal);
+
+        let seen = room.turn_token;
+        let next = WsMsg::HostContinue {
+            turn_token: Some(seen),
+        };
+        room.handle_message(&next, None);
+        assert_eq!(room.state, GameState::Selection);
+
+        // Double clicks arrive a turn late
+        room.handle_message(&skip, None);
+        room.handle_message(&next, None);
+        assert_eq!(room.state, GameState::Selection);
+
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 1));
+        let award = WsMsg::AwardQuestion {
+            pid: 1,
+            correct: true,
+            turn_token: Some(seen),
+        };
+        room.handle_message(&award, None);
+        assert_eq!(room.players[0].player.score, 0, "Stale award is ignored");
+    }
+
+    #[test]
+    fn test_request_state_answers_only_the_asker() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        let response = room.handle_message(&WsMsg::RequestState {}, Some(1));
+
+        assert!(response.messages_to_host.is_empty());
+        assert!(response.messages_to_players.is_empty());
+        assert!(response.messages_to_observers.is_empty());
+        let game_states: Vec<_> = response
+            .messages_to_specific
+            .iter()
+            .filter(|(_, m)| matches!(m, WsMsg::GameState { .. }))
+            .collect();
+        match game_states.as_slice() {
+            [(1, WsMsg::GameState { categories, .. })] => {
+                assert_eq!(
+                    categories[0].questions[0].answer, "",
+                    "Answers are redacted"
+                );
+            }
+            other => panic!("Expected one GameState for AJ, got {:?}", other),
+        }
+        assert!(response.messages_to_specific.iter().any(|(pid, m)| *pid == 1
+            && matches!(m, WsMsg::PlayerState { can_buzz: true, .. })));
+
+        let response = room.handle_message(&WsMsg::RequestState {}, None);
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::GameState { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_question_stats_record_buzzers_and_rulings() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 1));
+        room.buzz_opened_at = Some(Instant::now());
+        // Buzzing first opened a while back and has since reopened
+        room.first_opened_at = Some(Instant::now() - Duration::from_secs(1));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(false), None);
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        let response = room.handle_message(&WsMsg::host_checked(true), None);
+
+        let stats = match response
+            .messages_to_host
+            .iter()
+            .find(|m| matches!(m, WsMsg::QuestionStats { .. }))
+        {
+            Some(WsMsg::QuestionStats { stats }) => stats.clone(),
+            other => panic!("Expected QuestionStats for the host, got {:?}", other),
+        };
+        assert_eq!((stats.category_index, stats.question_index), (0, 1));
+        let buzzers: Vec<_> = stats.buzzes.iter().map(|b| b.pid).collect();
+        assert_eq!(buzzers, [1, 2], "AJ won the buzz race");
+        assert!(
+            stats.buzzes.iter().all(|b| b.ms_to_buzz >= Some(1000)),
+            "Timed from when buzzing first opened"
+        );
+        assert_eq!(
+            stats.rulings,
+            [
+                Ruling {
+                    pid: 1,
+                    correct: false
+                },
+                Ruling {
+                    pid: 2,
+                    correct: true
+                },
+            ]
+        );
+        assert_eq!(stats.answered_by(), Some(2));
+        assert_eq!(room.question_stats, [stats]);
+        assert!(
+            !response
+                .messages_to_players
+                .iter()
+                .any(|m| matches!(m, WsMsg::QuestionStats { .. })),
+            "Stats are for the host"
+        );
+    }
+
+    fn double_buzz_room() -> Room {
+        let mut room = create_test_room();
+        room.settings.double_buzz_ms = Some(300);
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        room
+    }
+
+    #[test]
+    fn test_double_buzz_confirms_within_window() {
+        let mut room = double_buzz_room();
+
+        let response = room.handle_message(&WsMsg::Buzz { seq: Some(1) }, Some(1));
+        assert!(response.messages_to_host.is_empty(), "One tap isn't a buzz");
+        assert!(
+            matches!(
+                response.messages_to_specific.as_slice(),
+                [(1, WsMsg::Notice { .. })]
+            ),
+            "The player hears their tap was counted"
+        );
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+
+        room.handle_message(&WsMsg::Buzz { seq: Some(2) }, Some(1));
+        assert_eq!(room.state, GameState::Answer);
+        assert_eq!(room.current_buzzer, Some(1));
+    }
+
+    #[test]
+    fn test_double_buzz_forgets_taps_before_buzzing_opened() {
+        let mut room = double_buzz_room();
+        room.state = GameState::QuestionReading;
+
+        let response = room.handle_message(&WsMsg::Buzz { seq: Some(1) }, Some(1));
+        assert!(matches!(
+            response.messages_to_specific.as_slice(),
+            [(
+                1,
+                WsMsg::PlayerState {
+                    can_buzz: false,
+                    ..
+                }
+            )]
+        ));
+        room.open_buzzing();
+        assert!(room.first_taps.is_empty());
+
+        room.handle_message(&WsMsg::Buzz { seq: Some(2) }, Some(1));
+        assert_eq!(
+            room.state,
+            GameState::WaitingForBuzz,
+            "Needs two fresh taps"
+        );
+    }
+
+    #[test]
+    fn test_double_buzz_ignores_lone_taps() {
+        let mut room = double_buzz_room();
+
+        room.handle_message(&WsMsg::Buzz { seq: Some(1) }, Some(1));
+        // The second tap comes too late to confirm the first
+        room.first_taps
+            .insert(1, Instant::now() - Duration::from_secs(1));
+        room.handle_message(&WsMsg::Buzz { seq: Some(2) }, Some(1));
+
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert!(room.buzz_queue.is_empty());
+        assert!(room.first_taps.contains_key(&1), "The late tap starts over");
+    }
+
+    #[test]
+    fn test_rejected_buzz_tells_player_why() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(false), None);
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert!(matches!(
+            response.messages_to_specific.as_slice(),
+            [(
+                1,
+                WsMsg::PlayerState {
+                    can_buzz: false,
+                    blocked_reason: Some(BuzzBlockedReason::AlreadyAnswered),
+                    ..
+                }
+            )]
+        ));
+        assert!(response.messages_to_host.is_empty());
+    }
+
+    #[test]
+    fn test_buzzing_waits_for_question_acks() {
+        let mut room = reading_room_with_players();
+
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        let response = room.handle_message(&WsMsg::HostReady {}, None);
+        assert_eq!(room.state, GameState::QuestionReading);
+        assert!(room.awaiting_acks);
+        assert_eq!(
+            response.timers.len(),
+            1,
+            "Fallback timer should be scheduled"
+        );
+
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(2));
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert!(!room.awaiting_acks);
+
+        // The fallback no longer does anything once buzzing is open
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_timer(&response.timers[0]);
+        assert_eq!(room.state, GameState::Answer);
+    }
+
+    #[test]
+    fn test_buzzing_opens_immediately_when_all_acked() {
+        let mut room = reading_room_with_players();
+
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(2));
+        let response = room.handle_message(&WsMsg::HostReady {}, None);
+
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert!(response.timers.is_empty());
+    }
+
+    #[test]
+    fn test_question_ack_timeout_opens_buzzing() {
+        let mut room = reading_room_with_players();
+
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        let response = room.handle_message(&WsMsg::HostReady {}, None);
+        assert_eq!(room.state, GameState::QuestionReading);
+
+        let response = room.handle_timer(&response.timers[0]);
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert_eq!(player_state_recipients(&response), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_disconnected_players_need_not_ack() {
+        let mut room = reading_room_with_players();
+        room.players[1].status = ConnectionStatus::Disconnected;
+
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        room.handle_message(&WsMsg::HostReady {}, None);
+
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+    }
+
+    #[test]
+    fn test_waiting_count_ignores_acks_from_dropped_players() {
+        let mut room = reading_room_with_players();
+        add_test_player(&mut room, 3, "Sam");
+
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(2));
+        room.players[0].status = ConnectionStatus::Disconnected;
+        room.players[1].status = ConnectionStatus::Disconnected;
+        let response = room.handle_message(&WsMsg::HostReady {}, None);
+
+        assert_eq!(room.state, GameState::QuestionReading);
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::Notice { message }] if message.contains("Waiting for 1 player")
+        ));
+    }
+
+    #[test]
+    fn test_stale_question_timer_is_ignored() {
+        let mut room = reading_room_with_players();
+
+        let stale = room.handle_message(&WsMsg::HostReady {}, None);
+        // Picking another question cancels the pending fallback
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 1,
+            },
+            None,
+        );
+        room.handle_message(&WsMsg::HostReady {}, None);
+
+        room.handle_timer(&stale.timers[0]);
+        assert_eq!(room.state, GameState::QuestionReading);
+    }
+
+    #[test]
+    fn test_witness_delays() {
+        struct TestCase {
+            name: &'static str,
+            latencies: Vec<u64>,
+            expected: Vec<u64>,
+        }
+
+        let base = Duration::from_millis(500);
+        let test_cases = vec![
+            TestCase {
+                name: "No players",
+                latencies: vec![],
+                expected: vec![],
+            },
+            TestCase {
+                name: "Latency is subtracted from the base",
+                latencies: vec![0, 120, 80],
+                expected: vec![500, 380, 420],
+            },
+            TestCase {
+                name: "Latency beyond the base clamps to zero",
+                latencies: vec![900, 100],
+                expected: vec![0, 400],
+            },
+            TestCase {
+                name: "Outliers above the 95th percentile are capped",
+                latencies: (0..19).map(|_| 100).chain([450]).collect(),
+                expected: (0..20).map(|_| 400).collect(),
+            },
+        ];
+
+        for case in test_cases {
+            let delays = witness_delays(base, &case.latencies);
+            let expected: Vec<Duration> = case
+                .expected
+                .iter()
+                .map(|&ms| Duration::from_millis(ms))
+                .collect();
+            assert_eq!(delays, expected, "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn test_witness_delays_never_exceed_base() {
+        let base = Duration::from_millis(50);
+        for delay in witness_delays(base, &[0, 10, 40, 5_000, u64::MAX]) {
+            assert!(delay <= base);
+        }
+        assert_eq!(
+            witness_delays(Duration::ZERO, &[0, 100]),
+            vec![Duration::ZERO, Duration::ZERO],
+            "A zero base disables the hold-back"
+        );
+    }
+
+    #[test]
+    fn test_witness_targets_skip_sender() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+
+        let targets: Vec<PlayerId> = room
+            .witness_targets(Some(1))
+            .into_iter()
+            .map(|(pid, _, _)| pid)
+            .collect();
+        assert_eq!(targets, vec![2]);
+        assert_eq!(room.witness_targets(None).len(), 2);
+    }
+
+    fn chat_messages(msgs: &[WsMsg]) -> Vec<(PlayerId, &str, &str)> {
+        msgs.iter()
+            .filter_map(|m| match m {
+                WsMsg::ChatMessage {
+                    pid, name, text, ..
+                } => Some((*pid, name.as_str(), text.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chat_broadcast_to_host_and_players() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+
+        let response = room.handle_message(
+            &WsMsg::Chat {
+                text: "  good luck!  ".to_string(),
+            },
+            Some(1),
+        );
+        assert_eq!(
+            chat_messages(&response.messages_to_host),
+            vec![(1, "AJ", "good luck!")]
+        );
+        assert_eq!(
+            chat_messages(&response.messages_to_players),
+            vec![(1, "AJ", "good luck!")]
+        );
+        assert_eq!(
+            chat_messages(&response.messages_to_observers),
+            vec![(1, "AJ", "good luck!")]
+        );
+
+        let response = room.handle_message(
+            &WsMsg::Chat {
+                text: "thanks".to_string(),
+            },
+            None,
+        );
+        assert_eq!(
+            chat_messages(&response.messages_to_players),
+            vec![(0, "Host", "thanks")]
+        );
+    }
+
+    #[test]
+    fn test_chat_truncated_and_empty_ignored() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+
+        let response = room.handle_message(
+            &WsMsg::Chat {
+                text: "é".repeat(MAX_CHAT_LEN + 20),
+            },
+            Some(1),
+        );
+        let chats = chat_messages(&response.messages_to_players);
+        assert_eq!(chats[0].2.chars().count(), MAX_CHAT_LEN);
+
+        let response = room.handle_message(
+            &WsMsg::Chat {
+                text: "   ".to_string(),
+            },
+            Some(1),
+        );
+        assert!(response.messages_to_players.is_empty());
+    }
+
+    #[test]
+    fn test_chat_rate_limited() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        let chat = WsMsg::Chat {
+            text: "hi".to_string(),
+        };
+
+        for _ in 0..CHAT_RATE_LIMIT {
+            let response = room.handle_message(&chat, Some(1));
+            assert_eq!(chat_messages(&response.messages_to_players).len(), 1);
+        }
+
+        let response = room.handle_message(&chat, Some(1));
+        assert!(response.messages_to_players.is_empty());
+        assert!(matches!(
+            response.messages_to_specific.as_slice(),
+            [(1, WsMsg::Notice { .. })]
+        ));
+
+        // The limit is per player
+        let response = room.handle_message(&chat, Some(2));
+        assert_eq!(chat_messages(&response.messages_to_players).len(), 1);
+    }
+
+    #[test]
+    fn test_host_choice_out_of_range_rejected() {
+        let mut room = create_test_room();
+        room.state = GameState::Selection;
+
+        for (category_index, question_index) in [(0, 2), (1, 0), (usize::MAX, usize::MAX)] {
+            let response = room.handle_message(
+                &WsMsg::HostChoice {
+                    category_index,
+                    question_index,
+                },
+                None,
+            );
+
+            assert_eq!(room.state, GameState::Selection);
+            assert_eq!(room.current_question, None);
+            assert!(matches!(
+                response.messages_to_host.as_slice(),
+                [WsMsg::InvalidSelection { .. }]
+            ));
+            assert!(response.messages_to_players.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_host_choice_answered_question_rejected() {
+        let mut room = create_test_room();
+        room.state = GameState::Selection;
+        room.categories[0].questions[0].answered = true;
+
+        let response = room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+            None,
+        );
+
+        assert_eq!(room.state, GameState::Selection);
+        assert_eq!(room.current_question, None);
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::InvalidSelection {
+                category_index: 0,
+                question_index: 0,
+                ..
+            }]
+        ));
+    }
+
+    fn can_buzz(response: &RoomResponse, player_id: PlayerId) -> Option<bool> {
+        response
+            .messages_to_specific
+            .iter()
+            .find_map(|(pid, msg)| match msg {
+                WsMsg::PlayerState { can_buzz, .. } if *pid == player_id => Some(*can_buzz),
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn test_early_buzz_locks_player_out_briefly() {
+        let mut room = reading_room_with_players();
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(2));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.state, GameState::QuestionReading);
+        assert!(room.early_buzz.contains(&1));
+
+        let opened = room.handle_message(&WsMsg::HostReady {}, None);
+        assert_eq!(
+            can_buzz(&opened, 1),
+            Some(false),
+            "Early buzzer is locked out"
+        );
+        assert_eq!(can_buzz(&opened, 2), Some(true));
+        let lockout = opened
+            .timers
+            .iter()
+            .find(|t| t.kind == TimerKind::EarlyBuzzLockout)
+            .expect("Lockout timer should be scheduled")
+            .clone();
+        assert_eq!(lockout.after, EARLY_BUZZ_LOCKOUT);
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.current_buzzer, None, "Locked out buzz is rejected");
+
+        // Pretend the lockout has passed
+        room.buzz_opened_at = Some(Instant::now() - EARLY_BUZZ_LOCKOUT);
+        let released = room.handle_timer(&lockout);
+        assert_eq!(can_buzz(&released, 1), Some(true));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.current_buzzer, Some(1));
+    }
+
+    #[test]
+    fn test_disciplined_players_buzz_immediately() {
+        let mut room = reading_room_with_players();
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(2));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let opened = room.handle_message(&WsMsg::HostReady {}, None);
+        assert_eq!(can_buzz(&opened, 2), Some(true));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        assert_eq!(room.current_buzzer, Some(2));
+    }
+
+    #[test]
+    fn test_buzz_order_reported_to_host() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        add_test_player(&mut room, 3, "Riley");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+
+        assert_eq!(
+            room.current_buzzer,
+            Some(2),
+            "Scoring still uses the first buzz"
+        );
+        assert_eq!(room.buzz_queue.len(), 3, "Every buzz is recorded");
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::BuzzOrder { order }] if *order == vec![2, 1]
+        ));
+
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 1,
+            },
+            None,
+        );
+        assert!(room.buzz_queue.is_empty(), "Queue resets per question");
+    }
+
+    #[test]
+    fn test_wrong_answer_blocks_only_that_player() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let response = room.handle_message(&WsMsg::host_checked(false), None);
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+
+        let states: Vec<(PlayerId, bool, Option<BuzzBlockedReason>)> = response
+            .messages_to_specific
+            .iter()
+            .filter_map(|(pid, msg)| match msg {
+                WsMsg::PlayerState {
+                    can_buzz,
+                    blocked_reason,
+                    ..
+                } => Some((*pid, *can_buzz, *blocked_reason)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                (1, false, Some(BuzzBlockedReason::AlreadyAnswered)),
+                (2, true, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deduct_on_wrong_modes() {
+        // Same events in both modes: AJ misses, Sam gets it
+        let play = |deduct_on_wrong: bool| {
+            let mut room = create_test_room();
+            room.settings.deduct_on_wrong = deduct_on_wrong;
+            room.scoring = room.settings.scoring_strategy();
+            add_test_player(&mut room, 1, "AJ");
+            add_test_player(&mut room, 2, "Sam");
+            room.state = GameState::WaitingForBuzz;
+            room.current_question = Some((0, 0));
+
+            room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+            room.handle_message(&WsMsg::host_checked(false), None);
+            assert_eq!(room.state, GameState::WaitingForBuzz, "Buzz passes along");
+            room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+            room.handle_message(&WsMsg::host_checked(true), None);
+
+            (room.players[0].player.score, room.players[1].player.score)
+        };
+
+        assert_eq!(play(true), (-200, 200));
+        assert_eq!(play(false), (0, 200));
+    }
+
+    #[test]
+    fn test_award_question_to_non_buzzer() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::award_question(2, true), Some(1));
+        assert_eq!(room.players[1].player.score, 0, "Only the host awards");
+
+        let response = room.handle_message(&WsMsg::award_question(2, true), None);
+        assert_eq!(room.state, GameState::AnswerReveal);
+        assert_eq!(room.current_buzzer, Some(2));
+        assert!(room.categories[0].questions[0].answered);
+        assert_eq!(room.players[0].player.score, 0);
+        assert_eq!(room.players[1].player.score, 200);
+        assert!(
+            response
+                .messages_to_players
+                .iter()
+                .any(|m| matches!(m, WsMsg::AnswerRevealed { .. }))
+        );
+
+        let response = room.handle_message(&WsMsg::award_question(2, true), None);
+        assert_eq!(room.players[1].player.score, 200, "Nothing left to award");
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::Notice { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_void_question_after_buzz_leaves_scores_unchanged() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.players[1].player.score = 100;
+        room.state = GameState::Selection;
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+            None,
+        );
+        room.state = GameState::WaitingForBuzz;
+
+        // AJ misses and loses points, then Sam buzzes
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(false), None);
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        assert_eq!(room.state, GameState::Answer);
+        assert_eq!(room.players[0].player.score, -200);
+
+        let response = room.handle_message(&WsMsg::VoidQuestion {}, None);
+        assert_eq!(room.state, GameState::AnswerReveal);
+        assert!(room.categories[0].questions[0].answered);
+        assert_eq!(room.players[0].player.score, 0, "Deduction reversed");
+        assert_eq!(room.players[1].player.score, 100);
+        assert!(response.messages_to_players.iter().any(|m| matches!(
+            m,
+            WsMsg::QuestionVoided {
+                category_index: 0,
+                question_index: 0
+            }
+        )));
+
+        let response = room.handle_message(&WsMsg::VoidQuestion {}, None);
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::Notice { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_streak_bonus() {
+        let mut room = create_test_room();
+        room.settings.streak_bonus = 50;
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+
+        let answer = |room: &mut Room, pid: PlayerId, q_idx: usize, correct: bool| {
+            room.state = GameState::WaitingForBuzz;
+            room.current_question = Some((0, q_idx));
+            room.handle_message(&WsMsg::Buzz { seq: None }, Some(pid));
+            room.handle_message(&WsMsg::host_checked(correct), None);
+            for player in &mut room.players {
+                player.player.buzzed = false;
+            }
+        };
+
+        // 200 + (400 + 2x50) + (200 + 3x50)
+        for q_idx in [0, 1, 0] {
+            answer(&mut room, 1, q_idx, true);
         }
+        assert_eq!(room.players[0].player.streak, 3);
+        assert_eq!(room.players[0].player.score, 1050);
+
+        answer(&mut room, 1, 0, false);
+        assert_eq!(room.players[0].player.streak, 0, "A miss ends the streak");
+        answer(&mut room, 2, 0, true);
+        assert_eq!(room.players[1].player.score, 200, "No bonus for one answer");
+    }
+
+    #[test]
+    fn test_buzzer_leaving_reopens_buzzing() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+
+        let response = room.handle_message(&WsMsg::LeaveRoom {}, Some(1));
+        assert_eq!(room.players.len(), 1);
+        assert_eq!(room.current_buzzer, None);
+        assert_eq!(room.state, GameState::WaitingForBuzz, "Sam can still buzz");
+        assert!(matches!(
+            response.messages_to_host.first(),
+            Some(WsMsg::PlayerList(players)) if players.len() == 1
+        ));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        room.handle_message(&WsMsg::LeaveRoom {}, Some(2));
+        assert_eq!(room.state, GameState::AnswerReveal, "Nobody left to buzz");
+        assert!(room.categories[0].questions[0].answered);
+    }
+
+    #[test]
+    fn test_min_score_floors_deductions() {
+        let mut room = create_test_room();
+        room.settings.min_score = Some(0);
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(false), None);
+        assert_eq!(room.players[0].player.score, 0, "Floored at 0");
+
+        room.players[1].player.score = 100;
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        room.handle_message(&WsMsg::host_checked(false), None);
+        assert_eq!(room.players[1].player.score, 0);
+    }
+
+    fn buzzed_room_with_answer_timeout(action: AnswerTimeoutAction) -> (Room, ScheduledTimer) {
+        let mut room = create_test_room();
+        room.settings.answer_timeout_ms = Some(5_000);
+        room.settings.answer_timeout_action = action;
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let timer = response
+            .timers
+            .iter()
+            .find(|t| t.kind == TimerKind::AnswerTimeout)
+            .expect("Answer timer should start on buzz")
+            .clone();
+        assert_eq!(timer.after, Duration::from_secs(5));
+        (room, timer)
+    }
+
+    #[test]
+    fn test_answer_timeout_rules_incorrect() {
+        let (mut room, timer) = buzzed_room_with_answer_timeout(AnswerTimeoutAction::Incorrect);
+
+        let response = room.handle_timer(&timer);
+
+        assert!(matches!(
+            response.messages_to_players.first(),
+            Some(WsMsg::AnswerTimeout { pid: 1 })
+        ));
+        assert_eq!(room.players[0].player.score, -200);
+        assert_eq!(room.state, GameState::WaitingForBuzz, "Sam can still buzz");
+    }
+
+    #[test]
+    fn test_answer_timeout_can_skip() {
+        let (mut room, timer) = buzzed_room_with_answer_timeout(AnswerTimeoutAction::Skip);
+
+        room.handle_timer(&timer);
+
+        assert_eq!(room.players[0].player.score, 0);
+        assert_eq!(room.state, GameState::AnswerReveal);
+        assert!(room.categories[0].questions[0].answered);
+    }
+
+    #[test]
+    fn test_host_ruling_cancels_answer_timeout() {
+        let (mut room, timer) = buzzed_room_with_answer_timeout(AnswerTimeoutAction::Incorrect);
+
+        room.handle_message(&WsMsg::host_checked(true), None);
+        let response = room.handle_timer(&timer);
+
+        assert!(response.messages_to_players.is_empty());
+        assert_eq!(room.players[0].player.score, 200);
+        assert_eq!(room.state, GameState::AnswerReveal);
+    }
+
+    #[test]
+    fn test_answer_timeout_disabled_by_default() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
 
-        let test_cases = vec![
-            TestCase {
-                name: "StartGame transitions to Selection",
-                initial_state: GameState::Start,
-                setup: |_| {},
-                message: WsMsg::StartGame {},
-                sender_id: None,
-                expected_state: GameState::Selection,
-                assertions: |_| {},
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert!(response.timers.is_empty());
+    }
+
+    fn host_choice_auto_ready_timer(room: &mut Room) -> ScheduledTimer {
+        let response = room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
             },
-            TestCase {
-                name: "HostChoice transitions to QuestionReading",
-                initial_state: GameState::Selection,
-                setup: |_| {},
-                message: WsMsg::HostChoice {
-                    category_index: 0,
-                    question_index: 0,
-                },
-                sender_id: None,
-                expected_state: GameState::QuestionReading,
-                assertions: |room| {
-                    assert_eq!(room.current_question, Some((0, 0)));
-                    assert_eq!(room.current_buzzer, None);
-                },
+            None,
+        );
+        response
+            .timers
+            .iter()
+            .find(|t| t.kind == TimerKind::AutoReady)
+            .expect("Choosing a question should schedule the auto ready")
+            .clone()
+    }
+
+    #[test]
+    fn test_auto_ready_opens_buzzing_without_host_ready() {
+        let mut room = create_test_room();
+        room.settings.auto_ready_delay_ms = Some(4_000);
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Selection;
+
+        let timer = host_choice_auto_ready_timer(&mut room);
+        assert_eq!(timer.after, Duration::from_secs(4));
+        assert_eq!(room.state, GameState::QuestionReading);
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+
+        let response = room.handle_timer(&timer);
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert!(
+            response.messages_to_players.iter().any(|m| matches!(
+                m,
+                WsMsg::GameState {
+                    state: GameState::WaitingForBuzz,
+                    ..
+                }
+            )),
+            "Players should hear buzzing is open"
+        );
+    }
+
+    #[test]
+    fn test_manual_host_ready_cancels_auto_ready() {
+        let mut room = create_test_room();
+        room.settings.auto_ready_delay_ms = Some(4_000);
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Selection;
+
+        let timer = host_choice_auto_ready_timer(&mut room);
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        room.handle_message(&WsMsg::HostReady {}, None);
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+
+        let response = room.handle_timer(&timer);
+        assert!(response.messages_to_players.is_empty());
+        assert_eq!(room.state, GameState::Answer);
+    }
+
+    /// AJ buzzes and answers wrong in a room with a 3 second steal window.
+    fn room_in_steal_window() -> (Room, ScheduledTimer) {
+        let mut room = create_test_room();
+        room.settings.steal_window_ms = Some(3_000);
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let response = room.handle_message(&WsMsg::host_checked(false), None);
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert!(
+            response
+                .messages_to_players
+                .iter()
+                .any(|m| matches!(m, WsMsg::StealWindowOpen { duration_ms: 3_000 }))
+        );
+        let timer = response
+            .timers
+            .iter()
+            .find(|t| t.kind == TimerKind::StealWindow)
+            .expect("Steal window should start on a wrong answer")
+            .clone();
+        assert_eq!(timer.after, Duration::from_secs(3));
+        (room, timer)
+    }
+
+    #[test]
+    fn test_steal_within_window() {
+        let (mut room, timer) = room_in_steal_window();
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        assert_eq!(room.current_buzzer, Some(2));
+        let response = room.handle_timer(&timer);
+        assert!(response.messages_to_players.is_empty());
+        assert_eq!(room.state, GameState::Answer, "Sam's steal stands");
+
+        room.handle_message(&WsMsg::host_checked(true), None);
+        assert_eq!(room.players[1].player.score, 200);
+    }
+
+    #[test]
+    fn test_steal_window_times_out() {
+        let (mut room, timer) = room_in_steal_window();
+
+        let response = room.handle_timer(&timer);
+        assert_eq!(room.state, GameState::AnswerReveal);
+        assert!(room.categories[0].questions[0].answered);
+        assert!(
+            response
+                .messages_to_players
+                .iter()
+                .any(|m| matches!(m, WsMsg::AnswerRevealed { .. }))
+        );
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        assert_eq!(room.current_buzzer, None, "Too late to steal");
+    }
+
+    #[test]
+    fn test_no_steal_window_by_default() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let response = room.handle_message(&WsMsg::host_checked(false), None);
+        assert!(response.timers.is_empty());
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+    }
+
+    /// AJ and Sam are answering a poll question worth 400.
+    fn polling_room() -> Room {
+        let mut room = create_test_room();
+        room.categories[0].questions[1].mode = QuestionMode::Poll;
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::Selection;
+
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 1,
             },
-            TestCase {
-                name: "HostChoice resets player buzz states",
-                initial_state: GameState::Selection,
-                setup: |room| {
-                    add_test_player(room, 1, "AJ");
-                    add_test_player(room, 1, "Sam");
-                    room.players[0].player.buzzed = true;
-                    room.players[1].player.buzzed = true;
-                },
-                message: WsMsg::HostChoice {
-                    category_index: 0,
-                    question_index: 0,
-                },
-                sender_id: None,
-                expected_state: GameState::QuestionReading,
-                assertions: |room| {
-                    assert!(!room.players[0].player.buzzed);
-                    assert!(!room.players[1].player.buzzed);
-                },
+            None,
+        );
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(2));
+        room.handle_message(&WsMsg::HostReady {}, None);
+        assert_eq!(room.state, GameState::Polling, "Polls skip buzzing");
+        room
+    }
+
+    fn submit(room: &mut Room, pid: PlayerId, text: &str) -> RoomResponse {
+        room.handle_message(
+            &WsMsg::SubmitAnswer {
+                text: text.to_string(),
             },
-            TestCase {
-                name: "HostReady transitions to WaitingForBuzz",
-                initial_state: GameState::QuestionReading,
-                setup: |_| {},
-                message: WsMsg::HostReady {},
-                sender_id: None,
-                expected_state: GameState::WaitingForBuzz,
-                assertions: |_| {},
+            Some(pid),
+        )
+    }
+
+    #[test]
+    fn test_poll_collects_and_grades_answers() {
+        let mut room = polling_room();
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.current_buzzer, None, "No buzzing in a poll");
+
+        submit(&mut room, 1, "five");
+        submit(&mut room, 1, " six ");
+        let response = submit(&mut room, 2, "seven");
+        assert!(matches!(
+            &response.messages_to_host[..],
+            [WsMsg::PollResults { answers }]
+                if answers == &[(1, "six".to_string()), (2, "seven".to_string())]
+        ));
+        assert!(
+            response.messages_to_players.is_empty(),
+            "Answers stay private"
+        );
+
+        room.handle_message(
+            &WsMsg::PollChecked {
+                pid: 1,
+                correct: true,
             },
-            TestCase {
-                name: "Player buzz transitions to Answer",
-                initial_state: GameState::WaitingForBuzz,
-                setup: |room| {
-                    add_test_player(room, 1, "AJ");
-                },
-                message: WsMsg::Buzz {},
-                sender_id: Some(1),
-                expected_state: GameState::Answer,
-                assertions: |room| {
-                    assert_eq!(room.current_buzzer, Some(1));
-                    assert!(room.players[0].player.buzzed);
-                },
+            Some(2),
+        );
+        assert_eq!(room.players[0].player.score, 0, "Players can't grade");
+
+        room.handle_message(
+            &WsMsg::PollChecked {
+                pid: 1,
+                correct: true,
             },
-            TestCase {
-                name: "Player cannot buzz twice",
-                initial_state: GameState::WaitingForBuzz,
-                setup: |room| {
-                    add_test_player(room, 1, "AJ");
-                    room.players[0].player.buzzed = true;
-                },
-                message: WsMsg::Buzz {},
-                sender_id: Some(1),
-                expected_state: GameState::WaitingForBuzz,
-                assertions: |room| {
-                    assert_eq!(room.current_buzzer, None);
-                },
+            None,
+        );
+        assert_eq!(room.state, GameState::Polling);
+        submit(&mut room, 1, "changed my mind");
+        assert_eq!(room.poll_answers, [(2, "seven".to_string())]);
+
+        let response = room.handle_message(
+            &WsMsg::PollChecked {
+                pid: 2,
+                correct: false,
+            },
+            None,
+        );
+        assert_eq!(room.players[0].player.score, 400);
+        assert_eq!(room.players[1].player.score, -400);
+        assert_eq!(room.state, GameState::AnswerReveal);
+        assert!(room.categories[0].questions[1].answered);
+        assert!(
+            response
+                .messages_to_players
+                .iter()
+                .any(|m| matches!(m, WsMsg::AnswerRevealed { .. }))
+        );
+    }
+
+    #[test]
+    fn test_auto_grade_awards_matching_answers() {
+        let mut room = polling_room();
+        room.settings.auto_grade = true;
+        room.categories[0].questions[1].answer = "The Moon".to_string();
+
+        submit(&mut room, 1, "moon");
+        assert_eq!(room.players[0].player.score, 400);
+        assert!(room.poll_answers.is_empty(), "Matches need no host ruling");
+
+        submit(&mut room, 2, "Mars");
+        assert_eq!(room.players[1].player.score, 0);
+        assert_eq!(room.poll_answers, [(2, "Mars".to_string())]);
+
+        // The host still rules on answers that didn't match
+        room.handle_message(
+            &WsMsg::PollChecked {
+                pid: 2,
+                correct: true,
+            },
+            None,
+        );
+        assert_eq!(room.players[1].player.score, 400);
+        assert_eq!(room.state, GameState::AnswerReveal);
+    }
+
+    #[test]
+    fn test_host_can_overrule_auto_grade() {
+        let mut room = polling_room();
+        room.settings.auto_grade = true;
+        room.categories[0].questions[1].answer = "The Moon".to_string();
+
+        submit(&mut room, 1, "moon");
+        assert_eq!(room.players[0].player.score, 400);
+        assert_eq!(room.history.len(), 1, "Auto-grading can be undone");
+
+        let award = |correct| WsMsg::award_question(1, correct);
+        room.handle_message(&award(false), None);
+        assert_eq!(room.players[0].player.score, -400, "Overruled as wrong");
+        let rulings = &room.question_stats.last().expect("stats").rulings;
+        assert_eq!(rulings.len(), 1, "The ruling is replaced, not added");
+        assert!(!rulings[0].correct);
+
+        room.handle_message(&award(false), None);
+        assert_eq!(room.players[0].player.score, -400, "Same ruling is a no-op");
+
+        room.handle_message(&WsMsg::Undo {}, None);
+        assert_eq!(room.players[0].player.score, 400);
+        room.handle_message(&WsMsg::Undo {}, None);
+        assert_eq!(room.players[0].player.score, 0);
+        assert_eq!(room.poll_answers, [(1, "moon".to_string())]);
+        assert_eq!(room.state, GameState::Polling);
+    }
+
+    #[test]
+    fn test_host_can_close_poll_early() {
+        let mut room = polling_room();
+        submit(&mut room, 1, "six");
+
+        room.handle_message(&WsMsg::host_skip(), None);
+        assert_eq!(room.state, GameState::AnswerReveal);
+
+        room.handle_message(&WsMsg::host_continue(), None);
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+            None,
+        );
+        assert!(room.poll_answers.is_empty());
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(2));
+        room.handle_message(&WsMsg::HostReady {}, None);
+        assert_eq!(
+            room.state,
+            GameState::WaitingForBuzz,
+            "Buzzer questions still race"
+        );
+    }
+
+    #[test]
+    fn test_earliest_buzz_accounts_for_latency() {
+        assert_eq!(earliest_buzz(&[]), None);
+        // Sam reached the server 20ms later but from 150ms away
+        assert_eq!(earliest_buzz(&[(1, 1_000, 0), (2, 1_020, 150)]), Some(2));
+        assert_eq!(earliest_buzz(&[(1, 1_000, 10), (2, 1_020, 15)]), Some(1));
+        assert_eq!(
+            earliest_buzz(&[(1, 1_050, 50), (2, 1_000, 0)]),
+            Some(1),
+            "Ties go to the first arrival"
+        );
+    }
+
+    #[test]
+    fn test_buzz_window_holds_buzzes_until_it_closes() {
+        let mut room = create_test_room();
+        room.settings.buzz_window_ms = Some(80);
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        let timer = response
+            .timers
+            .iter()
+            .find(|t| t.kind == TimerKind::BuzzWindow)
+            .expect("First buzz opens the window")
+            .clone();
+        assert_eq!(timer.after, Duration::from_millis(80));
+        assert!(
+            response
+                .messages_to_host
+                .iter()
+                .all(|m| !matches!(m, WsMsg::Buzzed { .. }))
+        );
+
+        let response = room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert!(
+            response.timers.is_empty(),
+            "Only the first buzz starts the window"
+        );
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+        assert_eq!(room.current_buzzer, None);
+
+        let response = room.handle_timer(&timer);
+        assert_eq!(room.current_buzzer, Some(2));
+        assert_eq!(room.state, GameState::Answer);
+        assert!(
+            response
+                .messages_to_host
+                .iter()
+                .any(|m| matches!(m, WsMsg::Buzzed { pid: 2, .. }))
+        );
+        assert!(
+            !room.players[0].player.buzzed,
+            "The player who lost the race can still buzz later"
+        );
+    }
+
+    #[test]
+    fn test_multiplier_scales_awarded_value() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::SetMultiplier { factor: 2 }, Some(1));
+        assert_eq!(
+            room.value_multiplier, 1,
+            "Players can't change the multiplier"
+        );
+        room.handle_message(&WsMsg::SetMultiplier { factor: 0 }, None);
+        assert_eq!(room.value_multiplier, 1, "Zero is rejected");
+
+        let response = room.handle_message(&WsMsg::SetMultiplier { factor: 2 }, None);
+        assert!(
+            matches!(
+                response.messages_to_players.as_slice(),
+                [WsMsg::Notice { .. }]
+            ),
+            "Players hear about the new stakes"
+        );
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(true), None);
+        assert_eq!(room.players[0].player.score, 400);
+        assert_eq!(
+            room.categories[0].questions[0].value, 200,
+            "The board itself is unchanged"
+        );
+    }
+
+    #[test]
+    fn test_huge_multiplier_saturates() {
+        assert_eq!(scaled_value(200, 3), 600);
+        assert_eq!(scaled_value(200, u32::MAX), i32::MAX);
+
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        room.handle_message(&WsMsg::SetMultiplier { factor: u32::MAX }, None);
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(false), None);
+        assert_eq!(
+            room.players[0].player.score,
+            -i32::MAX,
+            "A wrong answer costs the most it can, never a wrapped gain"
+        );
+    }
+
+    #[test]
+    fn test_estimate_reading_ms() {
+        assert_eq!(estimate_reading_ms(""), MIN_READING_MS);
+        assert_eq!(estimate_reading_ms("Moon"), MIN_READING_MS);
+        assert_eq!(
+            estimate_reading_ms("This planet is  known as\nthe Red Planet"),
+            8 * READING_MS_PER_WORD
+        );
+        assert!(estimate_reading_ms(&"word ".repeat(40)) > estimate_reading_ms("a few words here"));
+    }
+
+    #[test]
+    fn test_read_question_cue_only_while_reading() {
+        let mut room = create_test_room();
+        room.state = GameState::Selection;
+        assert!(room.read_question_cue().is_none());
+
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+            None,
+        );
+        assert!(matches!(
+            room.read_question_cue(),
+            Some(WsMsg::ReadQuestionNow { text, estimated_duration_ms })
+                if text == "What is 2+2?" && estimated_duration_ms == 3 * READING_MS_PER_WORD
+        ));
+    }
+
+    #[test]
+    fn test_undo_restores_misclicked_ruling() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(true), None);
+        assert_eq!(room.state, GameState::AnswerReveal);
+        assert!(room.categories[0].questions[0].answered);
+
+        let response = room.handle_message(&WsMsg::Undo {}, None);
+
+        assert_eq!(room.state, GameState::Answer);
+        assert_eq!(room.current_buzzer, Some(1));
+        assert_eq!(room.players[0].player.score, 0);
+        assert!(!room.categories[0].questions[0].answered);
+        assert!(matches!(
+            response.messages_to_players.first(),
+            Some(WsMsg::GameState {
+                state: GameState::Answer,
+                ..
+            })
+        ));
+
+        // The host can now rule the way they meant to
+        room.handle_message(&WsMsg::host_checked(false), None);
+        assert_eq!(room.players[0].player.score, -200);
+        assert_eq!(room.state, GameState::WaitingForBuzz);
+    }
+
+    #[test]
+    fn test_undo_steps_back_through_history() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(false), None);
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+        room.handle_message(&WsMsg::host_skip(), None);
+
+        room.handle_message(&WsMsg::Undo {}, None);
+        assert_eq!(room.state, GameState::Answer);
+        assert_eq!(room.current_buzzer, Some(2));
+        assert!(!room.categories[0].questions[0].answered);
+
+        room.handle_message(&WsMsg::Undo {}, None);
+        assert_eq!(room.current_buzzer, Some(1));
+        assert_eq!(room.players[0].player.score, 0);
+        assert!(!room.players[1].player.buzzed, "Sam hadn't buzzed yet");
+
+        let response = room.handle_message(&WsMsg::Undo {}, None);
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::Notice { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_undo_stops_at_the_current_question() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(true), None);
+        room.handle_message(&WsMsg::host_continue(), None);
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 1,
             },
-        ];
+            None,
+        );
 
-        for tc in test_cases {
-            let mut room = create_test_room();
-            room.state = tc.initial_state;
-            (tc.setup)(&mut room);
+        let response = room.handle_message(&WsMsg::Undo {}, None);
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::Notice { .. }]
+        ));
+        assert_eq!(room.state, GameState::QuestionReading);
+        assert_eq!(
+            room.players[0].player.score, 200,
+            "The earlier ruling stands"
+        );
+    }
 
-            room.handle_message(&tc.message, tc.sender_id);
+    #[test]
+    fn test_undo_restores_buzz_queue_and_timers() {
+        let mut room = create_test_room();
+        room.settings.answer_timeout_ms = Some(5_000);
+        room.settings.steal_window_ms = Some(5_000);
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        let queue = room.buzz_queue.clone();
+        room.handle_message(&WsMsg::host_checked(false), None);
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(2));
+
+        let response = room.handle_message(&WsMsg::Undo {}, None);
+        assert_eq!(room.buzz_queue, queue, "Sam's buzz is taken back too");
+        let kinds: Vec<TimerKind> = response.timers.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, [TimerKind::AnswerTimeout]);
+
+        // The steal window the wrong answer opened is no longer running
+        let response = room.handle_message(&WsMsg::host_checked(true), None);
+        assert_eq!(room.state, GameState::AnswerReveal);
+        assert!(response.timers.is_empty());
+    }
 
-            assert_eq!(
-                room.state, tc.expected_state,
-                "Test case failed: {}",
-                tc.name
-            );
-            (tc.assertions)(&room)
+    #[test]
+    fn test_undo_history_is_capped() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.current_question = Some((0, 0));
+
+        for _ in 0..UNDO_DEPTH + 5 {
+            room.state = GameState::Answer;
+            room.current_buzzer = Some(1);
+            room.handle_message(&WsMsg::host_checked(true), None);
         }
+        assert_eq!(room.history.len(), UNDO_DEPTH);
     }
 
     #[test]
-    fn test_scoring() {
-        struct TestCase {
-            name: &'static str,
-            setup: fn(&mut Room),
-            correct: bool,
-            expected_score: i32,
-            expected_state: GameState,
-            question_answered: bool,
-        }
+    fn test_players_cannot_undo() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 0));
+        room.current_buzzer = Some(1);
+        room.handle_message(&WsMsg::host_checked(true), None);
 
-        let test_cases = vec![
-            TestCase {
-                name: "Correct answer awards points",
-                setup: |room| {
-                    add_test_player(room, 1, "AJ");
-                    room.state = GameState::Answer;
-                    room.current_question = Some((0, 0));
-                    room.current_buzzer = Some(1);
-                },
-                correct: true,
-                expected_score: 200,
-                expected_state: GameState::AnswerReveal,
-                question_answered: true,
-            },
-            TestCase {
-                name: "Incorrect answer deducts points",
-                setup: |room| {
-                    add_test_player(room, 1, "AJ");
-                    add_test_player(room, 2, "Sam");
-                    room.state = GameState::Answer;
-                    room.current_question = Some((0, 0));
-                    room.current_buzzer = Some(1);
-                    room.players[0].player.buzzed = true;
-                },
-                correct: false,
-                expected_score: -200,
-                expected_state: GameState::WaitingForBuzz,
-                question_answered: false,
-            },
-            TestCase {
-                name: "All players wrong marks question answered",
-                setup: |room| {
-                    add_test_player(room, 1, "AJ");
-                    add_test_player(room, 2, "Sam");
-                    room.state = GameState::Answer;
-                    room.current_question = Some((0, 0));
-                    room.current_buzzer = Some(1);
-                    room.players[0].player.buzzed = true;
-                    room.players[1].player.buzzed = true;
-                },
-                correct: false,
-                expected_score: -200,
-                expected_state: GameState::AnswerReveal,
-                question_answered: true,
-            },
-            TestCase {
-                name: "Game ends when no questions remain",
-                setup: |room| {
-                    add_test_player(room, 1, "AJ");
-                    room.state = GameState::Answer;
-                    room.categories[0].questions[0].answered = true;
-                    room.current_question = Some((0, 1));
-                    room.current_buzzer = Some(1);
-                },
-                correct: true,
-                expected_score: 400,
-                expected_state: GameState::AnswerReveal,
-                question_answered: true,
-            },
-        ];
+        room.handle_message(&WsMsg::Undo {}, Some(1));
+        assert_eq!(room.state, GameState::AnswerReveal);
+        assert_eq!(room.history.len(), 1);
+    }
 
-        for tc in test_cases {
-            let mut room = create_test_room();
-            (tc.setup)(&mut room);
+    #[test]
+    fn test_duplicate_buzz_seq_ignored() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 0));
 
-            let (cat_idx, q_idx) = room
-                .current_question
-                .expect("Failed to get current question");
+        room.handle_message(&WsMsg::Buzz { seq: Some(1) }, Some(1));
+        room.handle_message(&WsMsg::host_checked(false), None);
+        assert_eq!(room.state, GameState::WaitingForBuzz);
 
-            room.handle_message(
-                &WsMsg::HostChecked {
-                    correct: tc.correct,
-                },
-                None,
-            );
+        // AJ's retransmission lands after Sam's first buzz
+        let response = room.handle_message(&WsMsg::Buzz { seq: Some(1) }, Some(1));
+        assert!(response.messages_to_host.is_empty(), "Duplicate is dropped");
+        assert_eq!(room.buzz_queue.len(), 1, "Duplicate isn't queued either");
 
-            assert_eq!(
-                room.players[0].player.score, tc.expected_score,
-                "Test case failed (score): {}",
-                tc.name
-            );
-            assert_eq!(
-                room.state, tc.expected_state,
-                "Test case failed (state): {}",
-                tc.name
-            );
-            assert_eq!(
-                room.categories[cat_idx].questions[q_idx].answered, tc.question_answered,
-                "Test case failed (answered): {}",
-                tc.name
-            );
-        }
+        room.handle_message(&WsMsg::Buzz { seq: Some(1) }, Some(2));
+        assert_eq!(room.current_buzzer, Some(2));
+        assert_eq!(room.buzz_order(), vec![1, 2]);
     }
 
     #[test]
-    fn test_host_skip_marks_question_answered() {
+    fn test_buzz_without_seq_always_processed() {
         let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Player1");
-
+        add_test_player(&mut room, 1, "AJ");
         room.state = GameState::WaitingForBuzz;
         room.current_question = Some((0, 0));
 
-        room.handle_message(&WsMsg::HostSkip {}, None);
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.buzz_queue.len(), 2);
+
+        let legacy: WsMsg = serde_json::from_str(r#"{"Buzz":{}}"#).expect("Old clients parse");
+        assert!(matches!(legacy, WsMsg::Buzz { seq: None }));
+    }
+
+    #[test]
+    fn test_restore_board_state() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Selection;
 
+        let msg: WsMsg =
+            serde_json::from_str(r#"{"RestoreBoardState":{"answered":[[0,0],[7,0],[0,9]]}}"#)
+                .expect("Should parse");
+        let response = room.handle_message(&msg, None);
+        assert!(matches!(
+            response.messages_to_players.as_slice(),
+            [WsMsg::GameState { .. }]
+        ));
+        assert!(room.categories[0].questions[0].answered);
         assert!(
-            room.categories[0].questions[0].answered,
-            "Skipped question should be marked as answered"
+            !room.categories[0].questions[1].answered,
+            "Out-of-range pairs are ignored"
         );
-        assert_eq!(
-            room.state,
-            GameState::AnswerReveal,
-            "Should transition to AnswerReveal"
+
+        room.handle_message(
+            &WsMsg::RestoreBoardState {
+                answered: vec![(0, 1)],
+            },
+            Some(1),
         );
+        assert!(
+            !room.categories[0].questions[1].answered,
+            "Players can't restore board state"
+        );
+    }
+
+    fn edit_value(value: u32, force: bool) -> WsMsg {
+        WsMsg::EditQuestion {
+            category_index: 0,
+            question_index: 0,
+            question: None,
+            answer: None,
+            value: Some(value),
+            force,
+        }
     }
 
     #[test]
-    fn test_host_skip_transitions_to_selection() {
+    fn test_edited_value_is_awarded() {
         let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Player1");
-
-        room.state = GameState::WaitingForBuzz;
-        room.current_question = Some((0, 0));
-
-        room.handle_message(&WsMsg::HostSkip {}, None);
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Selection;
 
+        let response = room.handle_message(&edit_value(1000, false), None);
+        assert!(matches!(
+            response.messages_to_players.as_slice(),
+            [WsMsg::GameState { .. }]
+        ));
         assert_eq!(
-            room.state,
-            GameState::AnswerReveal,
-            "Should first go to AnswerReveal"
+            room.categories[0].questions[0].answer, "4",
+            "Answer untouched"
         );
 
-        room.handle_message(&WsMsg::HostContinue {}, None);
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+            None,
+        );
+        room.handle_message(&WsMsg::QuestionLoaded {}, Some(1));
+        room.handle_message(&WsMsg::HostReady {}, None);
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        room.handle_message(&WsMsg::host_checked(true), None);
+        assert_eq!(room.players[0].player.score, 1000);
+    }
 
+    #[test]
+    fn test_edit_rejects_overlong_text() {
+        let mut room = create_test_room();
+        room.max_text_chars = 10;
+
+        let response = room.handle_message(
+            &WsMsg::EditQuestion {
+                category_index: 0,
+                question_index: 0,
+                question: Some("x".repeat(11)),
+                answer: None,
+                value: Some(1000),
+                force: false,
+            },
+            None,
+        );
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::InvalidSelection { .. }]
+        ));
         assert_eq!(
-            room.state,
-            GameState::Selection,
-            "Should return to Selection when questions remain"
+            room.categories[0].questions[0].value, 200,
+            "Nothing applied"
+        );
+
+        room.handle_message(
+            &WsMsg::EditQuestion {
+                category_index: 0,
+                question_index: 0,
+                question: None,
+                answer: Some("x".repeat(10)),
+                value: None,
+                force: false,
+            },
+            None,
         );
+        assert_eq!(room.categories[0].questions[0].answer, "x".repeat(10));
     }
 
     #[test]
-    fn test_host_skip_transitions_to_game_end() {
+    fn test_editing_played_question_requires_force() {
         let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Winner");
-        add_test_player(&mut room, 2, "Loser");
+        room.categories[0].questions[0].answered = true;
 
-        room.players[0].player.score = 500;
-        room.players[1].player.score = 200;
+        let response = room.handle_message(&edit_value(1000, false), None);
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::InvalidSelection { .. }]
+        ));
+        assert_eq!(room.categories[0].questions[0].value, 200);
+
+        room.handle_message(&edit_value(1000, true), None);
+        assert_eq!(room.categories[0].questions[0].value, 1000);
+    }
+
+    #[test]
+    fn test_players_cannot_edit_questions() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+
+        room.handle_message(&edit_value(1000, true), Some(1));
+        assert_eq!(room.categories[0].questions[0].value, 200);
+    }
+
+    fn two_board_room() -> Room {
+        let mut room = create_test_room();
+        let mut double = room.categories.clone();
+        double[0].title = "Double".to_string();
+        for question in &mut double[0].questions {
+            question.value *= 2;
+            question.answered = true;
+        }
+        room.boards = vec![room.categories.clone(), double];
+        room
+    }
+
+    #[test]
+    fn test_next_board_resets_and_broadcasts_new_categories() {
+        let mut room = two_board_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Selection;
+        room.categories[0].questions[0].answered = true;
+
+        let response = room.handle_message(&WsMsg::NextBoard {}, None);
+
+        assert_eq!(room.current_board, 1);
+        assert_eq!(room.state, GameState::Selection);
+        assert_eq!(room.categories[0].title, "Double");
+        assert!(
+            room.categories[0].questions.iter().all(|q| !q.answered),
+            "The new board starts unplayed"
+        );
+        assert!(matches!(
+            response.messages_to_players.as_slice(),
+            [WsMsg::GameState { categories, .. }] if categories[0].title == "Double"
+        ));
+    }
 
+    #[test]
+    fn test_next_board_mid_question_drops_buzz_state() {
+        let mut room = two_board_room();
+        add_test_player(&mut room, 1, "AJ");
         room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((0, 1));
+        room.awaiting_acks = true;
+        room.ready_acks.insert(1);
+        room.early_buzz.insert(1);
+        room.buzz_queue.push((1, 0));
+        room.held_buzzes.push((1, 0, 0));
+        room.first_taps.insert(1, Instant::now());
+        room.buzz_opened_at = Some(Instant::now());
+        room.schedule_timer(TimerKind::BuzzWindow, Duration::from_secs(1));
+        room.schedule_timer(TimerKind::AutoReady, Duration::from_secs(1));
+
+        room.handle_message(&WsMsg::NextBoard {}, None);
+
+        assert_eq!(room.current_board, 1);
+        assert!(!room.awaiting_acks);
+        assert!(room.ready_acks.is_empty());
+        assert!(room.early_buzz.is_empty());
+        assert!(room.buzz_queue.is_empty());
+        assert!(room.held_buzzes.is_empty());
+        assert!(room.first_taps.is_empty());
+        assert!(room.buzz_opened_at.is_none());
+        assert!(room.timers.is_empty(), "No question timers carry over");
+    }
+
+    #[test]
+    fn test_cleared_board_waits_for_next_board() {
+        let mut room = two_board_room();
+        add_test_player(&mut room, 1, "AJ");
         room.categories[0].questions[0].answered = true;
-        room.current_question = Some((0, 1)); // Last question
+        room.state = GameState::Answer;
+        room.current_question = Some((0, 1));
+        room.current_buzzer = Some(1);
+
+        room.handle_message(&WsMsg::host_checked(true), None);
+        let response = room.handle_message(&WsMsg::host_continue(), None);
+
+        assert_eq!(room.state, GameState::Selection);
+        assert!(matches!(
+            response.messages_to_host.last(),
+            Some(WsMsg::Notice { .. })
+        ));
+    }
+
+    #[test]
+    fn test_next_board_on_last_board_ends_game() {
+        let mut room = two_board_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.players[0].player.score = 400;
+        room.current_board = 1;
+        room.state = GameState::Selection;
+
+        room.handle_message(&WsMsg::NextBoard {}, None);
+
+        assert_eq!(room.state, GameState::GameEnd);
+        assert_eq!(room.winner, Some(1));
+    }
+
+    #[test]
+    fn test_single_board_room_has_no_next_board() {
+        let mut room = create_test_room();
+        room.state = GameState::Selection;
+
+        room.handle_message(&WsMsg::NextBoard {}, Some(1));
+        assert_eq!(room.state, GameState::Selection, "Players can't advance");
+
+        room.handle_message(&WsMsg::NextBoard {}, None);
+        assert_eq!(room.state, GameState::GameEnd);
+        assert_eq!(room.current_board, 0);
+    }
+
+    #[test]
+    fn test_random_question_skips_answered_questions() {
+        for seed in 0..50 {
+            let mut room = create_test_room();
+            add_test_player(&mut room, 1, "AJ");
+            room.state = GameState::Selection;
+            room.categories[0].questions[0].answered = true;
+
+            room.handle_message(&WsMsg::RandomQuestion { seed: Some(seed) }, None);
+
+            assert_eq!(room.current_question, Some((0, 1)), "Seed {}", seed);
+            assert_eq!(room.state, GameState::QuestionReading);
+        }
+
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Selection;
+        let response = room.handle_message(&WsMsg::RandomQuestion { seed: None }, Some(1));
+        assert_eq!(room.state, GameState::Selection, "Only the host can pick");
+        assert!(response.messages_to_players.is_empty());
+
+        for question in &mut room.categories[0].questions {
+            question.answered = true;
+        }
+        room.handle_message(&WsMsg::RandomQuestion { seed: None }, None);
+        assert_eq!(room.state, GameState::GameEnd);
+    }
+
+    #[test]
+    fn test_random_question_moves_on_to_the_next_board() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.boards = vec![room.categories.clone(), room.categories.clone()];
+        room.state = GameState::Selection;
+        for question in &mut room.categories[0].questions {
+            question.answered = true;
+        }
 
-        room.handle_message(&WsMsg::HostSkip {}, None);
+        room.handle_message(&WsMsg::RandomQuestion { seed: Some(7) }, None);
+        assert_eq!(room.current_board, 1);
+        assert_eq!(room.state, GameState::QuestionReading);
+        assert!(room.current_question.is_some());
+    }
+
+    #[test]
+    fn test_shuffle_board_is_seeded_and_pre_game_only() {
+        let shuffled = |seed: u64| {
+            let mut room = create_test_room();
+            let template = room.categories[0].clone();
+            room.categories = (0..8)
+                .map(|n| Category {
+                    title: format!("Category {}", n),
+                    ..template.clone()
+                })
+                .collect();
+            room.handle_message(
+                &WsMsg::ShuffleBoard {
+                    seed: Some(seed),
+                    shuffle_questions: false,
+                },
+                None,
+            );
+            room.categories
+                .iter()
+                .map(|c| c.title.clone())
+                .collect::<Vec<_>>()
+        };
 
+        assert_eq!(shuffled(7), shuffled(7), "Same seed, same order");
+        let mut titles = shuffled(7);
+        titles.sort();
         assert_eq!(
-            room.state,
-            GameState::AnswerReveal,
-            "Should first go to AnswerReveal"
+            titles,
+            (0..8)
+                .map(|n| format!("Category {}", n))
+                .collect::<Vec<_>>()
         );
 
-        room.handle_message(&WsMsg::HostContinue {}, None);
-
-        assert_eq!(
-            room.state,
-            GameState::GameEnd,
-            "Should transition to GameEnd when no questions remain"
+        let mut room = create_test_room();
+        room.state = GameState::Selection;
+        let before = room.categories[0].questions.clone();
+        let response = room.handle_message(
+            &WsMsg::ShuffleBoard {
+                seed: Some(7),
+                shuffle_questions: true,
+            },
+            None,
         );
         assert_eq!(
-            room.winner,
-            Some(1),
-            "Should determine winner when game ends"
+            room.categories[0]
+                .questions
+                .iter()
+                .map(|q| &q.question)
+                .collect::<Vec<_>>(),
+            before.iter().map(|q| &q.question).collect::<Vec<_>>(),
+            "Too late to shuffle"
         );
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::Notice { .. }]
+        ));
     }
 
     #[test]
-    fn test_host_skip_resets_buzz_states() {
+    fn test_reveal_all_only_after_game_end() {
         let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Player1");
-        add_test_player(&mut room, 2, "Player2");
-
-        room.state = GameState::WaitingForBuzz;
-        room.current_question = Some((0, 0));
-        room.players[0].player.buzzed = true;
-        room.players[1].player.buzzed = true;
-        room.current_buzzer = Some(1);
-
-        room.handle_message(&WsMsg::HostSkip {}, None);
-        room.handle_message(&WsMsg::HostContinue {}, None);
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Selection;
+        let reveal = WsMsg::RevealAll { to_players: true };
 
+        let response = room.handle_message(&reveal, None);
+        assert!(matches!(
+            response.messages_to_host.as_slice(),
+            [WsMsg::Notice { .. }]
+        ));
         assert!(
-            !room.players[0].player.buzzed,
-            "Player 1 buzz state should be reset"
+            response.messages_to_players.is_empty(),
+            "No answers mid-game"
         );
+
+        room.handle_message(&WsMsg::EndGame {}, None);
         assert!(
-            !room.players[1].player.buzzed,
-            "Player 2 buzz state should be reset"
+            room.handle_message(&reveal, Some(1))
+                .messages_to_players
+                .is_empty()
         );
+        let response = room.handle_message(&reveal, None);
+        match response.messages_to_players.as_slice() {
+            [WsMsg::AllAnswers { board }] => {
+                assert_eq!(
+                    board[0].questions[0].answer,
+                    room.categories[0].questions[0].answer
+                );
+            }
+            other => panic!("Expected AllAnswers, got {other:?}"),
+        }
+        let host_only = room.handle_message(&WsMsg::RevealAll { to_players: false }, None);
+        assert!(host_only.messages_to_players.is_empty());
+        assert!(matches!(
+            host_only.messages_to_host.as_slice(),
+            [WsMsg::AllAnswers { .. }]
+        ));
     }
 
     #[test]
-    fn test_host_skip_does_not_affect_scores() {
-        let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Player1");
+    fn test_rejoining_after_mid_list_leave_gets_fresh_id() {
+        use crate::handshake::reserve_player_slot;
 
-        room.state = GameState::WaitingForBuzz;
-        room.current_question = Some((0, 0));
-        room.players[0].player.score = 100;
+        let mut room = create_test_room();
+        let pids: Vec<PlayerId> = ["AJ", "Sam", "Charlie"]
+            .into_iter()
+            .map(|name| {
+                reserve_player_slot(&mut room, name)
+                    .expect("Room has space")
+                    .0
+            })
+            .collect();
 
-        room.handle_message(&WsMsg::HostSkip {}, None);
+        // Sam leaves from the middle of the list
+        room.players.retain(|p| p.player.pid != pids[1]);
+        let (new_pid, _) = reserve_player_slot(&mut room, "Dana").expect("Room has space");
 
-        assert_eq!(
-            room.players[0].player.score, 100,
-            "Skipping should not affect player scores"
+        assert!(
+            !pids.contains(&new_pid),
+            "Ids of past players are never reused"
         );
     }
 
     #[test]
-    fn test_host_skip_without_current_question() {
-        let mut room = create_test_room();
+    fn test_issued_ids_survive_snapshot() {
+        use crate::{handshake::reserve_player_slot, persist::SavedRoom};
 
-        room.state = GameState::Selection;
-        room.current_question = None;
-
-        let response = room.handle_message(&WsMsg::HostSkip {}, None);
+        let mut room = create_test_room();
+        let (left, _) = reserve_player_slot(&mut room, "AJ").expect("Room has space");
+        room.players.clear();
 
-        assert_eq!(
-            room.state,
-            GameState::Selection,
-            "State should not change when there's no current question"
-        );
-        assert_eq!(
-            response.messages_to_host.len(),
-            0,
-            "Should return empty response when there's no current question"
-        );
+        let restored = Room::from(SavedRoom::from(&room));
+        assert!(restored.issued_pids.contains(&left));
     }
 
     #[test]
-    fn test_answer_reveal_after_correct() {
+    fn test_reset_game_clears_scores_and_board() {
         let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Player1");
-
+        add_test_player(&mut room, 1, "AJ");
+        add_test_player(&mut room, 2, "Sam");
         room.state = GameState::Answer;
         room.current_question = Some((0, 0));
         room.current_buzzer = Some(1);
+        room.players[1].player.buzzed = true;
+        room.handle_message(&WsMsg::host_checked(true), None);
+        room.categories[0].questions[1].answered = true;
+        room.handle_message(&WsMsg::host_continue(), None);
+        assert_eq!(room.state, GameState::GameEnd);
+        assert_eq!(room.winner, Some(1));
 
-        // Host marks answer correct
-        room.handle_message(&WsMsg::HostChecked { correct: true }, None);
-
-        assert_eq!(
-            room.state,
-            GameState::AnswerReveal,
-            "Should transition to AnswerReveal after correct answer"
-        );
-        assert_eq!(room.players[0].player.score, 200, "Score should be updated");
-
-        // Host continues
-        room.handle_message(&WsMsg::HostContinue {}, None);
-
-        assert_eq!(
-            room.state,
-            GameState::Selection,
-            "Should transition to Selection after continue"
-        );
-        assert_eq!(
-            room.current_question, None,
-            "Current question should be cleared"
-        );
-        assert_eq!(
-            room.current_buzzer, None,
-            "Current buzzer should be cleared"
-        );
+        let response = room.handle_message(&WsMsg::ResetGame {}, None);
+
+        assert_eq!(room.state, GameState::Start);
+        assert!(room.players.iter().all(|p| p.player.score == 0));
+        assert!(room.players.iter().all(|p| !p.player.buzzed));
+        assert!(room.categories[0].questions.iter().all(|q| !q.answered));
+        assert_eq!(room.current_question, None);
+        assert_eq!(room.current_buzzer, None);
+        assert_eq!(room.winner, None);
+        assert_eq!(room.players[0].player.pid, 1, "Players keep their ids");
+        assert!(matches!(
+            response.messages_to_players.as_slice(),
+            [WsMsg::GameState {
+                state: GameState::Start,
+                ..
+            }]
+        ));
     }
 
     #[test]
-    fn test_answer_reveal_after_all_incorrect() {
+    fn test_players_cannot_reset_game() {
         let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Player1");
-        add_test_player(&mut room, 2, "Player2");
+        add_test_player(&mut room, 1, "AJ");
+        room.players[0].player.score = 200;
 
-        room.state = GameState::Answer;
-        room.current_question = Some((0, 0));
-        room.current_buzzer = Some(1);
-        room.players[0].player.buzzed = true;
-        room.players[1].player.buzzed = true; // All players have buzzed
+        room.handle_message(&WsMsg::ResetGame {}, Some(1));
+        assert_eq!(room.players[0].player.score, 200);
+    }
 
-        // Host marks answer incorrect
-        room.handle_message(&WsMsg::HostChecked { correct: false }, None);
+    #[tokio::test]
+    async fn test_host_grace_pauses_game() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Selection;
 
-        assert_eq!(
-            room.state,
-            GameState::AnswerReveal,
-            "Should transition to AnswerReveal when all players buzzed incorrectly"
-        );
-        assert_eq!(
-            room.players[0].player.score, -200,
-            "Score should be deducted"
+        let timers = room.disconnect_host().await;
+        let [timer] = timers.as_slice() else {
+            panic!("Expected one grace timer, got {:?}", timers);
+        };
+        assert_eq!(timer.kind, TimerKind::HostGrace);
+        assert_eq!(timer.after, Duration::from_secs(30));
+
+        let response = room.handle_timer(timer);
+        assert_eq!(room.state, GameState::Paused);
+        assert_eq!(room.paused_from, Some(GameState::Selection));
+        assert!(
+            response
+                .messages_to_players
+                .iter()
+                .any(|m| matches!(m, WsMsg::Notice { .. }))
         );
+    }
 
-        // Host continues
-        room.handle_message(&WsMsg::HostContinue {}, None);
+    #[tokio::test]
+    async fn test_host_back_within_grace_changes_nothing() {
+        let mut room = create_test_room();
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::Selection;
 
-        assert_eq!(
-            room.state,
-            GameState::Selection,
-            "Should transition to Selection after continue"
-        );
+        let timers = room.disconnect_host().await;
+        room.reconnect_host().await;
+
+        for timer in &timers {
+            room.handle_timer(timer);
+        }
+        assert_eq!(room.state, GameState::Selection);
+        assert!(!room.host_away);
     }
 
     #[test]
-    fn test_answer_reveal_after_skip() {
+    fn test_game_state_broadcast_is_split_for_host_and_players() {
         let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Player1");
+        add_test_player(&mut room, 1, "AJ");
 
-        room.state = GameState::WaitingForBuzz;
-        room.current_question = Some((0, 0));
-        room.players[0].player.score = 100;
+        let response = room.handle_message(&WsMsg::StartGame {}, None);
 
-        // Host skips question
-        room.handle_message(&WsMsg::HostSkip {}, None);
+        let answers = |msg: &WsMsg| match msg {
+            WsMsg::GameState { categories, .. } => categories
+                .iter()
+                .flat_map(|c| &c.questions)
+                .map(|q| q.answer.clone())
+                .collect::<Vec<_>>(),
+            other => panic!("Expected GameState, got {:?}", other),
+        };
+        assert_eq!(answers(&response.messages_to_host[0]), ["4", "6"]);
+        assert_eq!(answers(&response.messages_to_players[0]), ["", ""]);
+        assert_eq!(answers(&response.messages_to_observers[0]), ["", ""]);
 
-        assert_eq!(
-            room.state,
-            GameState::AnswerReveal,
-            "Should transition to AnswerReveal after skip"
-        );
-        assert_eq!(
-            room.players[0].player.score, 100,
-            "Score should not change after skip"
-        );
-        assert!(
-            room.categories[0].questions[0].answered,
-            "Question should be marked as answered"
-        );
+        let json =
+            serde_json::to_string(&response.messages_to_players[0]).expect("Failed to serialize");
+        assert!(!json.contains("\"answer\":"), "{}", json);
+    }
 
-        // Host continues
-        room.handle_message(&WsMsg::HostContinue {}, None);
+    #[tokio::test]
+    async fn test_stuck_player_does_not_block_dispatch() {
+        use tokio_mpmc::channel;
 
-        assert_eq!(
-            room.state,
-            GameState::Selection,
-            "Should transition to Selection after continue"
+        let mut room = create_test_room();
+        room.state = GameState::Selection;
+        // AJ's client stopped reading and their one-message buffer fills up
+        let (stuck_tx, _stuck_rx) = channel(1);
+        room.players.push(PlayerEntry::new(
+            Player::new(1, "AJ".to_string(), 0, false, "token".to_string()),
+            stuck_tx,
+        ));
+        let (player_tx, player_rx) = channel(32);
+        room.players.push(PlayerEntry::new(
+            Player::new(2, "Sam".to_string(), 0, false, "token".to_string()),
+            player_tx,
+        ));
+
+        let select = WsMsg::HostChoice {
+            category_index: 0,
+            question_index: 0,
+        };
+        timeout(SEND_TIMEOUT * 3, room.update(&select, None))
+            .await
+            .expect("Dispatch should finish despite AJ")
+            .expect("update");
+
+        assert!(matches!(
+            room.players[0].status,
+            ConnectionStatus::Disconnected
+        ));
+        assert!(matches!(
+            room.players[1].status,
+            ConnectionStatus::Connected
+        ));
+        assert!(!player_rx.is_empty(), "Sam still hears about the question");
+        assert!(
+            room.players[0].sender.is_closed(),
+            "AJ's connection is dropped so their client reconnects"
         );
     }
 
-    #[test]
-    fn test_answer_reveal_to_game_end() {
+    #[tokio::test]
+    async fn test_stuck_host_does_not_stall_dispatch() {
+        use tokio_mpmc::channel;
+
         let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Winner");
-        add_test_player(&mut room, 2, "Loser");
+        room.state = GameState::Selection;
+        let (stuck_tx, _stuck_rx) = channel(1);
+        stuck_tx.send(WsMsg::RequestState {}).await.expect("send");
+        room.hosts.push(HostEntry::new(0, stuck_tx));
+        let (host_tx, host_rx) = channel(32);
+        room.hosts.push(HostEntry::new(0, host_tx));
+
+        let select = WsMsg::HostChoice {
+            category_index: 0,
+            question_index: 0,
+        };
+        timeout(SEND_TIMEOUT / 4, room.update(&select, None))
+            .await
+            .expect("A stuck co-host must not hold up dispatch")
+            .expect("update");
+        assert!(!host_rx.is_empty(), "The other host still hears about it");
+    }
 
-        room.players[0].player.score = 500;
-        room.players[1].player.score = 200;
+    #[tokio::test]
+    async fn test_burst_of_broadcasts_reaches_prompt_player() {
+        use tokio_mpmc::channel;
 
-        room.state = GameState::Answer;
-        room.categories[0].questions[0].answered = true; // First question already answered
-        room.current_question = Some((0, 1)); // Last question
-        room.current_buzzer = Some(1);
+        const BURST: usize = 500;
+        let mut room = create_test_room();
+        let (player_tx, player_rx) = channel(crate::DEFAULT_CHANNEL_CAPACITY);
+        room.players.push(PlayerEntry::new(
+            Player::new(1, "AJ".to_string(), 0, false, "token".to_string()),
+            player_tx,
+        ));
+        let reader = tokio::spawn(async move {
+            let mut notices = Vec::new();
+            while notices.len() < BURST {
+                if let Ok(Some(WsMsg::Envelope { event, .. })) = player_rx.recv().await
+                    && let WsMsg::Notice { message } = *event
+                {
+                    notices.push(message);
+                }
+            }
+            notices
+        });
+
+        for n in 0..BURST {
+            let notice = WsMsg::Notice {
+                message: n.to_string(),
+            };
+            room.dispatch(RoomResponse::to_players_and_observers(notice))
+                .await;
+        }
 
-        // Host marks answer correct
-        room.handle_message(&WsMsg::HostChecked { correct: true }, None);
+        let notices = timeout(SEND_TIMEOUT, reader)
+            .await
+            .expect("Every notice should arrive")
+            .expect("reader");
+        let expected: Vec<String> = (0..BURST).map(|n| n.to_string()).collect();
+        assert_eq!(notices, expected);
+        assert!(matches!(
+            room.players[0].status,
+            ConnectionStatus::Connected
+        ));
+    }
 
+    #[tokio::test]
+    async fn test_each_player_sees_contiguous_seqs() {
+        use tokio_mpmc::channel;
+
+        let mut room = create_test_room();
+        let (host_tx, _host_rx) = channel(32);
+        room.hosts.push(HostEntry::new(0, host_tx));
+        let (player_tx, player_rx) = channel(32);
+        room.players.push(PlayerEntry::new(
+            Player::new(1, "AJ".to_string(), 0, false, "token".to_string()),
+            player_tx,
+        ));
+        let (other_tx, _other_rx) = channel(32);
+        room.players.push(PlayerEntry::new(
+            Player::new(2, "Bo".to_string(), 0, false, "token2".to_string()),
+            other_tx,
+        ));
+
+        let notice = |n: usize| WsMsg::Notice {
+            message: n.to_string(),
+        };
+        room.dispatch(RoomResponse::to_host(notice(0))).await;
+        room.dispatch(RoomResponse::to_players_and_observers(notice(1)))
+            .await;
+        room.dispatch(RoomResponse::to_player(2, notice(2))).await;
+        room.dispatch(RoomResponse::to_observers(notice(3))).await;
+        room.dispatch(RoomResponse::to_player(1, notice(4))).await;
+        room.dispatch(RoomResponse::to_players_and_observers(notice(5)))
+            .await;
+
+        let mut seqs = Vec::new();
+        while !player_rx.is_empty() {
+            if let Ok(Some(WsMsg::Envelope { seq, .. })) = player_rx.recv().await {
+                seqs.push(seq);
+            }
+        }
+        assert_eq!(seqs, [1, 2, 3], "Messages for others leave no gaps");
         assert_eq!(
-            room.state,
-            GameState::AnswerReveal,
-            "Should transition to AnswerReveal"
+            room.players[0].events.since(1).len(),
+            2,
+            "Direct messages replay too"
         );
+        assert_eq!(room.host_events.last_seq(), 1);
+    }
 
-        // Host continues from last question
-        room.handle_message(&WsMsg::HostContinue {}, None);
+    #[tokio::test]
+    async fn test_dispatch_redacts_answers_for_everyone_but_the_host() {
+        use tokio_mpmc::channel;
 
-        assert_eq!(
-            room.state,
-            GameState::GameEnd,
-            "Should transition to GameEnd when no questions remain"
+        let mut room = create_test_room();
+        let (host_tx, host_rx) = channel(8);
+        room.hosts.push(HostEntry::new(0, host_tx));
+        let (player_tx, player_rx) = channel(8);
+        room.players.push(PlayerEntry::new(
+            Player::new(1, "AJ".to_string(), 0, false, "token".to_string()),
+            player_tx,
+        ));
+        let (spectator_tx, spectator_rx) = channel(8);
+        room.spectators.push(spectator_tx);
+
+        // A handler mistakenly sending the host's copy to everyone
+        let full = room.build_game_state_msg();
+        room.dispatch(RoomResponse::broadcast_state(full.clone()))
+            .await;
+        room.dispatch(RoomResponse::to_player(1, full)).await;
+
+        async fn answers(rx: &tokio_mpmc::Receiver<WsMsg>) -> Vec<String> {
+            let mut answers = Vec::new();
+            while !rx.is_empty() {
+                if let Ok(Some(WsMsg::Envelope { event, .. })) = rx.recv().await
+                    && let WsMsg::GameState { categories, .. } = *event
+                {
+                    answers.extend(
+                        categories
+                            .into_iter()
+                            .flat_map(|c| c.questions)
+                            .map(|q| q.answer),
+                    );
+                }
+            }
+            answers
+        }
+        assert!(answers(&host_rx).await.iter().all(|a| !a.is_empty()));
+        let player_answers = answers(&player_rx).await;
+        assert!(!player_answers.is_empty());
+        assert!(player_answers.iter().all(String::is_empty));
+        let spectator_answers = answers(&spectator_rx).await;
+        assert!(!spectator_answers.is_empty());
+        assert!(spectator_answers.iter().all(String::is_empty));
+    }
+
+    #[tokio::test]
+    async fn test_answer_hint_only_reaches_host() {
+        use tokio_mpmc::channel;
+
+        let mut room = create_test_room();
+        let (host_tx, host_rx) = channel(32);
+        room.hosts.push(HostEntry::new(0, host_tx));
+        let (player_tx, player_rx) = channel(32);
+        room.players.push(PlayerEntry::new(
+            Player::new(1, "AJ".to_string(), 0, false, "token".to_string()),
+            player_tx,
+        ));
+
+        async fn drain(rx: &tokio_mpmc::Receiver<WsMsg>) -> Vec<String> {
+            let mut hints = Vec::new();
+            while !rx.is_empty() {
+                if let Ok(Some(WsMsg::Envelope { event, .. })) = rx.recv().await
+                    && let WsMsg::AnswerHint { answer } = *event
+                {
+                    hints.push(answer);
+                }
+            }
+            hints
+        }
+
+        let select = WsMsg::HostChoice {
+            category_index: 0,
+            question_index: 1,
+        };
+        room.update(&select, None).await.expect("update");
+        assert_eq!(drain(&host_rx).await, ["6"], "Hint on selecting a question");
+
+        room.update(&WsMsg::QuestionLoaded {}, Some(1))
+            .await
+            .expect("update");
+        room.update(&WsMsg::HostReady {}, None)
+            .await
+            .expect("update");
+        room.update(&WsMsg::Buzz { seq: None }, Some(1))
+            .await
+            .expect("update");
+        assert_eq!(room.state, GameState::Answer);
+        assert_eq!(drain(&host_rx).await, ["6"], "Hint on entering Answer");
+
+        assert!(
+            drain(&player_rx).await.is_empty(),
+            "Players never get the hint"
         );
-        assert_eq!(room.winner, Some(1), "Winner should be determined");
     }
 
     #[test]
-    fn test_incorrect_stays_in_waiting_for_buzz() {
+    fn test_force_state_bypasses_transitions() {
         let mut room = create_test_room();
-        add_test_player(&mut room, 1, "Player1");
-        add_test_player(&mut room, 2, "Player2");
-
+        add_test_player(&mut room, 1, "AJ");
         room.state = GameState::Answer;
-        room.current_question = Some((0, 0));
-        room.current_buzzer = Some(1);
-        room.players[0].player.buzzed = true;
-        room.players[1].player.buzzed = false; // Player 2 hasn't buzzed yet
 
-        // Host marks answer incorrect
-        room.handle_message(&WsMsg::HostChecked { correct: false }, None);
+        room.handle_message(
+            &WsMsg::ForceState {
+                state: GameState::Selection,
+            },
+            Some(1),
+        );
+        assert_eq!(room.state, GameState::Answer, "Players can't force state");
 
-        assert_eq!(
-            room.state,
-            GameState::WaitingForBuzz,
-            "Should stay in WaitingForBuzz when more players can buzz"
+        let response = room.handle_message(
+            &WsMsg::ForceState {
+                state: GameState::Selection,
+            },
+            None,
         );
-        assert_eq!(
-            room.current_buzzer, None,
-            "Current buzzer should be cleared"
+        assert_eq!(room.state, GameState::Selection);
+        assert!(matches!(
+            response.messages_to_players.as_slice(),
+            [WsMsg::GameState {
+                state: GameState::Selection,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_removed_category_recovers_to_selection() {
+        let mut room = create_test_room();
+        let second = room.categories[0].clone();
+        room.categories.push(second);
+        add_test_player(&mut room, 1, "AJ");
+        room.state = GameState::WaitingForBuzz;
+        room.current_question = Some((1, 0));
+        room.handle_message(&WsMsg::Buzz { seq: None }, Some(1));
+        assert_eq!(room.state, GameState::Answer);
+
+        room.categories.remove(1);
+        let response = room.handle_message(&WsMsg::host_checked(true), None);
+        assert_eq!(room.state, GameState::Selection);
+        assert_eq!(room.current_question, None);
+        assert_eq!(room.current_buzzer, None);
+        assert!(!room.players[0].player.buzzed);
+        assert_eq!(room.players[0].player.score, 0, "Nothing left to award");
+        assert!(
+            response
+                .messages_to_players
+                .iter()
+                .any(|m| matches!(m, WsMsg::SelectionCleared {}))
         );
-        assert_eq!(
-            room.current_question,
-            Some((0, 0)),
-            "Current question should remain"
+
+        room.handle_message(
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+            None,
         );
+        assert_eq!(room.state, GameState::QuestionReading, "The game goes on");
     }
 }
@@ -0,0 +1,51 @@
+/// Leading words that don't change an answer ("The Moon" is "moon")
+const ARTICLES: [&str; 3] = ["the", "a", "an"];
+
+/// Reduce an answer to what matters when comparing it: lowercase, without
+/// punctuation, leading articles or extra whitespace.
+pub fn normalize_answer(answer: &str) -> String {
+    let cleaned: String = answer
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c.is_whitespace() {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .to_lowercase();
+    let mut words: Vec<&str> = cleaned.split_whitespace().collect();
+    if words.len() > 1 && ARTICLES.contains(&words[0]) {
+        words.remove(0);
+    }
+    words.join(" ")
+}
+
+/// Whether a submitted answer matches the expected one once both are
+/// normalized. Blank answers never match.
+pub fn answers_match(submitted: &str, expected: &str) -> bool {
+    let submitted = normalize_answer(submitted);
+    !submitted.is_empty() && submitted == normalize_answer(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_answer() {
+        assert_eq!(normalize_answer("  The   Moon! "), "moon");
+        assert_eq!(normalize_answer("Washington, D.C."), "washington d c");
+        assert_eq!(normalize_answer("A"), "a", "A lone article is the answer");
+        assert_eq!(normalize_answer("?!"), "");
+    }
+
+    #[test]
+    fn test_answers_match() {
+        assert!(answers_match("moon", "The Moon"));
+        assert!(answers_match("the moon.", "Moon"));
+        assert!(!answers_match("mars", "The Moon"));
+        assert!(!answers_match("", ""));
+    }
+}
@@ -0,0 +1,411 @@
+use std::{
+    fmt,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use tokio_mpmc::{ChannelError, Sender};
+
+use crate::{
+    ConnectionStatus, WsQuery,
+    game::{GameState, Room},
+    generate_player_token,
+    host::HostEntry,
+    player::{Player, PlayerEntry, PlayerId, player_color},
+    send_player_list_to_host, send_player_list_to_hosts,
+    ws_msg::WsMsg,
+};
+
+/// Who a websocket connection belongs to once the handshake succeeds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuthenticatedUser {
+    Host,
+    Player(PlayerId),
+    /// Watches the game but can't act in it
+    Spectator,
+    /// A big screen showing the board; like a spectator but only gets the
+    /// board and buzzes
+    Display,
+}
+
+impl AuthenticatedUser {
+    /// Sender id passed to `Room::update` for this connection's messages.
+    pub fn player_id(&self) -> Option<PlayerId> {
+        match self {
+            AuthenticatedUser::Host | AuthenticatedUser::Spectator | AuthenticatedUser::Display => {
+                None
+            }
+            AuthenticatedUser::Player(pid) => Some(*pid),
+        }
+    }
+}
+
+/// Reasons a connection is refused. The client receives these as a
+/// `WsMsg::Error` with a stable `code` before the socket is closed.
+#[derive(Debug)]
+pub enum HandshakeError {
+    InvalidToken,
+    MissingCredentials,
+    RoomFull,
+    /// The host locked the room; only existing players may reconnect
+    RoomLocked,
+    PlayerNotFound,
+    Internal(anyhow::Error),
+}
+
+impl HandshakeError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            HandshakeError::InvalidToken => "invalid_token",
+            HandshakeError::MissingCredentials => "missing_credentials",
+            HandshakeError::RoomFull => "room_full",
+            HandshakeError::RoomLocked => "room_locked",
+            HandshakeError::PlayerNotFound => "player_not_found",
+            HandshakeError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn to_msg(&self) -> WsMsg {
+        WsMsg::Error {
+            code: self.code().to_string(),
+            message: self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandshakeError::InvalidToken => write!(f, "Invalid token"),
+            HandshakeError::MissingCredentials => write!(
+                f,
+                "Must provide playerName (new player) or token (reconnect)"
+            ),
+            HandshakeError::RoomFull => write!(f, "Room is full"),
+            HandshakeError::RoomLocked => write!(f, "Room is locked"),
+            HandshakeError::PlayerNotFound => write!(f, "Player could not be found in this room"),
+            // Internal details stay in the server logs
+            HandshakeError::Internal(_) => write!(f, "Internal server error"),
+        }
+    }
+}
+
+impl From<ChannelError> for HandshakeError {
+    fn from(e: ChannelError) -> Self {
+        HandshakeError::Internal(e.into())
+    }
+}
+
+impl From<anyhow::Error> for HandshakeError {
+    fn from(e: anyhow::Error) -> Self {
+        HandshakeError::Internal(e)
+    }
+}
+
+/// Bad-credential handshakes an address may make in a room within
+/// `HANDSHAKE_FAILURE_WINDOW` before its connections are refused.
+pub const MAX_HANDSHAKE_FAILURES: u32 = 5;
+
+/// How long a player slot reserved over HTTP is held for its player to
+/// connect
+pub const RESERVATION_TTL: Duration = Duration::from_secs(2 * 60);
+pub const HANDSHAKE_FAILURE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Failed handshakes from one address since `since`.
+#[derive(Debug)]
+pub struct HandshakeFailures {
+    count: u32,
+    since: Instant,
+}
+
+impl HandshakeFailures {
+    fn expired(&self) -> bool {
+        self.since.elapsed() >= HANDSHAKE_FAILURE_WINDOW
+    }
+}
+
+impl HandshakeError {
+    /// Errors that suggest someone guessing tokens or ids.
+    fn is_credential_failure(&self) -> bool {
+        matches!(
+            self,
+            HandshakeError::InvalidToken | HandshakeError::PlayerNotFound
+        )
+    }
+}
+
+/// Whether `ip` has failed too many handshakes in `room` lately.
+pub(crate) fn handshake_blocked(room: &Room, ip: IpAddr) -> bool {
+    room.failed_handshakes
+        .get(&ip)
+        .is_some_and(|f| !f.expired() && f.count >= MAX_HANDSHAKE_FAILURES)
+}
+
+/// Count a finished handshake from `ip`: bad credentials add to its
+/// failures, success clears them.
+pub(crate) fn record_handshake(
+    room: &mut Room,
+    ip: IpAddr,
+    result: &Result<AuthenticatedUser, HandshakeError>,
+) {
+    match result {
+        Ok(_) => {
+            room.failed_handshakes.remove(&ip);
+        }
+        Err(e) if e.is_credential_failure() => {
+            let failures = room
+                .failed_handshakes
+                .entry(ip)
+                .or_insert(HandshakeFailures {
+                    count: 0,
+                    since: Instant::now(),
+                });
+            if failures.expired() {
+                *failures = HandshakeFailures {
+                    count: 0,
+                    since: Instant::now(),
+                };
+            }
+            failures.count += 1;
+            if failures.count == MAX_HANDSHAKE_FAILURES {
+                tracing::warn!(%ip, "Too many failed handshakes, refusing connections");
+            }
+        }
+        Err(_) => {}
+    }
+}
+
+/// Authenticate a new connection against `room` and register its channel.
+pub(crate) async fn perform_handshake(
+    room: &mut Room,
+    query: &WsQuery,
+    tx: &Sender<WsMsg>,
+) -> Result<AuthenticatedUser, HandshakeError> {
+    let is_host = query.token.as_ref() == Some(&room.host_token);
+
+    tracing::Span::current().record("is_host", is_host);
+
+    if is_host {
+        register_host(room, query.player_id, tx).await
+    } else if query.display {
+        register_display(room, tx).await
+    } else if query.spectator {
+        register_spectator(room, tx).await
+    } else if let (Some(id), Some(token)) = (query.player_id, &query.token) {
+        reconnect_player(room, Some(id), token, tx).await
+    } else if let Some(name) = &query.player_name {
+        register_new_player(room, name, tx).await
+    } else if let Some(token) = &query.token {
+        reconnect_player(room, None, token, tx).await
+    } else {
+        Err(HandshakeError::MissingCredentials)
+    }
+}
+
+async fn register_host(
+    room: &mut Room,
+    player_id: Option<PlayerId>,
+    tx: &Sender<WsMsg>,
+) -> Result<AuthenticatedUser, HandshakeError> {
+    let host = HostEntry::new(player_id.unwrap_or(0), tx.clone());
+    send_player_list_to_host(&host, &room.players).await?;
+
+    tracing::info!("Host connected");
+
+    if room.state != GameState::Start {
+        let players: Vec<Player> = room.players.iter().map(|e| e.player.clone()).collect();
+        let game_state_msg = WsMsg::GameState {
+            state: room.state.clone(),
+            categories: room.categories.clone(),
+            players,
+            current_question: room.current_question,
+            current_buzzer: room.current_buzzer,
+            winner: None,
+            series_standings: room.wins.clone(),
+            turn_token: room.turn_token,
+        };
+        tx.send(game_state_msg).await?;
+        tracing::debug!(state = ?room.state, "Sending game state to reconnecting host");
+    }
+
+    // A host reconnecting from the same device replaces its dropped socket
+    room.hosts.retain(|h| !h.sender.is_closed());
+    room.hosts.push(host);
+    room.reconnect_host().await;
+    Ok(AuthenticatedUser::Host)
+}
+
+async fn register_spectator(
+    room: &mut Room,
+    tx: &Sender<WsMsg>,
+) -> Result<AuthenticatedUser, HandshakeError> {
+    tx.send(room.build_player_game_state_msg()).await?;
+    room.spectators.retain(|s| !s.is_closed());
+    room.spectators.push(tx.clone());
+
+    tracing::info!(count = room.spectators.len(), "Spectator connected");
+    Ok(AuthenticatedUser::Spectator)
+}
+
+async fn register_display(
+    room: &mut Room,
+    tx: &Sender<WsMsg>,
+) -> Result<AuthenticatedUser, HandshakeError> {
+    tx.send(room.build_player_game_state_msg()).await?;
+    room.displays.retain(|d| !d.is_closed());
+    room.displays.push(tx.clone());
+
+    tracing::info!(count = room.displays.len(), "Display connected");
+    Ok(AuthenticatedUser::Display)
+}
+
+/// Reattach a returning player, found by id (if given) and checked against their token.
+async fn reconnect_player(
+    room: &mut Room,
+    player_id: Option<PlayerId>,
+    token: &str,
+    tx: &Sender<WsMsg>,
+) -> Result<AuthenticatedUser, HandshakeError> {
+    let rotate_token = room.settings.rotate_tokens;
+    let existing = match player_id {
+        Some(id) => {
+            let existing = room
+                .players
+                .iter_mut()
+                .find(|p| p.player.pid == id)
+                .ok_or(HandshakeError::PlayerNotFound)?;
+            if existing.player.token != token {
+                return Err(HandshakeError::InvalidToken);
+            }
+            existing
+        }
+        None => room
+            .players
+            .iter_mut()
+            .find(|p| p.player.token == token)
+            .ok_or(HandshakeError::InvalidToken)?,
+    };
+
+    // Update existing player's send channel
+    existing.sender = tx.clone();
+    existing.status = ConnectionStatus::Connected;
+    existing.reserved_at = None;
+    let pid = existing.player.pid;
+
+    tracing::Span::current().record("player_id", pid);
+    tracing::info!("Player reconnected");
+
+    // Only replace the token once the player has the new one, so a failed
+    // send leaves them able to reconnect with the old
+    if rotate_token {
+        let token = crate::generate_player_token();
+        tx.send(WsMsg::TokenRotated {
+            token: token.clone(),
+        })
+        .await?;
+        existing.player.token = token;
+        tracing::debug!("Rotated player token");
+    }
+
+    // The roster is unchanged, so the host isn't sent another PlayerList
+    send_initial_player_state(room, pid, tx).await?;
+    Ok(AuthenticatedUser::Player(pid))
+}
+
+async fn register_new_player(
+    room: &mut Room,
+    requested_name: &str,
+    tx: &Sender<WsMsg>,
+) -> Result<AuthenticatedUser, HandshakeError> {
+    let (new_id, player_token) = reserve_player_slot(room, requested_name)?;
+    tracing::Span::current().record("player_id", new_id);
+
+    let entry = room
+        .players
+        .iter_mut()
+        .find(|p| p.player.pid == new_id)
+        .ok_or(HandshakeError::PlayerNotFound)?;
+    entry.sender = tx.clone();
+    entry.status = ConnectionStatus::Connected;
+    entry.reserved_at = None;
+
+    let new_player_msg = WsMsg::NewPlayer {
+        pid: new_id,
+        token: player_token,
+    };
+    tx.send(new_player_msg).await?;
+    send_initial_player_state(room, new_id, tx).await?;
+
+    send_player_list_to_hosts(&room.hosts, &room.players).await?;
+    Ok(AuthenticatedUser::Player(new_id))
+}
+
+/// What every player connection starts with, whatever the game state: the
+/// board as players see it, then their own `PlayerState`.
+async fn send_initial_player_state(
+    room: &Room,
+    pid: PlayerId,
+    tx: &Sender<WsMsg>,
+) -> Result<(), HandshakeError> {
+    tx.send(room.build_player_game_state_msg()).await?;
+    if let Some(player_state_msg) = room.build_player_state_msg(pid) {
+        tx.send(player_state_msg).await?;
+    }
+    Ok(())
+}
+
+/// Drop slots reserved over HTTP whose player never connected, so abandoned
+/// (or spammed) reservations don't hold the room full.
+fn expire_reservations(room: &mut Room) {
+    room.players.retain(|p| {
+        let expired = p
+            .reserved_at
+            .is_some_and(|at| at.elapsed() >= RESERVATION_TTL);
+        if expired {
+            tracing::info!(player_id = p.player.pid, "Reserved slot expired unused");
+        }
+        !expired
+    });
+}
+
+/// Add a not-yet-connected player to the room and hand back the id and token
+/// they connect with. The slot is held for [`RESERVATION_TTL`].
+pub(crate) fn reserve_player_slot(
+    room: &mut Room,
+    requested_name: &str,
+) -> Result<(PlayerId, String), HandshakeError> {
+    expire_reservations(room);
+    if room.locked {
+        tracing::info!(player_name = %requested_name, "Room locked, rejecting join");
+        return Err(HandshakeError::RoomLocked);
+    }
+    if room.players.len() >= room.settings.max_players {
+        tracing::info!(
+            player_name = %requested_name,
+            max_players = room.settings.max_players,
+            "Room full, rejecting join"
+        );
+        return Err(HandshakeError::RoomFull);
+    }
+
+    let name = room.unique_player_name(requested_name);
+    let new_id = room.allocate_player_id();
+
+    let player_token = generate_player_token();
+    let mut player = Player::new(
+        new_id,
+        name.clone(),
+        room.settings.starting_score,
+        false,
+        player_token.clone(),
+    );
+    // Counted over every join, since players who left free up no color
+    // until the palette runs out
+    player.color = player_color(room.issued_pids.len() - 1).to_string();
+    let mut entry = PlayerEntry::disconnected(player);
+    entry.reserved_at = Some(Instant::now());
+    room.players.push(entry);
+
+    tracing::info!(player_name = %name, player_id = new_id, "Player joined");
+    Ok((new_id, player_token))
+}
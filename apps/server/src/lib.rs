@@ -1,39 +1,88 @@
+pub mod admin;
+pub mod board;
+pub mod clients;
 pub mod game;
+pub mod grading;
+pub mod handshake;
 pub mod host;
+pub mod metrics;
+pub mod persist;
 pub mod player;
+pub mod rate_limit;
+pub mod replay;
+pub mod room_code;
+pub mod scoring;
+pub mod settings;
+pub mod stats;
+pub mod timer;
+pub mod transcript;
 pub mod ws_msg;
 
 use std::{
     collections::HashMap,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
     sync::Arc,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::anyhow;
 use axum::{
     Json, Router,
+    body::Bytes,
     extract::{
-        Path, Query, State, WebSocketUpgrade,
+        ConnectInfo, Path, Query, State, WebSocketUpgrade,
         ws::{Message, Utf8Bytes, WebSocket},
     },
-    response::{IntoResponse, Response},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::{any, get, post},
 };
 pub use game::{GameState, Room};
 pub use host::HostEntry;
-use http::StatusCode;
+use http::{
+    HeaderMap, StatusCode,
+    header::{ACCEPT, AUTHORIZATION},
+};
 pub use player::*;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, broadcast};
 use tokio_mpmc::channel;
 use tower_http::services::{ServeDir, ServeFile};
 
-use futures::{FutureExt, select};
+use futures::{FutureExt, Stream, StreamExt, select, stream};
 
-use crate::ws_msg::WsMsg;
+use crate::{
+    board::{check_text_lengths, normalize_board, validate_board},
+    clients::{ClientId, KnownClients},
+    handshake::{
+        AuthenticatedUser, HandshakeError, handshake_blocked, perform_handshake, record_handshake,
+        reserve_player_slot,
+    },
+    metrics::Metrics,
+    rate_limit::RateLimiter,
+    room_code::RoomCodeConfig,
+    timer::ScheduledTimer,
+    ws_msg::WsMsg,
+};
 
 pub type HeartbeatId = u32;
+
+/// A socket that sends nothing for this many ping intervals is treated as dead
+const MISSED_PINGS_BEFORE_DEAD: u32 = 3;
+/// Hard cap on a websocket frame or message; bigger ones close the connection
+const MAX_FRAME_BYTES: usize = 64 * 1024;
+/// Random draws `create_room` makes before deciding every code is taken
+const MAX_ROOM_CODE_ATTEMPTS: usize = 100;
+/// Default for `AppState::channel_capacity`, enough for a join storm on top
+/// of the broadcasts a busy question sends
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 128;
+/// Default for `AppState::max_board_text_chars`, a long paragraph
+pub const DEFAULT_MAX_BOARD_TEXT_CHARS: usize = 1_000;
 pub type UnixMs = u64; // # of milliseconds since unix epoch, or delta thereof
 
 #[derive(Deserialize)]
@@ -43,11 +92,50 @@ struct WsQuery {
     token: Option<String>, // only rejoining players include both token & player_id
     #[serde(rename = "playerID")]
     player_id: Option<u32>,
+    #[serde(default)]
+    spectator: bool, // watch only: no buzzing, not listed as a player
+    #[serde(default)]
+    display: bool, // big-screen board: only GameState and Buzzed, never chat
+    #[serde(default)]
+    envelope: bool, // wrap room events in WsMsg::Envelope with their seq
+    #[serde(rename = "clientId")]
+    client_id: Option<String>, // optional UUID remembering a returning player's name
 }
 
 pub struct AppState {
     pub room_map: Mutex<HashMap<String, Room>>,
     pub room_ttl: Duration,
+    /// Where room snapshots are saved, if persistence is enabled
+    pub snapshot_path: Option<PathBuf>,
+    /// Bearer token for the admin API; admin routes are disabled without one
+    pub admin_token: Option<String>,
+    pub metrics: Metrics,
+    /// Accept debug-only commands such as `ForceState`. Never enable in production
+    pub debug_commands: bool,
+    /// Limits how fast one address can create rooms
+    pub create_limiter: RateLimiter,
+    /// Limits how fast one address can reserve player slots over HTTP
+    pub join_limiter: RateLimiter,
+    /// When the server started, for uptime in `/health`
+    pub started_at: Instant,
+    /// How often the server pings each websocket to keep proxies from
+    /// dropping idle connections
+    pub ping_interval: Duration,
+    /// Length and charset of new room codes
+    pub room_codes: RoomCodeConfig,
+    /// Text messages longer than this are answered with an error instead of
+    /// being parsed. Anything over `MAX_FRAME_BYTES` closes the connection.
+    pub max_message_bytes: usize,
+    /// Names returning players last used, by their `clientId`
+    pub known_clients: KnownClients,
+    /// How long before a room expires for inactivity everyone in it is warned
+    pub expiry_warning: Duration,
+    /// Messages queued for each connection before sends to it start
+    /// waiting, see `game::SEND_TIMEOUT`
+    pub channel_capacity: usize,
+    /// Imported boards are turned away if any question or answer is longer
+    /// than this many characters
+    pub max_board_text_chars: usize,
 }
 
 impl Default for AppState {
@@ -61,13 +149,28 @@ impl AppState {
         Self {
             room_map: Mutex::new(HashMap::new()),
             room_ttl: Duration::from_secs(30 * 60),
+            snapshot_path: None,
+            admin_token: None,
+            metrics: Metrics::default(),
+            debug_commands: false,
+            create_limiter: RateLimiter::new(10, Duration::from_secs(6)),
+            // Classrooms often share one address, so allow a whole class at once
+            join_limiter: RateLimiter::new(30, Duration::from_secs(2)),
+            started_at: Instant::now(),
+            ping_interval: Duration::from_secs(30),
+            expiry_warning: Duration::from_secs(2 * 60),
+            room_codes: RoomCodeConfig::default(),
+            max_message_bytes: 8 * 1024,
+            known_clients: KnownClients::default(),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            max_board_text_chars: DEFAULT_MAX_BOARD_TEXT_CHARS,
         }
     }
 
     pub fn with_ttl(ttl: Duration) -> Self {
         Self {
-            room_map: Mutex::new(HashMap::new()),
             room_ttl: ttl,
+            ..Self::new()
         }
     }
 }
@@ -75,29 +178,71 @@ impl AppState {
 pub fn build_app(state: Arc<AppState>) -> Router {
     let room_routes = Router::new()
         .route("/create", post(create_room))
+        .route("/{code}/rejoin-host", post(rejoin_host))
+        .route("/{code}/join", post(join_room))
+        .route(
+            "/{code}/board",
+            get(board::download_board).post(board::import_board),
+        )
+        .route("/{code}/board.csv", post(board::import_board_csv))
         .route("/{code}/ws", any(ws_upgrade_handler))
+        .route("/{code}/events", get(events_handler))
         .route("/{code}/cpr", get(cpr_handler))
-        .with_state(state);
+        .route("/{code}/transcript", get(transcript_handler))
+        .with_state(state.clone());
+
+    let admin_routes = Router::new()
+        .route("/rooms", get(admin::list_rooms))
+        .with_state(state.clone());
 
-    let api_routes = Router::new().nest("/rooms", room_routes);
+    let api_routes = Router::new()
+        .nest("/rooms", room_routes)
+        .nest("/admin", admin_routes);
 
     Router::new()
-        .route("/health", get(|| async { "Server is up" }))
+        .route("/health", get(health_handler).with_state(state.clone()))
+        .route("/metrics", get(metrics::metrics_handler).with_state(state))
         .nest("/api/v1", api_routes)
         .fallback_service(
             ServeDir::new("public").not_found_service(ServeFile::new("public/index.html")),
         )
 }
 
-fn generate_room_code() -> String {
-    const CHARSET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
-    let mut rng = rand::rng();
-    (0..6)
-        .map(|_| {
-            let idx = rng.random_range(0..CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect()
+#[derive(Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    room_count: usize,
+    total_players: usize,
+    uptime_seconds: u64,
+}
+
+/// Plain text for existing probes; JSON stats when asked for with
+/// `Accept: application/json`.
+async fn health_handler(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let wants_json = headers
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"));
+    if !wants_json {
+        return "Server is up".into_response();
+    }
+
+    let room_map = state.room_map.lock().await;
+    Json(HealthResponse {
+        status: "ok",
+        room_count: room_map.len(),
+        total_players: room_map.values().map(|room| room.players.len()).sum(),
+        uptime_seconds: state.started_at.elapsed().as_secs(),
+    })
+    .into_response()
+}
+
+/// The token from an `Authorization: Bearer <token>` header, if present.
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
 }
 
 fn generate_host_token() -> String {
@@ -125,26 +270,72 @@ fn generate_player_token() -> String {
 #[tracing::instrument(skip(state, body))]
 async fn create_room(
     State(state): State<Arc<AppState>>,
-    Json(body): Json<CreateRoomRequest>,
-) -> (StatusCode, Json<CreateRoomResponse>) {
-    let mut room_map = state.room_map.lock().await;
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(mut body): Json<CreateRoomRequest>,
+) -> Response {
+    if !state.create_limiter.try_acquire(addr.ip()).await {
+        tracing::warn!(ip = %addr.ip(), "Room creation rate limited");
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many rooms created, slow down",
+        )
+            .into_response();
+    }
 
-    // Generate a unique room code
-    let code = loop {
-        let candidate = generate_room_code();
-        if !room_map.contains_key(&candidate) {
-            break candidate;
+    // Boards sent with the room get the same checks as imported ones
+    for board in body
+        .categories
+        .iter_mut()
+        .chain(body.boards.iter_mut().flatten())
+    {
+        normalize_board(board);
+        if let Err(reason) = validate_board(board) {
+            return (StatusCode::BAD_REQUEST, reason).into_response();
         }
+        let oversized = check_text_lengths(board, state.max_board_text_chars);
+        if !oversized.is_empty() {
+            return (StatusCode::BAD_REQUEST, Json(oversized)).into_response();
+        }
+    }
+
+    let mut room_map = state.room_map.lock().await;
+
+    // Generate a unique room code, giving up if the code space is nearly full
+    let Some(code) = (0..MAX_ROOM_CODE_ATTEMPTS)
+        .map(|_| state.room_codes.generate())
+        .find(|candidate| !room_map.contains_key(candidate))
+    else {
+        tracing::error!(
+            rooms = room_map.len(),
+            capacity = state.room_codes.capacity(),
+            "Could not find a free room code"
+        );
+        return (StatusCode::SERVICE_UNAVAILABLE, "No room codes available").into_response();
     };
 
     let host_token = generate_host_token();
     let mut room = Room::new(code.clone(), host_token.clone());
+    room.max_text_chars = state.max_board_text_chars;
 
     if let Some(categories) = body.categories {
         room.categories = categories;
     }
 
+    // Multi-round games open on their first board
+    if let Some(boards) = body.boards
+        && let Some(first) = boards.first()
+    {
+        room.categories = first.clone();
+        room.boards = boards;
+    }
+
+    if let Some(settings) = body.settings {
+        room.scoring = settings.scoring_strategy();
+        room.settings = settings;
+    }
+
     room_map.insert(code.clone(), room);
+    Metrics::incr(&state.metrics.rooms_created);
 
     tracing::info!(room_code = %code, "Room created");
 
@@ -155,6 +346,7 @@ async fn create_room(
             host_token,
         }),
     )
+        .into_response()
 }
 
 #[derive(Serialize)]
@@ -166,6 +358,111 @@ struct CreateRoomResponse {
 #[derive(Deserialize)]
 struct CreateRoomRequest {
     categories: Option<Vec<game::Category>>,
+    boards: Option<Vec<Vec<game::Category>>>,
+    settings: Option<settings::RoomSettings>,
+}
+
+#[derive(Deserialize)]
+struct RejoinHostRequest {
+    host_token: String,
+}
+
+#[derive(Serialize)]
+struct RejoinHostResponse {
+    room_code: String,
+    host_token: String,
+    state: GameState,
+    ws_path: String,
+}
+
+/// Let a host whose page reloaded check their room is still there (restoring
+/// it from the snapshot if the server restarted) and get its connection info.
+#[tracing::instrument(skip(state, body))]
+async fn rejoin_host(
+    State(state): State<Arc<AppState>>,
+    Path(RoomParams { code }): Path<RoomParams>,
+    Json(body): Json<RejoinHostRequest>,
+) -> Response {
+    let in_memory = state.room_map.lock().await.contains_key(&code);
+    if !in_memory {
+        match state.restore_room(&code, &body.host_token).await {
+            Ok(true) => {}
+            Ok(false) => return (StatusCode::NOT_FOUND, "Room does not exist").into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to restore room from snapshot");
+                return (StatusCode::NOT_FOUND, "Room does not exist").into_response();
+            }
+        }
+    }
+
+    let mut room_map = state.room_map.lock().await;
+    let Some(room) = room_map.get_mut(&code) else {
+        return (StatusCode::NOT_FOUND, "Room does not exist").into_response();
+    };
+    if room.host_token != body.host_token {
+        return (StatusCode::UNAUTHORIZED, "Invalid host token").into_response();
+    }
+    room.touch();
+
+    tracing::info!("Host rejoined room");
+
+    Json(RejoinHostResponse {
+        room_code: code.clone(),
+        host_token: room.host_token.clone(),
+        state: room.state.clone(),
+        ws_path: format!("/api/v1/rooms/{}/ws?token={}", code, room.host_token),
+    })
+    .into_response()
+}
+
+#[derive(Deserialize)]
+struct JoinRoomRequest {
+    player_name: String,
+}
+
+#[derive(Serialize)]
+struct JoinRoomResponse {
+    pid: PlayerId,
+    token: String,
+}
+
+/// Reserve a player slot over HTTP so flaky clients have their token before
+/// the websocket opens. They then connect with `playerID` + `token`.
+#[tracing::instrument(skip(state, body))]
+async fn join_room(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(RoomParams { code }): Path<RoomParams>,
+    Json(body): Json<JoinRoomRequest>,
+) -> Response {
+    if !state.join_limiter.try_acquire(addr.ip()).await {
+        tracing::warn!(ip = %addr.ip(), "Player slot reservation rate limited");
+        return (StatusCode::TOO_MANY_REQUESTS, "Too many joins, slow down").into_response();
+    }
+
+    let mut room_map = state.room_map.lock().await;
+    let Some(room) = room_map.get_mut(&code) else {
+        return (StatusCode::NOT_FOUND, "Room does not exist").into_response();
+    };
+
+    let (pid, token) = match reserve_player_slot(room, &body.player_name) {
+        Ok(reserved) => reserved,
+        Err(e @ (HandshakeError::RoomFull | HandshakeError::RoomLocked)) => {
+            return (StatusCode::CONFLICT, e.to_string()).into_response();
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to reserve player slot");
+            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+        }
+    };
+    room.touch();
+    Metrics::incr(&state.metrics.players_joined);
+
+    if let Err(e) = send_player_list_to_hosts(&room.hosts, &room.players).await {
+        tracing::warn!(error = %e, "Failed to send player list to host");
+    }
+
+    Json(JoinRoomResponse { pid, token }).into_response()
 }
 
 #[derive(Debug)]
@@ -174,45 +471,119 @@ pub enum ConnectionStatus {
     Disconnected,
 }
 #[derive(Serialize, Deserialize, Debug)]
-struct RoomParams {
-    code: String,
+pub(crate) struct RoomParams {
+    pub(crate) code: String,
 }
 
 async fn ws_upgrade_handler(
     State(state): State<Arc<AppState>>,
     ws_upgrade: WebSocketUpgrade,
-    Path(rp @ RoomParams { .. }): Path<RoomParams>,
-    Query(WsQuery {
-        token,
-        player_name,
-        player_id,
-    }): Query<WsQuery>,
+    Path(RoomParams { code }): Path<RoomParams>,
+    Query(query): Query<WsQuery>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
 ) -> Response {
+    let Some(code) = state.room_codes.normalize(&code) else {
+        return (StatusCode::BAD_REQUEST, "Invalid room code").into_response();
+    };
+    let rp = RoomParams { code };
     {
         let room_map = state.room_map.lock().await;
-        if !room_map.contains_key(&rp.code) {
+        let Some(room) = room_map.get(&rp.code) else {
             return (StatusCode::NOT_FOUND, "Room does not exist").into_response();
+        };
+        if handshake_blocked(room, addr.ip()) {
+            tracing::info!(room_code = %rp.code, ip = %addr.ip(), "Refusing connection after failed handshakes");
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many failed attempts, try again later",
+            )
+                .into_response();
         }
     }
-    ws_upgrade.on_upgrade(async move |ws| {
-        match ws_socket_handler(
-            ws,
-            rp,
-            state,
-            WsQuery {
-                token,
-                player_name,
-                player_id,
-            },
+    ws_upgrade
+        .max_frame_size(MAX_FRAME_BYTES)
+        .max_message_size(MAX_FRAME_BYTES)
+        .on_upgrade(async move |ws| {
+            match ws_socket_handler(ws, rp, state, query, addr.ip()).await {
+                Ok(()) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "WebSocket handler failed");
+                }
+            }
+        })
+}
+
+/// Send `msg` to each target after its delay, without holding anything up.
+fn deliver_in_sync(deliveries: Vec<(tokio_mpmc::Sender<WsMsg>, Duration, WsMsg)>) {
+    for (sender, delay, msg) in deliveries {
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            sender.send(msg).await
+        });
+    }
+}
+
+/// Read-only stream of a room's public events for clients that can't use
+/// websockets. Starts with the current `GameState` and ends when the room is removed.
+async fn events_handler(
+    State(state): State<Arc<AppState>>,
+    Path(RoomParams { code }): Path<RoomParams>,
+) -> Response {
+    let (initial, rx) = {
+        let room_map = state.room_map.lock().await;
+        let Some(room) = room_map.get(&code) else {
+            return (StatusCode::NOT_FOUND, "Room does not exist").into_response();
+        };
+        (
+            room.build_player_game_state_msg(),
+            room.observers.subscribe(),
         )
-        .await
-        {
-            Ok(()) => {}
-            Err(e) => {
-                tracing::error!(error = %e, "WebSocket handler failed");
+    };
+
+    tracing::info!(room_code = %code, "Event stream opened");
+
+    Sse::new(observer_stream(initial, rx))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn observer_stream(
+    initial: WsMsg,
+    rx: broadcast::Receiver<WsMsg>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let events = stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => return Some((msg, rx)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "Event stream fell behind, dropping events");
+                }
+                // The room was removed
+                Err(broadcast::error::RecvError::Closed) => return None,
             }
         }
-    })
+    });
+
+    stream::once(async { initial })
+        .chain(events)
+        .map(|msg| Ok(Event::default().json_data(&msg).unwrap_or_default()))
+}
+
+/// Fire each timer on its room once it elapses, scheduling any follow-ups.
+pub fn spawn_room_timers(state: &Arc<AppState>, code: &str, timers: Vec<ScheduledTimer>) {
+    for timer in timers {
+        let state = state.clone();
+        let code = code.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(timer.after).await;
+            let mut room_map = state.room_map.lock().await;
+            // The room may have been cleaned up in the meantime
+            if let Some(room) = room_map.get_mut(&code) {
+                let follow_ups = room.fire_timer(&timer).await;
+                spawn_room_timers(&state, &code, follow_ups);
+            }
+        });
+    }
 }
 
 async fn send_player_list_to_host(host: &HostEntry, players: &[PlayerEntry]) -> anyhow::Result<()> {
@@ -223,9 +594,40 @@ async fn send_player_list_to_host(host: &HostEntry, players: &[PlayerEntry]) ->
     Ok(())
 }
 
+async fn send_player_list_to_hosts(
+    hosts: &[HostEntry],
+    players: &[PlayerEntry],
+) -> anyhow::Result<()> {
+    for host in hosts {
+        send_player_list_to_host(host, players).await?;
+    }
+    Ok(())
+}
+
+/// Note the name player `pid` goes by so `id` can rejoin under it later.
+async fn remember_client_name(state: &AppState, code: &str, pid: PlayerId, id: ClientId) {
+    let name = state.room_map.lock().await.get(code).and_then(|room| {
+        room.players
+            .iter()
+            .find(|p| p.player.pid == pid)
+            .map(|p| p.player.name.clone())
+    });
+    if let Some(name) = name {
+        state.known_clients.remember(id, name).await;
+    }
+}
+
+/// Tell a client why it can't join, then close the socket.
+async fn reject_connection(ws: &mut WebSocket, msg: WsMsg) -> anyhow::Result<()> {
+    let ser = serde_json::to_string(&msg)?;
+    ws.send(Message::Text(Utf8Bytes::from(ser))).await?;
+    ws.send(Message::Close(None)).await?;
+    Ok(())
+}
+
 #[tracing::instrument(
     name = "ws_handler",
-    skip(ws, state),
+    skip(ws, state, query),
     fields(
         room_code = %code,
         player_id = tracing::field::Empty,
@@ -236,227 +638,256 @@ async fn ws_socket_handler(
     mut ws: WebSocket,
     RoomParams { code }: RoomParams,
     state: Arc<AppState>,
-    WsQuery {
-        player_name,
-        token,
-        player_id,
-    }: WsQuery,
+    mut query: WsQuery,
+    ip: IpAddr,
 ) -> anyhow::Result<()> {
     // for debugging
     tracing::debug!(
-    room_code = %code,
-        ?token,
-        ?player_name,
-        ?player_id,
+        room_code = %code,
+        token = ?query.token,
+        player_name = ?query.player_name,
+        player_id = ?query.player_id,
         "WebSocket connection attempt"
     );
+    let client_id = query.client_id.as_deref().and_then(ClientId::parse);
+    // A returning player who didn't type a name joins under their last one
+    let joining_player =
+        query.token.is_none() && !query.spectator && !query.display && query.player_name.is_none();
+    if joining_player && let Some(id) = &client_id {
+        query.player_name = state.known_clients.last_name(id).await;
+    }
     let ch: tokio_mpmc::Receiver<WsMsg>;
     let tx: tokio_mpmc::Sender<WsMsg>;
-    (tx, ch) = channel(20);
-    let mut connection_player_id: Option<u32> = player_id;
+    (tx, ch) = channel(state.channel_capacity);
     let tx_internal = tx.clone();
-    {
+    let handshake = {
         let mut room_map = state.room_map.lock().await;
         let room = room_map
             .get_mut(&code)
             .ok_or_else(|| anyhow!("Room {} does not exist", code))?;
-        // println!("room: {:?}", room);
-
-        let is_host = token.as_ref() == Some(&room.host_token);
-
-        tracing::Span::current().record("is_host", is_host);
-
-        if is_host {
-            let host = HostEntry::new(player_id.unwrap_or(0), tx.clone());
-            send_player_list_to_host(&host, &room.players).await?;
-
-            tracing::info!("Host connected");
-
-            if room.state != GameState::Start {
-                let players: Vec<Player> = room.players.iter().map(|e| e.player.clone()).collect();
-                let game_state_msg = WsMsg::GameState {
-                    state: room.state.clone(),
-                    categories: room.categories.clone(),
-                    players,
-                    current_question: room.current_question,
-                    current_buzzer: room.current_buzzer,
-                    winner: None,
-                };
-                tx.send(game_state_msg).await?;
-                tracing::debug!(state = ?room.state, "Sending game state to reconnecting host");
-            }
-
-            room.host = Some(host);
-        } else if let (Some(id), Some(_tok)) = (player_id, &token) {
-            if let Some(existing) = room.players.iter_mut().find(|p| p.player.pid == id) {
-                // Update existing player's send channel
-                existing.sender = tx.clone();
-
-                tracing::Span::current().record("player_id", id);
-
-                tracing::info!("Player reconnected");
-
-                let can_buzz = room.state == GameState::WaitingForBuzz;
-                let player_state_msg = WsMsg::PlayerState {
-                    pid: existing.player.pid,
-                    buzzed: existing.player.buzzed,
-                    score: existing.player.score,
-                    can_buzz,
-                };
-                tx.send(player_state_msg).await?;
-            } else {
-                return Err(anyhow!(
-                    "Player with ID {} could not be found in room {}",
-                    id,
-                    code
-                ));
-            }
-            if let Some(host) = &room.host {
-                send_player_list_to_host(host, &room.players).await?;
-            }
-        } else if let Some(name) = player_name {
-            let new_id: u32 = (room.players.len() + 1).try_into()?;
-            connection_player_id = Some(new_id);
-
-            tracing::Span::current().record("player_id", new_id);
-
-            let player_token = generate_player_token();
-            let player = PlayerEntry::new(
-                Player::new(new_id, name.clone(), 0, false, player_token.clone()),
-                tx.clone(),
-            );
-            room.players.push(player);
-
-            tracing::info!(player_name = %name, "Player joined");
-
-            let new_player_msg = WsMsg::NewPlayer {
-                pid: new_id,
-                token: player_token,
-            };
-            tx.send(new_player_msg).await?;
-
-            if let Some(host) = &room.host {
-                send_player_list_to_host(host, &room.players).await?;
-            }
-        } else if let Some(tok) = &token {
-            if let Some(existing) = room.players.iter_mut().find(|p| p.player.token == *tok) {
-                connection_player_id = Some(existing.player.pid);
-
-                tracing::Span::current().record("player_id", existing.player.pid);
-
-                existing.sender = tx.clone();
-
-                let can_buzz = room.state == GameState::WaitingForBuzz;
-                let player_state_msg = WsMsg::PlayerState {
-                    pid: existing.player.pid,
-                    buzzed: existing.player.buzzed,
-                    score: existing.player.score,
-                    can_buzz,
-                };
-
-                tx.send(player_state_msg).await?;
+        let players_before = room.players.len();
+        let handshake = perform_handshake(room, &query, &tx).await;
+        record_handshake(room, ip, &handshake);
+        if room.players.len() > players_before {
+            Metrics::incr(&state.metrics.players_joined);
+        }
+        handshake
+    };
+    let user = match handshake {
+        Ok(user) => user,
+        Err(e) => {
+            if let HandshakeError::Internal(err) = &e {
+                tracing::error!(error = %err, "Handshake failed");
             } else {
-                return Err(anyhow!("Invalid player token"));
+                tracing::info!(code = e.code(), "Handshake rejected");
             }
-        } else {
-            // Invalid connection
-            return Err(anyhow!(
-                "Invalid connection: must provide player_name (new player) or token (reconnect)"
-            ));
+            reject_connection(&mut ws, e.to_msg()).await?;
+            return Ok(());
         }
-        //
-        // for player in &room.players {
-        //     println!("player: {}", player.player.pid);
-        // }
+    };
+    if let (AuthenticatedUser::Player(pid), Some(id)) = (user, &client_id) {
+        remember_client_name(&state, &code, pid, id.clone()).await;
     }
-    loop {
-        select! {
-            res = ch.recv().fuse() => match res {
-                Ok(recv) => {
-                    let ser = serde_json::to_string(&recv)?;
-                    if let Some(r) = &recv {
-                        match &r {
+    let connection_player_id = user.player_id();
+    let is_spectator = matches!(
+        user,
+        AuthenticatedUser::Spectator | AuthenticatedUser::Display
+    );
+    let wants_envelope = query.envelope;
+    let mut pings = tokio::time::interval_at(
+        (Instant::now() + state.ping_interval).into(),
+        state.ping_interval,
+    );
+    let mut last_frame = Instant::now();
+    // Run the connection in its own block so cleanup happens however it ends
+    let result: anyhow::Result<()> = async {
+        loop {
+            select! {
+                _ = pings.tick().fuse() => {
+                    if last_frame.elapsed() > state.ping_interval * MISSED_PINGS_BEFORE_DEAD {
+                        tracing::info!(?connection_player_id, "No frames from client, dropping dead connection");
+                        break;
+                    }
+                    ws.send(Message::Ping(Bytes::new())).await?;
+                },
+                res = ch.recv().fuse() => match res {
+                    Ok(None) => {
+                        // The room gave up on this connection (e.g. it stopped
+                        // reading); closing lets the client reconnect
+                        tracing::info!(?connection_player_id, "Channel closed, dropping connection");
+                        ws.send(Message::Close(None)).await?;
+                        break;
+                    },
+                    Ok(Some(recv)) => {
+                        // Clients that didn't opt in still get bare events
+                        let recv = match recv {
+                            WsMsg::Envelope { event, .. } if !wants_envelope => *event,
+                            other => other,
+                        };
+                        let ser = serde_json::to_string(&recv)?;
+                        match &recv {
                             WsMsg::GameState { state, .. } => tracing::debug!(room_code = %code, ?state, "Sending GameState"),
                             other => tracing::trace!(room_code = %code, "Sending message: {:?}", other),
                         }
-                    }
-                    ws.send(Message::Text(Utf8Bytes::from(ser))).await?;
+                        ws.send(Message::Text(Utf8Bytes::from(ser))).await?;
+                    },
+                    Err(e) => Err(e)?
                 },
-                Err(e) => Err(e)?
-            },
-            msg_opt = ws.recv().fuse() => match msg_opt {
-                None => break,
-                Some(msg) => {
-                    let msg = if let Ok(msg) = msg {
-                        msg
-                    } else {
-                        // client disconnected
-                        Err(std::io::Error::new(
-                            std::io::ErrorKind::HostUnreachable,
-                            "websocket client disconnected in read",
-                        ))?
-                    };
-                    let msg: String = msg.into_text()?.to_string();
-                    // deser
-                    let msg: WsMsg = serde_json::from_str(&msg)?;
-                    // witness case, just for now
-                    if let m @ (WsMsg::StartGame {}
-                        | WsMsg::EndGame {}
-                        | WsMsg::BuzzEnable {}
-                        | WsMsg::BuzzDisable {}
-                        | WsMsg::Buzz {}) = msg.clone() {
-                        let witness = WsMsg::Witness { msg: Box::new(m) };
-                        let player_info: Vec<(u32, tokio_mpmc::Sender<WsMsg>, u64)> = {
-                            let room_map = state.room_map.lock().await;
+                msg_opt = ws.recv().fuse() => match msg_opt {
+                    None => break,
+                    Some(msg) => {
+                        let msg = if let Ok(msg) = msg {
+                            msg
+                        } else {
+                            // client disconnected
+                            Err(std::io::Error::new(
+                                std::io::ErrorKind::HostUnreachable,
+                                "websocket client disconnected in read",
+                            ))?
+                        };
+                        last_frame = Instant::now();
+                        let text = match msg {
+                            Message::Ping(_) | Message::Pong(_) => continue,
+                            Message::Close(_) => break,
+                            Message::Binary(_) => {
+                                tracing::debug!(?connection_player_id, "Rejecting binary message");
+                                tx_internal.send(WsMsg::Error {
+                                    code: "unsupported_message".to_string(),
+                                    message: "Only text messages are supported".to_string(),
+                                }).await?;
+                                continue;
+                            }
+                            Message::Text(text) => text,
+                        };
+                        if text.len() > state.max_message_bytes {
+                            tracing::warn!(?connection_player_id, bytes = text.len(), "Rejecting oversized message");
+                            tx_internal.send(WsMsg::Error {
+                                code: "message_too_large".to_string(),
+                                message: format!("Messages may be at most {} bytes", state.max_message_bytes),
+                            }).await?;
+                            continue;
+                        }
+                        let msg: WsMsg = match serde_json::from_str(&text) {
+                            Ok(msg) => msg,
+                            Err(e) => {
+                                tracing::warn!(?connection_player_id, error = %e, "Rejecting malformed message");
+                                tx_internal.send(WsMsg::Error {
+                                    code: "invalid_message".to_string(),
+                                    message: describe_parse_error(&e),
+                                }).await?;
+                                continue;
+                            }
+                        };
+                        if is_spectator {
+                            tracing::trace!(?msg, "Ignoring message from spectator");
+                            continue;
+                        }
+                        if matches!(msg, WsMsg::ForceState { .. }) && !state.debug_commands {
+                            tracing::warn!(?connection_player_id, "Rejecting ForceState: debug commands are disabled");
+                            continue;
+                        }
+                        // witness case, just for now
+                        if let m @ (WsMsg::StartGame {}
+                            | WsMsg::EndGame {}
+                            | WsMsg::BuzzEnable {}
+                            | WsMsg::BuzzDisable {}
+                            | WsMsg::Buzz { .. }) = msg.clone() {
+                            let deliveries = {
+                                let mut room_map = state.room_map.lock().await;
+                                let room = room_map
+                                    .get_mut(&code)
+                                    .ok_or_else(|| anyhow!("Room {} does not exist", code))?;
+                                // Numbered now so clients can order it despite the delay
+                                let targets = room.witness_targets(connection_player_id);
+                                room.stamp_for_each(targets, &WsMsg::Witness { msg: Box::new(m) })
+                            };
+                            deliver_in_sync(deliveries);
+                        };
+                        // Read-aloud cue for players' TTS, timed like witnesses
+                        // so every device starts speaking together
+                        if matches!(msg, WsMsg::ReadQuestion {}) && connection_player_id.is_none() {
+                            let mut room_map = state.room_map.lock().await;
                             let room = room_map
-                                .get(&code)
+                                .get_mut(&code)
                                 .ok_or_else(|| anyhow!("Room {} does not exist", code))?;
-                            room.players
-                                .iter()
-                                .map(|p| (p.player.pid, p.sender.clone(), p.latency().unwrap_or(0).into()))
-                                .collect()
-                        };
-                        let sender_player_id = connection_player_id;
-                        for (cpid, csender, lat) in player_info {
-                            let witnessc = witness.clone();
-                            let latc = lat;
-                            tokio::spawn(async move {
-                                if let Some(id) = sender_player_id
-                                    && cpid == id {
-                                        return Ok(());
-                                    }
-                                let s = csender;
-                                tokio::time::sleep(Duration::from_millis(500_u64.saturating_sub(latc))).await;
-                                s.send(witnessc).await
-                            });
+                            if let Some(cue) = room.read_question_cue() {
+                                let targets = room.witness_targets(None);
+                                deliver_in_sync(room.stamp_for_each(targets, &cue));
+                            }
+                        }
+                        // heartbeat case
+                        if let WsMsg::Heartbeat { hbid, .. } = msg.clone() {
+                            tx_internal.send(WsMsg::GotHeartbeat { hbid }).await?;
+                            Metrics::incr(&state.metrics.heartbeats_resolved);
+                            //continue;
+                        }
+                        // everything else
+                        let mut room_map = state.room_map.lock().await;
+                        let room = room_map
+                            .get_mut(&code)
+                            .ok_or_else(|| anyhow!("Room {} does not exist", code))?;
+                        let timers = room.update(&msg, connection_player_id).await?;
+                        if let WsMsg::Buzz { .. } = msg {
+                            Metrics::incr(&state.metrics.buzzes_processed);
+                        }
+                        room.touch();
+                        spawn_room_timers(&state, &code, timers);
+                        if matches!(msg, WsMsg::LeaveRoom {}) && connection_player_id.is_some() {
+                            tracing::info!(?connection_player_id, "Player left, closing connection");
+                            ws.send(Message::Close(None)).await?;
+                            break;
                         }
-                    };
-                    // heartbeat case
-                    if let WsMsg::Heartbeat { hbid, .. } = msg.clone() {
-                        tx_internal.send(WsMsg::GotHeartbeat { hbid }).await?;
-                        //continue;
                     }
-                    // everything else
-                    let mut room_map = state.room_map.lock().await;
-                    let room = room_map
-                        .get_mut(&code)
-                        .ok_or_else(|| anyhow!("Room {} does not exist", code))?;
-                    room.update(&msg, connection_player_id).await?;
-                    room.touch();
                 }
             }
         }
+        Ok(())
+    }
+    .await;
+    match user {
+        AuthenticatedUser::Spectator => {
+            tx_internal.close();
+            if let Some(room) = state.room_map.lock().await.get_mut(&code) {
+                room.spectators.retain(|s| !s.is_closed());
+            }
+        }
+        AuthenticatedUser::Display => {
+            tx_internal.close();
+            if let Some(room) = state.room_map.lock().await.get_mut(&code) {
+                room.displays.retain(|d| !d.is_closed());
+            }
+        }
+        AuthenticatedUser::Host => {
+            tx_internal.close();
+            let mut room_map = state.room_map.lock().await;
+            // The game only waits on the host once every host socket is gone
+            if let Some(room) = room_map.get_mut(&code) {
+                room.hosts.retain(|h| !h.sender.is_closed());
+                if room.hosts.is_empty() {
+                    let timers = room.disconnect_host().await;
+                    spawn_room_timers(&state, &code, timers);
+                }
+            }
+        }
+        AuthenticatedUser::Player(pid) => {
+            // Keep any rename made during the game for next time
+            if let Some(id) = client_id {
+                remember_client_name(&state, &code, pid, id).await;
+            }
+        }
     }
     tracing::info!(?connection_player_id, "WebSocket connection closed");
-    Ok(())
+    result
 }
 
 #[tracing::instrument(skip(state), fields(room_code = %rp.code))]
 async fn cpr_handler(
     State(state): State<Arc<AppState>>,
     Path(rp @ RoomParams { .. }): Path<RoomParams>,
-) -> String {
-    let code = rp.code;
+) -> Response {
+    let Some(code) = state.room_codes.normalize(&rp.code) else {
+        return (StatusCode::BAD_REQUEST, "Invalid room code").into_response();
+    };
     let res = {
         let mut room_map = state.room_map.lock().await;
         let room_res = room_map
@@ -468,7 +899,7 @@ async fn cpr_handler(
             Ok(room) => {
                 for entry in &mut room.players {
                     match entry.heartbeat().await {
-                        Ok(()) => {}
+                        Ok(()) => Metrics::incr(&state.metrics.heartbeats_sent),
                         Err(e) => {
                             tracing::warn!(
                                 player_id = entry.player.pid,
@@ -488,26 +919,66 @@ async fn cpr_handler(
         }
     };
     match res {
-        Ok(s) => s,
+        Ok(s) => s.into_response(),
         Err(e) => {
             tracing::error!(error = %e, "CPR handler failed");
-            format!("Err, {e}")
+            format!("Err, {e}").into_response()
         }
     }
 }
 
+/// A short, printable reason a message didn't parse. Serde lists every
+/// variant on an unknown one, which isn't worth sending back.
+fn describe_parse_error(e: &serde_json::Error) -> String {
+    const MAX_DETAIL_CHARS: usize = 200;
+    let detail = e.to_string();
+    let detail = detail
+        .split_once(", expected one of")
+        .map_or(detail.as_str(), |(head, _)| head);
+    detail
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_DETAIL_CHARS)
+        .collect()
+}
+
+/// The room's game transcript, if it keeps one, authenticated with
+/// `Authorization: Bearer <host_token>`.
+#[tracing::instrument(skip(state, headers))]
+async fn transcript_handler(
+    State(state): State<Arc<AppState>>,
+    Path(RoomParams { code }): Path<RoomParams>,
+    headers: HeaderMap,
+) -> Response {
+    let room_map = state.room_map.lock().await;
+    let Some(room) = room_map.get(&code) else {
+        return (StatusCode::NOT_FOUND, "Room does not exist").into_response();
+    };
+    if bearer_token(&headers) != Some(room.host_token.as_str()) {
+        return (StatusCode::UNAUTHORIZED, "Invalid host token").into_response();
+    }
+    if !room.settings.transcript {
+        return (StatusCode::NOT_FOUND, "Room does not keep a transcript").into_response();
+    }
+
+    Json(&room.transcript).into_response()
+}
+
 #[tracing::instrument(skip(state))]
 pub async fn cleanup_inactive_rooms(state: &Arc<AppState>) {
     let mut room_map = state.room_map.lock().await;
-    let threshold = SystemTime::now()
-        .checked_sub(state.room_ttl)
-        .unwrap_or(SystemTime::UNIX_EPOCH);
-
-    let rooms_to_remove: Vec<String> = room_map
-        .iter()
-        .filter(|(_, room)| room.last_activity < threshold)
-        .map(|(code, _)| code.clone())
-        .collect();
+    let now = SystemTime::now();
+
+    let mut rooms_to_remove = Vec::new();
+    for (code, room) in room_map.iter_mut() {
+        let ttl = room.ttl_override.unwrap_or(state.room_ttl);
+        let idle = now.duration_since(room.last_activity).unwrap_or_default();
+        if idle > ttl {
+            rooms_to_remove.push(code.clone());
+        } else if !room.expiry_warned && idle > ttl.saturating_sub(state.expiry_warning) {
+            room.warn_expiry(ttl - idle).await;
+        }
+    }
 
     if rooms_to_remove.is_empty() {
         tracing::trace!("No inactive rooms to clean up");
@@ -515,6 +986,10 @@ pub async fn cleanup_inactive_rooms(state: &Arc<AppState>) {
         for code in &rooms_to_remove {
             room_map.remove(code);
         }
+        Metrics::add(
+            &state.metrics.rooms_cleaned_up,
+            rooms_to_remove.len() as u64,
+        );
         tracing::info!(count = rooms_to_remove.len(), "Cleaned up inactive rooms");
     }
 }
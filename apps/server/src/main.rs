@@ -1,10 +1,18 @@
-use std::{sync::Arc, time::Duration};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
 use anyhow::Result;
-use madhacks2025::{AppState, build_app, cleanup_inactive_rooms};
+use madhacks2025::{AppState, build_app, cleanup_inactive_rooms, room_code::RoomCodeConfig};
 
 const HOST: &str = "0.0.0.0";
 const PORT: u16 = 3000;
+const SNAPSHOT_PATH_VAR: &str = "SNAPSHOT_PATH";
+const ADMIN_TOKEN_VAR: &str = "ADMIN_TOKEN";
+const DEBUG_COMMANDS_VAR: &str = "DEBUG_COMMANDS";
+const ROOM_CODE_LENGTH_VAR: &str = "ROOM_CODE_LENGTH";
+const ROOM_CODE_CHARSET_VAR: &str = "ROOM_CODE_CHARSET";
+const CHANNEL_CAPACITY_VAR: &str = "CHANNEL_CAPACITY";
+const MAX_BOARD_TEXT_CHARS_VAR: &str = "MAX_BOARD_TEXT_CHARS";
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -17,22 +25,74 @@ async fn main() -> Result<()> {
 
     tracing::info!("Starting server on {}:{}", HOST, PORT);
 
-    let state = Arc::new(AppState::new());
+    // Optional persistence: restore rooms on startup and save them periodically
+    let snapshot_path = std::env::var(SNAPSHOT_PATH_VAR).ok().map(PathBuf::from);
+    let room_code_length = match std::env::var(ROOM_CODE_LENGTH_VAR) {
+        Ok(length) => length.parse()?,
+        Err(_) => madhacks2025::room_code::DEFAULT_LENGTH,
+    };
+    let room_code_charset = std::env::var(ROOM_CODE_CHARSET_VAR).ok();
+    let room_codes = RoomCodeConfig::new(room_code_length, room_code_charset.as_deref())?;
+    let channel_capacity = match std::env::var(CHANNEL_CAPACITY_VAR) {
+        Ok(capacity) => capacity.parse()?,
+        Err(_) => madhacks2025::DEFAULT_CHANNEL_CAPACITY,
+    };
+    let max_board_text_chars = match std::env::var(MAX_BOARD_TEXT_CHARS_VAR) {
+        Ok(max) => max.parse()?,
+        Err(_) => madhacks2025::DEFAULT_MAX_BOARD_TEXT_CHARS,
+    };
+    let state = Arc::new(AppState {
+        snapshot_path: snapshot_path.clone(),
+        admin_token: std::env::var(ADMIN_TOKEN_VAR)
+            .ok()
+            .filter(|t| !t.is_empty()),
+        debug_commands: std::env::var(DEBUG_COMMANDS_VAR).is_ok_and(|v| v == "1" || v == "true"),
+        room_codes,
+        channel_capacity,
+        max_board_text_chars,
+        ..AppState::new()
+    });
     let cleanup_state = state.clone();
-    let app = build_app(state);
+
+    if let Some(path) = &snapshot_path
+        && path.exists()
+        && let Err(e) = state.load_snapshot(path).await
+    {
+        tracing::error!(error = %e, path = %path.display(), "Failed to load room snapshot");
+    }
 
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(60));
         loop {
             interval.tick().await;
             cleanup_inactive_rooms(&cleanup_state).await;
+            cleanup_state.create_limiter.prune().await;
+            cleanup_state.join_limiter.prune().await;
         }
     });
 
+    if let Some(path) = snapshot_path {
+        let snapshot_state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = snapshot_state.save_snapshot(&path).await {
+                    tracing::error!(error = %e, path = %path.display(), "Failed to save room snapshot");
+                }
+            }
+        });
+    }
+
+    let app = build_app(state);
+
     let listener = tokio::net::TcpListener::bind(format!("{}:{}", HOST, PORT)).await?;
     tracing::info!("Server running on http://{}:{}", HOST, PORT);
-    axum::serve(listener, app)
-        .await
-        .expect("Failed to start server");
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .expect("Failed to start server");
     Ok(())
 }
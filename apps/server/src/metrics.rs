@@ -0,0 +1,80 @@
+use std::{
+    fmt::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use axum::extract::State;
+
+use crate::AppState;
+
+/// Server-wide counters, exposed in Prometheus text format at `GET /metrics`.
+#[derive(Default, Debug)]
+pub struct Metrics {
+    pub rooms_created: AtomicU64,
+    pub rooms_cleaned_up: AtomicU64,
+    pub players_joined: AtomicU64,
+    pub buzzes_processed: AtomicU64,
+    pub heartbeats_sent: AtomicU64,
+    pub heartbeats_resolved: AtomicU64,
+}
+
+impl Metrics {
+    pub fn incr(counter: &AtomicU64) {
+        Self::add(counter, 1);
+    }
+
+    pub fn add(counter: &AtomicU64, n: u64) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Render every counter plus the `active_rooms` gauge.
+    pub fn render(&self, active_rooms: usize) -> String {
+        let counters = [
+            ("rooms_created", "Rooms created", &self.rooms_created),
+            (
+                "rooms_cleaned_up",
+                "Inactive rooms removed",
+                &self.rooms_cleaned_up,
+            ),
+            ("players_joined", "New players joined", &self.players_joined),
+            (
+                "buzzes_processed",
+                "Buzzes handled by rooms",
+                &self.buzzes_processed,
+            ),
+            (
+                "heartbeats_sent",
+                "Heartbeats requested from players",
+                &self.heartbeats_sent,
+            ),
+            (
+                "heartbeats_resolved",
+                "Heartbeats answered by players",
+                &self.heartbeats_resolved,
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, help, counter) in counters {
+            let _ = writeln!(out, "# HELP buzzer_{name}_total {help}");
+            let _ = writeln!(out, "# TYPE buzzer_{name}_total counter");
+            let _ = writeln!(
+                out,
+                "buzzer_{name}_total {}",
+                counter.load(Ordering::Relaxed)
+            );
+        }
+        let _ = writeln!(out, "# HELP buzzer_active_rooms Rooms currently live");
+        let _ = writeln!(out, "# TYPE buzzer_active_rooms gauge");
+        let _ = writeln!(out, "buzzer_active_rooms {active_rooms}");
+        out
+    }
+}
+
+pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> String {
+    let active_rooms = state.room_map.lock().await.len();
+    state.metrics.render(active_rooms)
+}
@@ -0,0 +1,189 @@
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState, PlayerEntry,
+    game::{Category, GameState, Room},
+    player::{Player, PlayerId},
+    settings::RoomSettings,
+    stats::QuestionStats,
+};
+
+/// The durable part of a [`Room`]. Connections can't be saved, so restored
+/// players and hosts start disconnected until they reconnect with their token.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedRoom {
+    pub code: String,
+    pub host_token: String,
+    pub state: GameState,
+    pub players: Vec<Player>,
+    pub categories: Vec<Category>,
+    pub current_question: Option<(usize, usize)>,
+    pub current_buzzer: Option<PlayerId>,
+    pub winner: Option<PlayerId>,
+    #[serde(default)]
+    pub settings: RoomSettings,
+    #[serde(default)]
+    pub paused_from: Option<GameState>,
+    #[serde(default)]
+    pub boards: Vec<Vec<Category>>,
+    #[serde(default)]
+    pub current_board: usize,
+    /// Ids of players who have left too, so they stay retired after a restart
+    #[serde(default)]
+    pub issued_pids: Vec<PlayerId>,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default = "default_value_multiplier")]
+    pub value_multiplier: u32,
+    #[serde(default)]
+    pub wins: HashMap<PlayerId, u32>,
+    #[serde(default)]
+    pub ttl_override: Option<Duration>,
+    /// `Player` doesn't serialize its token, so they are saved alongside
+    #[serde(default)]
+    pub player_tokens: HashMap<PlayerId, String>,
+    #[serde(default)]
+    pub question_stats: Vec<QuestionStats>,
+}
+
+fn default_value_multiplier() -> u32 {
+    1
+}
+
+impl From<&Room> for SavedRoom {
+    fn from(room: &Room) -> Self {
+        Self {
+            code: room.code.clone(),
+            host_token: room.host_token.clone(),
+            state: room.state.clone(),
+            players: room.players.iter().map(|e| e.player.clone()).collect(),
+            categories: room.categories.clone(),
+            current_question: room.current_question,
+            current_buzzer: room.current_buzzer,
+            winner: room.winner,
+            settings: room.settings.clone(),
+            paused_from: room.paused_from.clone(),
+            boards: room.boards.clone(),
+            current_board: room.current_board,
+            issued_pids: room.issued_pids.iter().copied().collect(),
+            locked: room.locked,
+            value_multiplier: room.value_multiplier,
+            wins: room.wins.clone(),
+            ttl_override: room.ttl_override,
+            player_tokens: room
+                .players
+                .iter()
+                .map(|e| (e.player.pid, e.player.token.clone()))
+                .collect(),
+            question_stats: room.question_stats.clone(),
+        }
+    }
+}
+
+impl From<SavedRoom> for Room {
+    fn from(mut saved: SavedRoom) -> Self {
+        let mut room = Room::new(saved.code, saved.host_token);
+        room.state = saved.state;
+        room.issued_pids = saved
+            .issued_pids
+            .into_iter()
+            .chain(saved.players.iter().map(|p| p.pid))
+            .collect();
+        room.players = saved
+            .players
+            .into_iter()
+            .map(|mut player| {
+                // Older snapshots carry the token inline, which still deserializes
+                if let Some(token) = saved.player_tokens.remove(&player.pid) {
+                    player.token = token;
+                }
+                PlayerEntry::disconnected(player)
+            })
+            .collect();
+        room.categories = saved.categories;
+        room.current_question = saved.current_question;
+        room.current_buzzer = saved.current_buzzer;
+        room.winner = saved.winner;
+        room.scoring = saved.settings.scoring_strategy();
+        room.settings = saved.settings;
+        room.paused_from = saved.paused_from;
+        room.boards = saved.boards;
+        room.current_board = saved.current_board;
+        room.locked = saved.locked;
+        room.value_multiplier = saved.value_multiplier;
+        room.wins = saved.wins;
+        room.ttl_override = saved.ttl_override;
+        room.question_stats = saved.question_stats;
+        room
+    }
+}
+
+impl AppState {
+    /// Write every room to `path` as JSON.
+    pub async fn save_snapshot(&self, path: impl AsRef<Path>) -> anyhow::Result<usize> {
+        let path = path.as_ref();
+        let rooms: Vec<SavedRoom> = {
+            let room_map = self.room_map.lock().await;
+            room_map.values().map(SavedRoom::from).collect()
+        };
+
+        // Write then rename so a crash mid-write never leaves a truncated snapshot
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec(&rooms)?).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+
+        tracing::debug!(count = rooms.len(), path = %path.display(), "Saved room snapshot");
+        Ok(rooms.len())
+    }
+
+    /// Restore rooms from a snapshot written by [`AppState::save_snapshot`].
+    pub async fn load_snapshot(&self, path: impl AsRef<Path>) -> anyhow::Result<usize> {
+        let path = path.as_ref();
+        let data = tokio::fs::read(path).await?;
+        let rooms: Vec<SavedRoom> = serde_json::from_slice(&data)?;
+        let count = rooms.len();
+
+        let mut room_map = self.room_map.lock().await;
+        for saved in rooms {
+            let mut room = Room::from(saved);
+            room.max_text_chars = self.max_board_text_chars;
+            room_map.insert(room.code.clone(), room);
+        }
+
+        tracing::info!(count, path = %path.display(), "Restored rooms from snapshot");
+        Ok(count)
+    }
+
+    /// Restore a single room from the configured snapshot, if it is saved
+    /// there under `host_token` and not already live. Returns whether the
+    /// room now exists; a wrong token looks the same as a missing room.
+    pub async fn restore_room(&self, code: &str, host_token: &str) -> anyhow::Result<bool> {
+        let Some(path) = &self.snapshot_path else {
+            return Ok(false);
+        };
+        if !tokio::fs::try_exists(path).await? {
+            return Ok(false);
+        }
+
+        let data = tokio::fs::read(path).await?;
+        let rooms: Vec<SavedRoom> = serde_json::from_slice(&data)?;
+        let Some(saved) = rooms
+            .into_iter()
+            .find(|r| r.code == code && r.host_token == host_token)
+        else {
+            return Ok(false);
+        };
+
+        let mut room_map = self.room_map.lock().await;
+        room_map.entry(saved.code.clone()).or_insert_with(|| {
+            let mut room = Room::from(saved);
+            room.max_text_chars = self.max_board_text_chars;
+            room
+        });
+
+        tracing::info!(room_code = %code, "Restored room from snapshot");
+        Ok(true)
+    }
+}
@@ -1,35 +1,118 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fmt,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use tokio_mpmc::Sender;
 
-use crate::{ConnectionStatus, HeartbeatId, UnixMs, ws_msg::WsMsg};
+use crate::{ConnectionStatus, HeartbeatId, UnixMs, replay::EventLog, ws_msg::WsMsg};
 
 pub type PlayerId = u32;
 
+/// Number of recent latency samples kept per player
+pub const LATENCY_SAMPLES: usize = 5;
+
+/// Heartbeats still unanswered after this long are forgotten
+const HEARTBEAT_EXPIRY_MS: UnixMs = 60_000;
+
+/// Colors handed to players in join order, so both the host and player
+/// screens can tell buzzers apart
+pub const PLAYER_COLORS: [&str; 12] = [
+    "#ef4444", "#3b82f6", "#22c55e", "#eab308", "#a855f7", "#f97316", "#14b8a6", "#ec4899",
+    "#84cc16", "#6366f1", "#06b6d4", "#f43f5e",
+];
+
+/// Palette color for the player who joined `join_index`-th (0-based).
+/// Colors only repeat once the palette is exhausted.
+pub fn player_color(join_index: usize) -> &'static str {
+    PLAYER_COLORS[join_index % PLAYER_COLORS.len()]
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Player {
     pub pid: PlayerId,
     pub name: String,
     pub score: i32,
     pub buzzed: bool,
+    /// Secret the player reconnects with. Never serialized, since `Player`s
+    /// are broadcast to everyone in the room; it only goes out in
+    /// `NewPlayer` and `TokenRotated`, to its owner.
+    #[serde(skip_serializing, default)]
     pub token: String,
+    /// Hex color, see [`PLAYER_COLORS`]
+    #[serde(default)]
+    pub color: String,
+    /// Correct answers in a row, reset by a wrong one
+    #[serde(default)]
+    pub streak: u32,
 }
 
 pub struct PlayerEntry {
     pub player: Player,
     pub sender: Sender<WsMsg>,
     pub status: ConnectionStatus,
-    latencies: [u32; 5],
+    pub last_renamed: Option<Instant>,
+    pub benched_until: Option<Instant>,
+    /// When the slot was reserved over HTTP, until the player first connects
+    pub reserved_at: Option<Instant>,
+    /// Everything sent to this player, numbered in their own sequence so
+    /// they see no gaps, and replayed on `ResumeFrom`
+    pub events: EventLog,
+    recent_chats: VecDeque<Instant>,
+    latencies: LatencySamples,
     times_doheartbeat: HashMap<HeartbeatId, TrackedMessageTime>,
     hbid_counter: u32,
 }
 
+/// Ring buffer of the last [`LATENCY_SAMPLES`] latencies, in ms.
+#[derive(Clone, Debug, Default)]
+pub struct LatencySamples {
+    samples: [u32; LATENCY_SAMPLES],
+    next: usize,
+    len: usize,
+}
+
+impl LatencySamples {
+    /// Record a sample, replacing the oldest one once full.
+    pub fn push(&mut self, sample: u32) {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % LATENCY_SAMPLES;
+        self.len = (self.len + 1).min(LATENCY_SAMPLES);
+    }
+
+    /// Recorded samples, in no particular order. Slots not yet filled are
+    /// left out.
+    pub fn samples(&self) -> &[u32] {
+        &self.samples[..self.len]
+    }
+
+    pub fn mean(&self) -> f64 {
+        let samples = self.samples();
+        if samples.is_empty() {
+            return 0.0;
+        }
+        samples.iter().map(|&s| f64::from(s)).sum::<f64>() / samples.len() as f64
+    }
+
+    /// Sample standard deviation; zero until there are two samples.
+    pub fn std_dev(&self) -> f64 {
+        let samples = self.samples();
+        if samples.len() < 2 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        let variance = samples
+            .iter()
+            .map(|&s| (f64::from(s) - mean).powi(2))
+            .sum::<f64>()
+            / (samples.len() - 1) as f64;
+        variance.sqrt()
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct TrackedMessageTime {
     pub t_sent: UnixMs,
@@ -41,6 +124,9 @@ impl fmt::Debug for PlayerEntry {
         f.debug_struct("PlayerEntry")
             .field("player", &self.player)
             .field("status", &self.status)
+            .field("last_renamed", &self.last_renamed)
+            .field("benched_until", &self.benched_until)
+            .field("recent_chats", &self.recent_chats.len())
             .field("latencies", &self.latencies)
             .field("sender len", &self.sender.len())
             .field("times_doheartbeat", &self.times_doheartbeat)
@@ -50,23 +136,79 @@ impl fmt::Debug for PlayerEntry {
 }
 
 impl PlayerEntry {
+    /// An entry for a player with no live connection, e.g. one restored from a
+    /// snapshot. Messages to them are dropped until they reconnect.
+    pub fn disconnected(player: Player) -> Self {
+        let (sender, _) = tokio_mpmc::channel(1);
+        sender.close();
+        let mut entry = Self::new(player, sender);
+        entry.status = ConnectionStatus::Disconnected;
+        entry
+    }
+
     pub fn new(player: Player, sender: Sender<WsMsg>) -> Self {
         Self {
             player,
             sender,
-            latencies: [0; 5],
+            latencies: LatencySamples::default(),
             times_doheartbeat: HashMap::new(),
             status: ConnectionStatus::Connected,
+            last_renamed: None,
+            benched_until: None,
+            reserved_at: None,
+            events: EventLog::default(),
+            recent_chats: VecDeque::new(),
             hbid_counter: 0,
         }
     }
 }
 
 impl PlayerEntry {
+    /// Average of the recent latency samples, or 0 before any arrive.
     pub fn latency(&self) -> Result<u32> {
-        let sum: u32 = self.latencies.iter().sum();
-        let latencies_len: u32 = self.latencies.len().try_into()?;
-        Ok(sum / latencies_len)
+        let samples = self.latencies.samples();
+        if samples.is_empty() {
+            return Ok(0);
+        }
+        let sum: u64 = samples.iter().map(|&s| u64::from(s)).sum();
+        let len: u64 = samples.len().try_into()?;
+        Ok((sum / len).try_into()?)
+    }
+
+    /// How much the recent latency samples vary (sample standard deviation, ms).
+    pub fn jitter(&self) -> f64 {
+        self.latencies.std_dev()
+    }
+
+    /// Time left before this player may rename again, if any.
+    pub fn rename_cooldown_remaining(&self, cooldown: Duration) -> Option<Duration> {
+        let elapsed = self.last_renamed?.elapsed();
+        cooldown.checked_sub(elapsed).filter(|d| !d.is_zero())
+    }
+
+    /// Time left on the host-imposed buzz bench, if any.
+    pub fn bench_remaining(&self) -> Option<Duration> {
+        self.benched_until
+            .and_then(|until| until.checked_duration_since(Instant::now()))
+            .filter(|d| !d.is_zero())
+    }
+
+    /// Record a chat message unless the player already sent `limit` within
+    /// the last `window`. Returns whether the message is allowed.
+    pub fn try_chat(&mut self, limit: usize, window: Duration) -> bool {
+        let now = Instant::now();
+        while self
+            .recent_chats
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= window)
+        {
+            self.recent_chats.pop_front();
+        }
+        if self.recent_chats.len() >= limit {
+            return false;
+        }
+        self.recent_chats.push_back(now);
+        true
     }
 
     pub fn time_ms() -> u64 {
@@ -88,6 +230,8 @@ impl PlayerEntry {
     }
 
     pub fn record_dohb(&mut self, hbid: HeartbeatId, t_sent: UnixMs) {
+        self.times_doheartbeat
+            .retain(|_, tmt| t_sent.saturating_sub(tmt.t_sent) < HEARTBEAT_EXPIRY_MS);
         self.times_doheartbeat.insert(
             hbid,
             TrackedMessageTime {
@@ -98,31 +242,27 @@ impl PlayerEntry {
     }
 
     pub fn on_latencyhb(&mut self, hbid: HeartbeatId, t_lathb: u32) -> bool {
-        if let Some(dohb) = self.times_doheartbeat.get(&hbid) {
-            if let Some(lat_fwd) = dohb.delta_32bit() {
-                println!("t_lathb={t_lathb},lat_fwd={lat_fwd}");
-                let lat = t_lathb.saturating_sub(lat_fwd);
-                tracing::trace!(
-                    player_id = self.player.pid,
-                    hbid,
-                    latency = lat,
-                    "Updated player latency"
-                );
-                for i in 1..(self.latencies.len() - 1) {
-                    self.latencies[i - 1] = self.latencies[i];
-                }
-                self.latencies[self.latencies.len() - 1] = lat;
-                self.times_doheartbeat.clear();
-                true
-            } else {
-                tracing::warn!(
-                    player_id = self.player.pid,
-                    hbid,
-                    "DoHeartbeat time sent but not received"
-                );
-                false
-            }
+        let Some(dohb) = self.times_doheartbeat.get(&hbid) else {
+            return false;
+        };
+        if let Some(lat_fwd) = dohb.delta_32bit() {
+            let lat = t_lathb.saturating_sub(lat_fwd);
+            tracing::trace!(
+                player_id = self.player.pid,
+                hbid,
+                latency = lat,
+                "Updated player latency"
+            );
+            self.latencies.push(lat);
+            // Other heartbeats may still be in flight
+            self.times_doheartbeat.remove(&hbid);
+            true
         } else {
+            tracing::warn!(
+                player_id = self.player.pid,
+                hbid,
+                "DoHeartbeat time sent but not received"
+            );
             false
         }
     }
@@ -131,10 +271,12 @@ impl PlayerEntry {
         let t_part: u32 = (t_sent % 1_000)
             .try_into()
             .expect("ms part of time exceeds 32-bit integer limit (impossible)");
-        t_part + (self.hbid_counter * 1_000)
+        t_part.wrapping_add(self.hbid_counter.wrapping_mul(1_000))
     }
 
     pub async fn heartbeat(&mut self) -> anyhow::Result<()> {
+        // Bump the counter so heartbeats sent within the same ms get distinct ids
+        self.hbid_counter = self.hbid_counter.wrapping_add(1);
         let t_sent = Self::time_ms();
         let hbid = self.generate_hbid(t_sent);
         self.sender
@@ -166,6 +308,118 @@ impl Player {
             score,
             buzzed,
             token,
+            color: String::new(),
+            streak: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_entry() -> PlayerEntry {
+        let (tx, _rx) = tokio_mpmc::channel(10);
+        PlayerEntry::new(
+            Player::new(1, "AJ".to_string(), 0, false, "token".to_string()),
+            tx,
+        )
+    }
+
+    #[test]
+    fn test_player_token_is_never_serialized() {
+        let player = Player::new(1, "AJ".to_string(), 0, false, "secret".to_string());
+        let json = serde_json::to_string(&player).expect("Player should serialize");
+        assert!(!json.contains("secret"), "Token leaked: {}", json);
+
+        let back: Player = serde_json::from_str(&json).expect("Player should deserialize");
+        assert!(back.token.is_empty());
+    }
+
+    fn record_latency(entry: &mut PlayerEntry, hbid: HeartbeatId, latency: u32) {
+        entry.record_dohb(hbid, 1_000);
+        entry.on_know_dohb_recv(hbid, 1_000);
+        assert!(entry.on_latencyhb(hbid, latency));
+    }
+
+    #[test]
+    fn test_latency_ignores_unfilled_slots() {
+        let mut entry = test_entry();
+        assert_eq!(entry.latency().expect("latency"), 0);
+        assert_eq!(entry.jitter(), 0.0);
+
+        record_latency(&mut entry, 1, 40);
+        assert_eq!(
+            entry.latency().expect("latency"),
+            40,
+            "A single sample is not diluted by empty slots"
+        );
+        assert_eq!(entry.jitter(), 0.0);
+
+        record_latency(&mut entry, 2, 60);
+        assert_eq!(entry.latency().expect("latency"), 50);
+    }
+
+    #[test]
+    fn test_latency_and_jitter_of_known_sequence() {
+        let mut entry = test_entry();
+        for (hbid, latency) in [10, 20, 30, 40, 50].into_iter().enumerate() {
+            record_latency(&mut entry, hbid as HeartbeatId, latency);
+        }
+
+        assert_eq!(entry.latency().expect("latency"), 30);
+        // Sample variance of 10..=50 step 10 is 250
+        assert!((entry.jitter() - 250_f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_latency_buffer_drops_oldest_samples() {
+        let mut entry = test_entry();
+        for (hbid, latency) in [1_000, 1_000, 10, 10, 10, 10, 10].into_iter().enumerate() {
+            record_latency(&mut entry, hbid as HeartbeatId, latency);
+        }
+
+        assert_eq!(
+            entry.latency().expect("latency"),
+            10,
+            "Samples older than the last {} are dropped",
+            LATENCY_SAMPLES
+        );
+        assert_eq!(entry.jitter(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_back_to_back_heartbeats_all_resolve() {
+        let (tx, rx) = tokio_mpmc::channel(10);
+        let mut entry = PlayerEntry::new(
+            Player::new(1, "AJ".to_string(), 0, false, "token".to_string()),
+            tx,
+        );
+
+        for _ in 0..3 {
+            entry.heartbeat().await.expect("heartbeat should send");
+        }
+
+        let mut hbids = Vec::new();
+        for _ in 0..3 {
+            match rx.recv().await {
+                Ok(Some(WsMsg::DoHeartbeat { hbid, .. })) => hbids.push(hbid),
+                other => panic!("Expected DoHeartbeat, got {:?}", other),
+            }
+        }
+        let mut unique = hbids.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), 3, "Heartbeat ids must be unique: {:?}", hbids);
+
+        for hbid in hbids {
+            assert!(entry.on_know_dohb_recv(hbid, PlayerEntry::time_ms()));
+            assert!(
+                entry.on_latencyhb(hbid, 20),
+                "Heartbeat {} should resolve",
+                hbid
+            );
         }
+        assert_eq!(entry.latencies.samples().len(), 3);
     }
 }
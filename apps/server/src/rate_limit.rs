@@ -0,0 +1,108 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::Mutex;
+
+/// Per-IP token buckets: each address may make `capacity` requests in a
+/// burst, then earns one more every `refill_every`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: u32,
+    refill_every: Duration,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: u32,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: u32, refill_every: Duration) -> Self {
+        Self {
+            capacity,
+            refill_every,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token for `ip`. Returns false when it has none left.
+    pub async fn try_acquire(&self, ip: IpAddr) -> bool {
+        let mut buckets = self.buckets.lock().await;
+        let now = Instant::now();
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+        self.refill(bucket, now);
+
+        if bucket.tokens == 0 {
+            return false;
+        }
+        bucket.tokens -= 1;
+        true
+    }
+
+    /// Forget addresses whose buckets have filled back up; they'd start
+    /// full anyway.
+    pub async fn prune(&self) {
+        let mut buckets = self.buckets.lock().await;
+        let now = Instant::now();
+        buckets.retain(|_, bucket| {
+            self.refill(bucket, now);
+            bucket.tokens < self.capacity
+        });
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        if self.refill_every.is_zero() {
+            bucket.tokens = self.capacity;
+            return;
+        }
+        let earned =
+            now.duration_since(bucket.last_refill).as_nanos() / self.refill_every.as_nanos();
+        if earned > 0 {
+            let earned = u32::try_from(earned).unwrap_or(u32::MAX);
+            bucket.tokens = bucket.tokens.saturating_add(earned).min(self.capacity);
+            bucket.last_refill += self.refill_every * earned;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    const ALICE: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    const BOB: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+    #[tokio::test]
+    async fn test_bucket_empties_per_address() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+
+        assert!(limiter.try_acquire(ALICE).await);
+        assert!(limiter.try_acquire(ALICE).await);
+        assert!(!limiter.try_acquire(ALICE).await);
+        assert!(limiter.try_acquire(BOB).await, "Buckets are per address");
+    }
+
+    #[tokio::test]
+    async fn test_bucket_refills_and_prunes() {
+        let limiter = RateLimiter::new(1, Duration::from_millis(20));
+
+        assert!(limiter.try_acquire(ALICE).await);
+        assert!(!limiter.try_acquire(ALICE).await);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(limiter.try_acquire(ALICE).await);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        limiter.prune().await;
+        assert!(limiter.buckets.lock().await.is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{PlayerEntry, UnixMs, ws_msg::WsMsg};
+
+/// How many recent events are kept per client for reconnecting
+pub const EVENT_LOG_CAPACITY: usize = 64;
+
+/// An event as it went out to a client, numbered in send order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SequencedEvent {
+    pub seq: u64,
+    pub ts: UnixMs,
+    pub event: WsMsg,
+}
+
+impl SequencedEvent {
+    pub fn into_envelope(self) -> WsMsg {
+        WsMsg::Envelope {
+            seq: self.seq,
+            ts: self.ts,
+            event: Box::new(self.event),
+        }
+    }
+}
+
+/// Numbers everything sent to one client and keeps a ring buffer of its
+/// recent events, so a client that reconnects can catch up on what it
+/// missed with `ResumeFrom`. Each client has its own, so seqs have no gaps.
+#[derive(Debug)]
+pub struct EventLog {
+    events: VecDeque<SequencedEvent>,
+    capacity: usize,
+    last_seq: u64,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(EVENT_LOG_CAPACITY)
+    }
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            last_seq: 0,
+        }
+    }
+
+    /// Seq of the newest event, or 0 before anything was sent.
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Give `event` the next seq and the current time, without buffering it.
+    pub fn stamp(&mut self, event: WsMsg) -> SequencedEvent {
+        self.last_seq += 1;
+        SequencedEvent {
+            seq: self.last_seq,
+            ts: PlayerEntry::time_ms(),
+            event,
+        }
+    }
+
+    /// Remember a stamped event, dropping the oldest event if full.
+    pub fn record(&mut self, event: SequencedEvent) {
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Stamp `event` and remember it.
+    pub fn push(&mut self, event: WsMsg) -> u64 {
+        let event = self.stamp(event);
+        let seq = event.seq;
+        self.record(event);
+        seq
+    }
+
+    /// Buffered events newer than `last_seq`, oldest first. Anything that
+    /// has already fallen out of the buffer is gone.
+    pub fn since(&self, last_seq: u64) -> Vec<SequencedEvent> {
+        self.events
+            .iter()
+            .filter(|e| e.seq > last_seq)
+            .cloned()
+            .collect()
+    }
+
+    /// Whether `msg` makes sense to replay. Full state messages are left out:
+    /// a reconnecting client already gets fresh ones, and replaying old
+    /// copies would roll it back.
+    pub fn is_replayable(msg: &WsMsg) -> bool {
+        !matches!(
+            msg,
+            WsMsg::GameState { .. } | WsMsg::PlayerState { .. } | WsMsg::PlayerList(_)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notice(n: usize) -> WsMsg {
+        WsMsg::Notice {
+            message: n.to_string(),
+        }
+    }
+
+    fn seqs(events: &[SequencedEvent]) -> Vec<u64> {
+        events.iter().map(|e| e.seq).collect()
+    }
+
+    #[test]
+    fn test_since_returns_newer_events() {
+        let mut log = EventLog::new(8);
+        for n in 0..5 {
+            log.push(notice(n));
+        }
+        assert_eq!(log.last_seq(), 5);
+        assert_eq!(seqs(&log.since(0)), [1, 2, 3, 4, 5]);
+        assert_eq!(seqs(&log.since(3)), [4, 5]);
+        assert!(log.since(5).is_empty());
+    }
+
+    #[test]
+    fn test_oldest_events_are_dropped() {
+        let mut log = EventLog::new(3);
+        for n in 0..10 {
+            log.push(notice(n));
+        }
+        assert_eq!(seqs(&log.since(0)), [8, 9, 10]);
+        assert_eq!(log.last_seq(), 10, "Seqs keep counting past the capacity");
+    }
+
+    #[test]
+    fn test_stamped_events_share_the_sequence() {
+        let mut log = EventLog::new(8);
+        let unbuffered = log.stamp(notice(0));
+        let buffered = log.push(notice(1));
+        assert_eq!((unbuffered.seq, buffered), (1, 2));
+        assert_eq!(seqs(&log.since(0)), [2], "Only recorded events replay");
+    }
+}
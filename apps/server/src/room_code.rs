@@ -0,0 +1,117 @@
+use anyhow::{Result, bail};
+use rand::Rng;
+
+/// Room codes use A–Z without the easily confused I and O
+pub const DEFAULT_CHARSET: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ";
+pub const DEFAULT_LENGTH: usize = 6;
+
+/// How room codes are generated. Longer codes or a bigger charset make
+/// collisions rarer on busy servers.
+#[derive(Clone, Debug)]
+pub struct RoomCodeConfig {
+    length: usize,
+    charset: Vec<u8>,
+}
+
+impl Default for RoomCodeConfig {
+    fn default() -> Self {
+        Self {
+            length: DEFAULT_LENGTH,
+            charset: DEFAULT_CHARSET.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl RoomCodeConfig {
+    /// Codes of `length` characters drawn from `charset`, or the default
+    /// charset if none is given. Custom charsets may only use uppercase
+    /// letters and digits, so that typed codes can be normalized by
+    /// uppercasing them.
+    pub fn new(length: usize, charset: Option<&str>) -> Result<Self> {
+        if length == 0 {
+            bail!("Room code length must be at least 1");
+        }
+        let charset = charset.unwrap_or(DEFAULT_CHARSET);
+        if charset.is_empty() {
+            bail!("Room code charset must not be empty");
+        }
+        if let Some(c) = charset
+            .chars()
+            .find(|c| !c.is_ascii_uppercase() && !c.is_ascii_digit())
+        {
+            bail!("Room code charset may only contain A-Z and 0-9, found {c:?}");
+        }
+
+        let mut charset = charset.as_bytes().to_vec();
+        charset.sort_unstable();
+        charset.dedup();
+        Ok(Self { length, charset })
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// How many distinct codes this config can produce, saturating at
+    /// `u64::MAX`.
+    pub fn capacity(&self) -> u64 {
+        (self.charset.len() as u64).saturating_pow(self.length.try_into().unwrap_or(u32::MAX))
+    }
+
+    pub fn generate(&self) -> String {
+        let mut rng = rand::rng();
+        (0..self.length)
+            .map(|_| {
+                let idx = rng.random_range(0..self.charset.len());
+                self.charset[idx] as char
+            })
+            .collect()
+    }
+
+    /// Tidy up a code as a person typed it (padding, lowercase). Returns
+    /// `None` if it can't be a room code at all.
+    pub fn normalize(&self, raw: &str) -> Option<String> {
+        let code = raw.trim().to_ascii_uppercase();
+        let valid = !code.is_empty() && code.bytes().all(|b| self.charset.contains(&b));
+        valid.then_some(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_codes() {
+        let config = RoomCodeConfig::default();
+        for _ in 0..100 {
+            let code = config.generate();
+            assert_eq!(code.len(), 6);
+            assert!(
+                code.bytes()
+                    .all(|b| DEFAULT_CHARSET.as_bytes().contains(&b))
+            );
+            assert!(!code.contains('I') && !code.contains('O'));
+        }
+    }
+
+    #[test]
+    fn test_codes_have_requested_length_and_charset() {
+        let config = RoomCodeConfig::new(10, Some("0123456789")).expect("Valid config");
+        for _ in 0..100 {
+            let code = config.generate();
+            assert_eq!(code.len(), 10);
+            assert!(code.bytes().all(|b| b.is_ascii_digit()), "{}", code);
+            assert_eq!(config.normalize(&code), Some(code));
+        }
+        assert_eq!(config.normalize("ABC"), None);
+        assert_eq!(config.capacity(), 10_000_000_000);
+    }
+
+    #[test]
+    fn test_rejects_bad_configs() {
+        assert!(RoomCodeConfig::new(0, None).is_err());
+        assert!(RoomCodeConfig::new(6, Some("")).is_err());
+        assert!(RoomCodeConfig::new(6, Some("abc")).is_err());
+    }
+}
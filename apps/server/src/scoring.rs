@@ -0,0 +1,99 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// How a host ruling changes the buzzing player's score. New house rules are
+/// new implementations rather than new branches in the state machine.
+pub trait ScoringStrategy: fmt::Debug + Send + Sync {
+    /// The player's score after being ruled `correct` or not on a question
+    /// worth `question_value`.
+    fn apply(&self, player_score: i32, question_value: i32, correct: bool) -> i32;
+}
+
+/// Classic rules: win the value when right, lose it when wrong.
+#[derive(Debug)]
+pub struct Standard;
+
+impl ScoringStrategy for Standard {
+    fn apply(&self, player_score: i32, question_value: i32, correct: bool) -> i32 {
+        if correct {
+            player_score.saturating_add(question_value)
+        } else {
+            player_score.saturating_sub(question_value)
+        }
+    }
+}
+
+/// Wrong answers cost nothing.
+#[derive(Debug)]
+pub struct NoDeduct;
+
+impl ScoringStrategy for NoDeduct {
+    fn apply(&self, player_score: i32, question_value: i32, correct: bool) -> i32 {
+        if correct {
+            player_score.saturating_add(question_value)
+        } else {
+            player_score
+        }
+    }
+}
+
+/// Right answers are worth double; wrong answers wipe out the player's
+/// winnings, though a negative score is never made worse.
+#[derive(Debug)]
+pub struct DoubleOrNothing;
+
+impl ScoringStrategy for DoubleOrNothing {
+    fn apply(&self, player_score: i32, question_value: i32, correct: bool) -> i32 {
+        if correct {
+            player_score.saturating_add(question_value.saturating_mul(2))
+        } else {
+            player_score.min(0)
+        }
+    }
+}
+
+/// The scoring rules a room is created with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringMode {
+    #[default]
+    Standard,
+    NoDeduct,
+    DoubleOrNothing,
+}
+
+impl ScoringMode {
+    pub fn strategy(self) -> Box<dyn ScoringStrategy> {
+        match self {
+            Self::Standard => Box::new(Standard),
+            Self::NoDeduct => Box::new(NoDeduct),
+            Self::DoubleOrNothing => Box::new(DoubleOrNothing),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_scoring() {
+        assert_eq!(Standard.apply(100, 200, true), 300);
+        assert_eq!(Standard.apply(100, 200, false), -100);
+    }
+
+    #[test]
+    fn test_no_deduct_scoring() {
+        assert_eq!(NoDeduct.apply(100, 200, true), 300);
+        assert_eq!(NoDeduct.apply(100, 200, false), 100);
+        assert_eq!(NoDeduct.apply(-100, 200, false), -100);
+    }
+
+    #[test]
+    fn test_double_or_nothing_scoring() {
+        assert_eq!(DoubleOrNothing.apply(100, 200, true), 500);
+        assert_eq!(DoubleOrNothing.apply(500, 200, false), 0);
+        assert_eq!(DoubleOrNothing.apply(-100, 200, false), -100);
+    }
+}
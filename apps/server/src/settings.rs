@@ -0,0 +1,270 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::{NoDeduct, ScoringMode, ScoringStrategy};
+
+/// Per-room options chosen by the host when the room is created.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomSettings {
+    pub accessibility: AccessibilitySettings,
+    /// Minimum time between two renames by the same player
+    pub rename_cooldown_ms: u64,
+    /// New players are turned away once the room holds this many
+    pub max_players: usize,
+    /// Lock the room to new players when the game starts
+    pub auto_lock: bool,
+    /// End the game as soon as someone reaches this score
+    pub target_score: Option<i32>,
+    /// Score every player starts with, including those joining mid-game
+    pub starting_score: i32,
+    /// Wrong answers can't take a score below this. Unset allows any score.
+    pub min_score: Option<i32>,
+    /// Subtract the question's value from players who answer wrong
+    pub deduct_on_wrong: bool,
+    /// Extra points per answer in a streak, for the second correct answer in
+    /// a row onwards: the third in a row earns 3x this on top of the value.
+    /// 0 turns streak bonuses off.
+    pub streak_bonus: i32,
+    /// House rules for scoring rulings
+    pub scoring: ScoringMode,
+    /// Award poll answers matching the question's answer without waiting for
+    /// the host. Answers that don't match are still left for the host.
+    pub auto_grade: bool,
+    /// Rule on the answer automatically if the host hasn't within this long
+    pub answer_timeout_ms: Option<u64>,
+    /// What an answer timeout counts as
+    pub answer_timeout_action: AnswerTimeoutAction,
+    /// After a wrong answer, the others only have this long to steal before
+    /// the answer is revealed. Unset leaves buzzing open indefinitely.
+    pub steal_window_ms: Option<u64>,
+    /// Send `HostReady` on the host's behalf this long after they pick a
+    /// question, unless they send it first. Unset leaves it to the host.
+    pub auto_ready_delay_ms: Option<u64>,
+    /// How long to wait for every player to load a question before buzzing
+    /// opens anyway
+    pub question_load_timeout_ms: u64,
+    /// How long a disconnected host has to come back before the game pauses
+    pub host_grace_ms: u64,
+    /// Hold buzzes this long after the first one arrives, then award the
+    /// buzz to whoever buzzed earliest once latency is accounted for. Unset
+    /// awards the first buzz to reach the server.
+    pub buzz_window_ms: Option<u64>,
+    /// Only count a buzz once the player taps again within this long, so
+    /// stray taps on touchscreens are ignored. Unset counts every buzz.
+    pub double_buzz_ms: Option<u64>,
+    /// How long witnesses are held back for a zero-latency player; lower it
+    /// for LAN play
+    pub witness_base_delay_ms: u64,
+    /// Keep a transcript of the game for `GET /rooms/{code}/transcript`
+    pub transcript: bool,
+    /// Give players a fresh token every time they reconnect, so a leaked
+    /// token stops working once its owner is back
+    pub rotate_tokens: bool,
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        Self {
+            accessibility: AccessibilitySettings::default(),
+            rename_cooldown_ms: 5_000,
+            max_players: 12,
+            auto_lock: false,
+            target_score: None,
+            starting_score: 0,
+            min_score: None,
+            deduct_on_wrong: true,
+            streak_bonus: 0,
+            scoring: ScoringMode::default(),
+            auto_grade: false,
+            answer_timeout_ms: None,
+            answer_timeout_action: AnswerTimeoutAction::default(),
+            steal_window_ms: None,
+            auto_ready_delay_ms: None,
+            question_load_timeout_ms: 2_000,
+            witness_base_delay_ms: 500,
+            transcript: false,
+            rotate_tokens: false,
+            buzz_window_ms: None,
+            double_buzz_ms: None,
+            host_grace_ms: 30_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnswerTimeoutAction {
+    /// Same as the host marking the answer incorrect
+    #[default]
+    Incorrect,
+    /// Same as the host skipping the question
+    Skip,
+}
+
+/// Options for players using screen readers or other assistive tech.
+///
+/// When enabled, nothing in the game advances on its own and every window a
+/// player has to act within is stretched to at least `min_window_ms`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    pub enabled: bool,
+    pub min_window_ms: u64,
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_window_ms: 10_000,
+        }
+    }
+}
+
+impl RoomSettings {
+    pub fn rename_cooldown(&self) -> Duration {
+        Duration::from_millis(self.rename_cooldown_ms)
+    }
+
+    pub fn question_load_timeout(&self) -> Duration {
+        self.window(Duration::from_millis(self.question_load_timeout_ms))
+    }
+
+    pub fn answer_timeout(&self) -> Option<Duration> {
+        self.auto_timeout(self.answer_timeout_ms.map(Duration::from_millis))
+    }
+
+    pub fn steal_window(&self) -> Option<Duration> {
+        self.auto_timeout(self.steal_window_ms.map(Duration::from_millis))
+    }
+
+    pub fn auto_ready_delay(&self) -> Option<Duration> {
+        self.auto_timeout(self.auto_ready_delay_ms.map(Duration::from_millis))
+    }
+
+    /// The strategy for `scoring`. Turning off `deduct_on_wrong` makes
+    /// standard scoring `NoDeduct`.
+    pub fn scoring_strategy(&self) -> Box<dyn ScoringStrategy> {
+        match self.scoring {
+            ScoringMode::Standard if !self.deduct_on_wrong => Box::new(NoDeduct),
+            mode => mode.strategy(),
+        }
+    }
+
+    /// Clamp a ruling that took a score from `before` down to `after` at
+    /// `min_score`. A score already below the floor isn't raised to it.
+    pub fn floor_score(&self, before: i32, after: i32) -> i32 {
+        match self.min_score {
+            Some(floor) if after < before => after.max(floor.min(before)),
+            _ => after,
+        }
+    }
+
+    /// Bonus for a correct answer that makes a streak of `streak`
+    pub fn streak_bonus_for(&self, streak: u32) -> i32 {
+        if streak < 2 {
+            return 0;
+        }
+        self.streak_bonus
+            .saturating_mul(i32::try_from(streak).unwrap_or(i32::MAX))
+    }
+
+    pub fn host_grace(&self) -> Duration {
+        Duration::from_millis(self.host_grace_ms)
+    }
+
+    pub fn buzz_window(&self) -> Option<Duration> {
+        self.buzz_window_ms.map(Duration::from_millis)
+    }
+
+    pub fn double_buzz_window(&self) -> Option<Duration> {
+        self.double_buzz_ms.map(Duration::from_millis)
+    }
+
+    pub fn witness_base_delay(&self) -> Duration {
+        Duration::from_millis(self.witness_base_delay_ms)
+    }
+
+    /// Resolve a configured auto-advance (auto-skip, auto-continue, ...).
+    /// Returns `None` when the timer should not run at all.
+    pub fn auto_timeout(&self, configured: Option<Duration>) -> Option<Duration> {
+        if self.accessibility.enabled {
+            None
+        } else {
+            configured
+        }
+    }
+
+    /// Resolve the length of a window players must act within.
+    pub fn window(&self, configured: Duration) -> Duration {
+        if self.accessibility.enabled {
+            configured.max(Duration::from_millis(self.accessibility.min_window_ms))
+        } else {
+            configured
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accessible_settings() -> RoomSettings {
+        RoomSettings {
+            accessibility: AccessibilitySettings {
+                enabled: true,
+                min_window_ms: 5_000,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_accessibility_disables_auto_timeouts() {
+        let settings = accessible_settings();
+
+        for configured in [
+            None,
+            Some(Duration::ZERO),
+            Some(Duration::from_millis(250)),
+            Some(Duration::from_secs(3600)),
+        ] {
+            assert_eq!(
+                settings.auto_timeout(configured),
+                None,
+                "No auto-advance should fire for {:?}",
+                configured
+            );
+        }
+    }
+
+    #[test]
+    fn test_auto_timeouts_pass_through_by_default() {
+        let settings = RoomSettings::default();
+        let configured = Some(Duration::from_secs(5));
+
+        assert_eq!(settings.auto_timeout(configured), configured);
+        assert_eq!(settings.auto_timeout(None), None);
+    }
+
+    #[test]
+    fn test_accessibility_enforces_minimum_window() {
+        let settings = accessible_settings();
+
+        assert_eq!(
+            settings.window(Duration::from_millis(300)),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            settings.window(Duration::from_secs(8)),
+            Duration::from_secs(8),
+            "Windows already longer than the minimum are kept"
+        );
+        assert_eq!(
+            RoomSettings::default().window(Duration::from_millis(300)),
+            Duration::from_millis(300)
+        );
+    }
+}
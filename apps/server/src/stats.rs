@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+use crate::player::PlayerId;
+
+/// A player getting the floor on a question
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BuzzStat {
+    pub pid: PlayerId,
+    /// Milliseconds from buzzing first opening on the question to this buzz
+    #[serde(rename = "msToBuzz")]
+    pub ms_to_buzz: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Ruling {
+    pub pid: PlayerId,
+    pub correct: bool,
+}
+
+/// What happened on one question, for post-game analysis such as finding the
+/// hardest question.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct QuestionStats {
+    pub board: usize,
+    #[serde(rename = "categoryIndex")]
+    pub category_index: usize,
+    #[serde(rename = "questionIndex")]
+    pub question_index: usize,
+    /// Everyone who got the floor, in order; the first won the buzz race
+    pub buzzes: Vec<BuzzStat>,
+    /// The host's rulings, in order
+    pub rulings: Vec<Ruling>,
+}
+
+impl QuestionStats {
+    pub fn new(board: usize, category_index: usize, question_index: usize) -> Self {
+        Self {
+            board,
+            category_index,
+            question_index,
+            buzzes: Vec::new(),
+            rulings: Vec::new(),
+        }
+    }
+
+    /// Whoever the question was ruled correct for, if anyone
+    pub fn answered_by(&self) -> Option<PlayerId> {
+        self.rulings.iter().find(|r| r.correct).map(|r| r.pid)
+    }
+}
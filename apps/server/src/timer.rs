@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+/// Things a room arranges to happen later without any client input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimerKind {
+    /// Open buzzing even though some players never acked the question
+    OpenBuzzing,
+    /// Lift the lockout on players who buzzed before buzzing opened
+    EarlyBuzzLockout,
+    /// Rule on an answer the host never checked
+    AnswerTimeout,
+    /// Pause the game if a disconnected host hasn't come back
+    HostGrace,
+    /// Reveal the answer if nobody steals after a wrong answer
+    StealWindow,
+    /// Award the buzz to the earliest of the buzzes held since the first one
+    BuzzWindow,
+    /// Act as if the host sent `HostReady` a while after picking a question
+    AutoReady,
+}
+
+/// A timer the room asked to be fired after `after`. Rooms keep the latest
+/// `id` per kind, so a timer that was cancelled or replaced fires as a no-op.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledTimer {
+    pub kind: TimerKind,
+    pub id: u64,
+    pub after: Duration,
+}
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::{PlayerEntry, UnixMs, player::PlayerId, ws_msg::WsMsg};
+
+/// How many entries a room's transcript keeps. `GameState` broadcasts carry
+/// the whole board, so a full transcript can take a few megabytes; rooms
+/// only keep one when `settings.transcript` is on.
+pub const TRANSCRIPT_CAPACITY: usize = 2_000;
+
+/// Who an event went to
+#[derive(Clone, Copy, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Audience {
+    Host,
+    Players,
+    Player(PlayerId),
+    Observers,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TranscriptEntry {
+    /// A message the room processed; `pid` is unset for the host
+    Command {
+        ts: UnixMs,
+        pid: Option<PlayerId>,
+        msg: WsMsg,
+    },
+    /// A message the room sent, with the seq it went out under. Broadcasts
+    /// to players have none, since every player numbers them separately.
+    Event {
+        ts: UnixMs,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        seq: Option<u64>,
+        to: Audience,
+        msg: WsMsg,
+    },
+}
+
+/// Everything that went in and out of a room, oldest first, for reviewing a
+/// game afterwards (e.g. when a buzzer "didn't work"). Once full, the oldest
+/// entries are dropped so the end of the game is always there.
+#[derive(Debug, Serialize)]
+pub struct Transcript {
+    /// How many entries fell off the front
+    dropped: u64,
+    entries: VecDeque<TranscriptEntry>,
+    #[serde(skip)]
+    capacity: usize,
+}
+
+impl Default for Transcript {
+    fn default() -> Self {
+        Self::new(TRANSCRIPT_CAPACITY)
+    }
+}
+
+impl Transcript {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            dropped: 0,
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &TranscriptEntry> {
+        self.entries.iter()
+    }
+
+    pub fn record_command(&mut self, pid: Option<PlayerId>, msg: &WsMsg) {
+        if is_heartbeat(msg) {
+            return;
+        }
+        self.push(TranscriptEntry::Command {
+            ts: PlayerEntry::time_ms(),
+            pid,
+            msg: msg.clone(),
+        });
+    }
+
+    pub fn record_event(&mut self, seq: Option<u64>, ts: UnixMs, to: Audience, msg: &WsMsg) {
+        if is_heartbeat(msg) {
+            return;
+        }
+        self.push(TranscriptEntry::Event {
+            ts,
+            seq,
+            to,
+            msg: msg.clone(),
+        });
+    }
+
+    fn push(&mut self, entry: TranscriptEntry) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+/// Latency checks run every few seconds and would crowd out the game
+fn is_heartbeat(msg: &WsMsg) -> bool {
+    matches!(
+        msg,
+        WsMsg::DoHeartbeat { .. }
+            | WsMsg::Heartbeat { .. }
+            | WsMsg::GotHeartbeat { .. }
+            | WsMsg::LatencyOfHeartbeat { .. }
+            | WsMsg::PlayerLatency { .. }
+    )
+}
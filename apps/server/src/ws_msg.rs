@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
     HeartbeatId, UnixMs,
-    game::{Category, GameState},
+    game::{BuzzBlockedReason, Category, GameState},
     player::{Player, PlayerId},
+    replay::SequencedEvent,
+    stats::QuestionStats,
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -11,11 +15,38 @@ pub enum WsMsg {
     Witness {
         msg: Box<WsMsg>,
     },
+    /// An event numbered in the order it was sent to this client, for
+    /// clients that asked for envelopes when connecting
+    Envelope {
+        seq: u64,
+        ts: UnixMs,
+        event: Box<WsMsg>,
+    },
     PlayerList(Vec<Player>),
+    Notice {
+        message: String,
+    },
     NewPlayer {
         pid: PlayerId,
         token: String,
     },
+    /// The player's token changed on reconnect; the old one no longer works
+    TokenRotated {
+        token: String,
+    },
+    /// Sent by a reconnecting client to catch up on events after `lastSeq`
+    ResumeFrom {
+        #[serde(rename = "lastSeq")]
+        last_seq: u64,
+    },
+    /// Buffered events the client missed, oldest first
+    Replay {
+        events: Vec<SequencedEvent>,
+    },
+    Error {
+        code: String,
+        message: String,
+    },
 
     // Game State Broadcast
     GameState {
@@ -27,6 +58,25 @@ pub enum WsMsg {
         #[serde(rename = "currentBuzzer")]
         current_buzzer: Option<PlayerId>,
         winner: Option<PlayerId>,
+        /// Games won per player since the series was last reset
+        #[serde(rename = "seriesStandings", default)]
+        series_standings: HashMap<PlayerId, u32>,
+        /// Changes with every state change; see `HostChecked`
+        #[serde(rename = "turnToken", default)]
+        turn_token: u64,
+    },
+
+    AnswerRevealed {
+        #[serde(rename = "categoryIndex")]
+        category_index: usize,
+        #[serde(rename = "questionIndex")]
+        question_index: usize,
+        answer: String,
+    },
+
+    /// How the question just revealed went, for the host
+    QuestionStats {
+        stats: QuestionStats,
     },
 
     PlayerState {
@@ -35,28 +85,228 @@ pub enum WsMsg {
         score: i32,
         #[serde(rename = "canBuzz")]
         can_buzz: bool,
+        #[serde(rename = "blockedReason")]
+        blocked_reason: Option<BuzzBlockedReason>,
     },
 
+    /// Ask for a fresh `GameState` (and `PlayerState`, for players) after
+    /// missing a broadcast, without reconnecting
+    #[serde(alias = "RequestState")]
+    RequestState {},
+
+    /// The room will be cleaned up for inactivity unless something happens
+    /// in it before then
+    RoomExpiringSoon {
+        #[serde(rename = "secondsRemaining")]
+        seconds_remaining: u64,
+    },
+
+    // Host connection, for players' "waiting for host" screen
+    #[serde(alias = "HostDisconnected")]
+    HostDisconnected {},
+    #[serde(alias = "HostReconnected")]
+    HostReconnected {},
+
     // Host Actions
     #[serde(alias = "StartGame")]
     StartGame {},
     #[serde(alias = "EndGame")]
     EndGame {},
+    #[serde(alias = "ResetGame")]
+    ResetGame {},
+    /// Forget the wins tallied over previous games
+    #[serde(alias = "ResetSeries")]
+    ResetSeries {},
+    /// Stop (or allow again) new players joining
+    LockRoom {
+        locked: bool,
+    },
+    /// Keep the room around this long without activity instead of the
+    /// server's default; 0 goes back to the default
+    SetRoomTtl {
+        seconds: u64,
+    },
+    /// Get the host token, replacing it with a fresh one (which invalidates
+    /// the old one) if `newHostToken` is set
+    TransferHost {
+        #[serde(rename = "newHostToken", default)]
+        new_host_token: bool,
+    },
+    /// The room's host token, sent only to the host that asked for it
+    HostToken {
+        token: String,
+    },
+    /// Debug escape hatch for a wedged game; only honoured when the server
+    /// enables debug commands
+    ForceState {
+        state: GameState,
+    },
     HostChoice {
         #[serde(rename = "categoryIndex")]
         category_index: usize,
         #[serde(rename = "questionIndex")]
         question_index: usize,
     },
+    /// Play a random unanswered question, the same one every time for a
+    /// given seed and board
+    RandomQuestion {
+        seed: Option<u64>,
+    },
+    /// The host threw out the question; any score changes on it were undone
+    QuestionVoided {
+        #[serde(rename = "categoryIndex")]
+        category_index: usize,
+        #[serde(rename = "questionIndex")]
+        question_index: usize,
+    },
+    /// The selected question vanished from the board (e.g. its category was
+    /// removed), so the game went back to selection
+    #[serde(alias = "SelectionCleared")]
+    SelectionCleared {},
+    InvalidSelection {
+        #[serde(rename = "categoryIndex")]
+        category_index: usize,
+        #[serde(rename = "questionIndex")]
+        question_index: usize,
+        reason: String,
+    },
+    /// Fix a typo on the board mid-game; `force` allows editing played questions
+    EditQuestion {
+        #[serde(rename = "categoryIndex")]
+        category_index: usize,
+        #[serde(rename = "questionIndex")]
+        question_index: usize,
+        question: Option<String>,
+        answer: Option<String>,
+        value: Option<u32>,
+        #[serde(default)]
+        force: bool,
+    },
+    /// Mark (category, question) pairs as already played, e.g. when resuming
+    /// an interrupted game from notes
+    RestoreBoardState {
+        answered: Vec<(usize, usize)>,
+    },
+    #[serde(alias = "NextBoard")]
+    NextBoard {},
+    /// Show every question and answer once the game is over
+    RevealAll {
+        /// Send the answers to players and observers too, not just the host
+        #[serde(rename = "toPlayers", default)]
+        to_players: bool,
+    },
+    /// Every question and answer on the board, played or not
+    AllAnswers {
+        board: Vec<Category>,
+    },
+    /// Put the categories in a random order before the game starts, the same
+    /// order every time for a given seed
+    ShuffleBoard {
+        seed: Option<u64>,
+        /// Shuffle the questions within each category too
+        #[serde(rename = "shuffleQuestions", default)]
+        shuffle_questions: bool,
+    },
+    /// Multiply every question's value from now on, e.g. 2 for Double Jeopardy
+    SetMultiplier {
+        factor: u32,
+    },
+    /// Have players' devices read the current question aloud
+    #[serde(alias = "ReadQuestion")]
+    ReadQuestion {},
+    ReadQuestionNow {
+        text: String,
+        #[serde(rename = "estimatedDurationMs")]
+        estimated_duration_ms: u64,
+    },
     #[serde(alias = "HostReady")]
     HostReady {},
     HostChecked {
         correct: bool,
+        /// The `turnToken` of the `GameState` the host was looking at. A
+        /// ruling for an earlier turn is ignored, so double clicks only
+        /// count once.
+        #[serde(rename = "turnToken", default)]
+        turn_token: Option<u64>,
+    },
+    /// Rule on the current question for a player who answered without
+    /// buzzing. On a poll question it grades, or regrades, their answer.
+    AwardQuestion {
+        pid: PlayerId,
+        correct: bool,
+        /// See `HostChecked`
+        #[serde(rename = "turnToken", default)]
+        turn_token: Option<u64>,
+    },
+    /// Grade one player's answer to a poll question. Sent again with the
+    /// other ruling, it overrules the grade (say, an auto-graded one).
+    PollChecked {
+        pid: PlayerId,
+        correct: bool,
+    },
+    /// Poll answers still waiting to be graded, for the host
+    PollResults {
+        answers: Vec<(PlayerId, String)>,
     },
     #[serde(alias = "HostSkip")]
-    HostSkip {},
+    HostSkip {
+        /// See `HostChecked`
+        #[serde(rename = "turnToken", default)]
+        turn_token: Option<u64>,
+    },
+    /// Throw out the current question without changing anyone's score
+    #[serde(alias = "VoidQuestion")]
+    VoidQuestion {},
     #[serde(alias = "HostContinue")]
-    HostContinue {},
+    HostContinue {
+        /// See `HostChecked`
+        #[serde(rename = "turnToken", default)]
+        turn_token: Option<u64>,
+    },
+    #[serde(alias = "Undo")]
+    Undo {},
+    #[serde(alias = "Pause")]
+    Pause {},
+    #[serde(alias = "Resume")]
+    Resume {},
+    BenchPlayer {
+        pid: PlayerId,
+        #[serde(rename = "durationMs")]
+        duration_ms: u64,
+    },
+
+    // Player Actions
+    RenamePlayer {
+        name: String,
+    },
+    /// Quit the room for good; the connection closes afterwards
+    #[serde(alias = "LeaveRoom")]
+    LeaveRoom {},
+    NameRejected {
+        reason: String,
+    },
+    #[serde(alias = "RaiseHand")]
+    RaiseHand {},
+    HandRaised {
+        pid: PlayerId,
+    },
+    #[serde(alias = "QuestionLoaded")]
+    QuestionLoaded {},
+    /// A player's answer to a poll question
+    SubmitAnswer {
+        text: String,
+    },
+
+    // Chat
+    Chat {
+        text: String,
+    },
+    ChatMessage {
+        pid: PlayerId,
+        name: String,
+        text: String,
+        timestamp: UnixMs,
+    },
 
     // Buzzer
     #[serde(alias = "BuzzEnable")]
@@ -64,11 +314,30 @@ pub enum WsMsg {
     #[serde(alias = "BuzzDisable")]
     BuzzDisable {},
     #[serde(alias = "Buzz")]
-    Buzz {},
+    Buzz {
+        /// Client-side counter so retransmitted buzzes can be dropped
+        #[serde(default)]
+        seq: Option<u32>,
+    },
     Buzzed {
         pid: PlayerId,
         name: String,
     },
+    BuzzOrder {
+        order: Vec<PlayerId>,
+    },
+    AnswerTimeout {
+        pid: PlayerId,
+    },
+    /// The others can steal the question for this long after a wrong answer
+    StealWindowOpen {
+        #[serde(rename = "durationMs")]
+        duration_ms: u64,
+    },
+    /// The current question's answer, for the host's eyes only
+    AnswerHint {
+        answer: String,
+    },
 
     // Heartbeats
     DoHeartbeat {
@@ -86,4 +355,39 @@ pub enum WsMsg {
         hbid: HeartbeatId,
         t_lat: UnixMs,
     },
+    PlayerLatency {
+        pid: PlayerId,
+        latency: u32,
+        jitter: f64,
+    },
+}
+
+impl WsMsg {
+    /// `HostChecked` without a turn token, so it applies to whatever turn
+    /// the room is on.
+    pub fn host_checked(correct: bool) -> Self {
+        Self::HostChecked {
+            correct,
+            turn_token: None,
+        }
+    }
+
+    /// `AwardQuestion` without a turn token.
+    pub fn award_question(pid: PlayerId, correct: bool) -> Self {
+        Self::AwardQuestion {
+            pid,
+            correct,
+            turn_token: None,
+        }
+    }
+
+    /// `HostSkip` without a turn token.
+    pub fn host_skip() -> Self {
+        Self::HostSkip { turn_token: None }
+    }
+
+    /// `HostContinue` without a turn token.
+    pub fn host_continue() -> Self {
+        Self::HostContinue { turn_token: None }
+    }
 }
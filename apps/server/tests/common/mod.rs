@@ -3,7 +3,7 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use futures::{SinkExt, StreamExt};
-use madhacks2025::game::{Category, Question};
+use madhacks2025::game::{Category, Question, QuestionMode};
 use tokio::sync::MutexGuard;
 use tokio::{net::TcpStream, task::JoinHandle};
 use tokio_tungstenite::tungstenite::Utf8Bytes;
@@ -17,7 +17,12 @@ pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 /// Start test server on random port
 /// Returns (server task, port number, shared app state)
 pub async fn start_test_server() -> (JoinHandle<()>, u16, Arc<AppState>) {
-    let state = Arc::new(AppState::new());
+    start_test_server_with(Arc::new(AppState::new())).await
+}
+
+/// Start test server on random port using existing app state
+/// Returns (server task, port number, shared app state)
+pub async fn start_test_server_with(state: Arc<AppState>) -> (JoinHandle<()>, u16, Arc<AppState>) {
     let app = build_app(state.clone());
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
@@ -27,8 +32,14 @@ pub async fn start_test_server() -> (JoinHandle<()>, u16, Arc<AppState>) {
     let addr: SocketAddr = listener.local_addr().expect("Failed to get local addr");
     let port = addr.port();
 
-    let server_handle =
-        tokio::spawn(async move { axum::serve(listener, app).await.expect("Server failed") });
+    let server_handle = tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .expect("Server failed")
+    });
 
     tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
 
@@ -110,6 +121,52 @@ pub async fn recv_msgs(ws: &mut WsStream) -> Vec<WsMsg> {
     received
 }
 
+/// Open the room's server-sent event stream
+pub async fn connect_sse_client(port: u16, room_code: &str) -> reqwest::Response {
+    let url = format!(
+        "http://127.0.0.1:{}/api/v1/rooms/{}/events",
+        port, room_code
+    );
+    reqwest::get(&url)
+        .await
+        .expect("Failed to open event stream")
+}
+
+/// Receive all available events from an SSE stream (with timeout)
+///
+/// # Arguments
+/// * `response` - Open event stream
+/// * `buffer` - Partial event text carried between calls
+///
+/// # Returns
+/// Vec of all received messages
+pub async fn recv_sse_msgs(response: &mut reqwest::Response, buffer: &mut String) -> Vec<WsMsg> {
+    let timeout = tokio::time::Duration::from_millis(100);
+
+    loop {
+        match tokio::time::timeout(timeout, response.chunk()).await {
+            Ok(Ok(Some(chunk))) => buffer.push_str(&String::from_utf8_lossy(&chunk)),
+            Ok(Ok(None)) => break, // Stream closed
+            Ok(Err(e)) => panic!("Event stream error: {}", e),
+            Err(_) => break, // Timeout - no more events
+        }
+    }
+
+    // Only parse complete events; keep any trailing partial one for next time
+    let Some(end) = buffer.rfind("\n\n") else {
+        return Vec::new();
+    };
+    let complete: String = buffer.drain(..end + 2).collect();
+    complete
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| match serde_json::from_str::<WsMsg>(data.trim()) {
+            Ok(parsed) => parsed,
+            Err(e) => panic!("Failed to parse WsMsg: {}. Text: {}", e, data),
+        })
+        .collect()
+}
+
 /// Create a room via HTTP API
 ///
 /// # Arguments
@@ -118,12 +175,24 @@ pub async fn recv_msgs(ws: &mut WsStream) -> Vec<WsMsg> {
 /// # Returns
 /// Room code of created room
 pub async fn create_room_http(port: u16) -> String {
+    create_room_http_with(port, serde_json::json!({})).await
+}
+
+/// Create a room via HTTP API with a custom request body
+///
+/// # Arguments
+/// * `port` - Server port
+/// * `body` - JSON body for the create request (categories, settings)
+///
+/// # Returns
+/// Room code of created room
+pub async fn create_room_http_with(port: u16, body: serde_json::Value) -> String {
     let url = format!("http://127.0.0.1:{}/api/v1/rooms/create", port);
 
     let client = reqwest::Client::new();
     let response = client
         .post(&url)
-        .json(&serde_json::json!({}))
+        .json(&body)
         .send()
         .await
         .expect("Failed to create room");
@@ -149,6 +218,9 @@ pub async fn add_room_categories(state: &AppState, room_code: &str) {
             answer: format!("Answer {}", i + 1),
             value: (i as u32 + 1) * 100,
             answered: false,
+            mode: QuestionMode::Buzzer,
+            media_url: None,
+            media_type: None,
         })
         .collect();
 
@@ -186,6 +258,18 @@ pub async fn play_question(
     c_idx: usize,
     q_idx: usize,
     correct: bool,
+) {
+    play_question_with(host_ws, player_ws, &mut [], c_idx, q_idx, correct).await;
+}
+
+/// Play a question where `player_ws` buzzes and `others` only load the question
+pub async fn play_question_with(
+    host_ws: &mut WsStream,
+    player_ws: &mut WsStream,
+    others: &mut [&mut WsStream],
+    c_idx: usize,
+    q_idx: usize,
+    correct: bool,
 ) {
     send_msg_and_recv_all(
         host_ws,
@@ -197,20 +281,27 @@ pub async fn play_question(
     .await;
     let _ = recv_msgs(player_ws).await;
 
+    for other in others.iter_mut() {
+        send_msg_and_recv_all(other, &WsMsg::QuestionLoaded {}).await;
+    }
+
+    // Player has rendered the question
+    send_msg_and_recv_all(player_ws, &WsMsg::QuestionLoaded {}).await;
+
     // Host starts question
     send_msg_and_recv_all(host_ws, &WsMsg::HostReady {}).await;
     let _ = recv_msgs(player_ws).await;
 
     // Player buzz
-    send_msg_and_recv_all(player_ws, &WsMsg::Buzz {}).await;
+    send_msg_and_recv_all(player_ws, &WsMsg::Buzz { seq: None }).await;
     let _ = recv_msgs(host_ws).await;
 
     // Host checks answer
-    send_msg_and_recv_all(host_ws, &WsMsg::HostChecked { correct }).await;
+    send_msg_and_recv_all(host_ws, &WsMsg::host_checked(correct)).await;
     let _ = recv_msgs(player_ws).await;
 
     // Host continues from answer reveal
-    send_msg_and_recv_all(host_ws, &WsMsg::HostContinue {}).await;
+    send_msg_and_recv_all(host_ws, &WsMsg::host_continue()).await;
     let _ = recv_msgs(player_ws).await;
 }
 
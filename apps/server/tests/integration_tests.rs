@@ -5,7 +5,7 @@ use std::time::Duration;
 use tokio::time::sleep;
 
 use common::*;
-use madhacks2025::{GameState, PlayerEntry, ws_msg::WsMsg};
+use madhacks2025::{GameState, PLAYER_COLORS, PlayerEntry, ws_msg::WsMsg};
 
 mod smoke_tests {
     use super::*;
@@ -22,6 +22,121 @@ mod smoke_tests {
         assert_eq!(body, "Server is up");
     }
 
+    #[tokio::test]
+    async fn test_room_codes_are_normalized() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+
+        let (_lower_ws, _) = add_player(port, &room_code.to_lowercase(), "AJ").await;
+        let (_padded_ws, _) = add_player(port, &format!("%20{}%20", room_code), "Sam").await;
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(room.players.len(), 2);
+        }
+
+        let cpr = |code: String| async move {
+            let url = format!("http://127.0.0.1:{}/api/v1/rooms/{}/cpr", port, code);
+            reqwest::get(&url).await.expect("CPR request failed")
+        };
+        let response = cpr(room_code.to_lowercase()).await;
+        assert_eq!(response.status(), 200);
+        let body = response.text().await.expect("Failed to read body");
+        assert!(body.starts_with("Ok"), "{}", body);
+
+        assert_eq!(cpr("IO0-1".to_string()).await.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_configured_room_code_length() {
+        let (_server, port, _state) =
+            start_test_server_with(std::sync::Arc::new(madhacks2025::AppState {
+                room_codes: madhacks2025::room_code::RoomCodeConfig::new(9, None)
+                    .expect("Valid room code config"),
+                ..madhacks2025::AppState::new()
+            }))
+            .await;
+
+        let room_code = create_room_http(port).await;
+        assert_eq!(room_code.len(), 9);
+        let (_ws, _) = add_player(port, &room_code.to_lowercase(), "AJ").await;
+    }
+
+    #[tokio::test]
+    async fn test_room_creation_stops_when_codes_run_out() {
+        let (_server, port, state) =
+            start_test_server_with(std::sync::Arc::new(madhacks2025::AppState {
+                room_codes: madhacks2025::room_code::RoomCodeConfig::new(1, Some("AB"))
+                    .expect("Valid room code config"),
+                ..madhacks2025::AppState::new()
+            }))
+            .await;
+
+        let url = format!("http://127.0.0.1:{}/api/v1/rooms/create", port);
+        let client = reqwest::Client::new();
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            let response = client
+                .post(&url)
+                .json(&serde_json::json!({}))
+                .send()
+                .await
+                .expect("Create request failed");
+            statuses.push(response.status().as_u16());
+        }
+        assert_eq!(statuses, [201, 201, 503]);
+        assert_eq!(state.room_map.lock().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_room_creation_is_rate_limited() {
+        let (_server, port, _state) =
+            start_test_server_with(std::sync::Arc::new(madhacks2025::AppState {
+                create_limiter: madhacks2025::rate_limit::RateLimiter::new(
+                    3,
+                    Duration::from_secs(60),
+                ),
+                ..madhacks2025::AppState::new()
+            }))
+            .await;
+
+        let url = format!("http://127.0.0.1:{}/api/v1/rooms/create", port);
+        let client = reqwest::Client::new();
+        let mut statuses = Vec::new();
+        for _ in 0..5 {
+            let response = client
+                .post(&url)
+                .json(&serde_json::json!({}))
+                .send()
+                .await
+                .expect("Create request failed");
+            statuses.push(response.status().as_u16());
+        }
+        assert_eq!(statuses, [201, 201, 201, 429, 429]);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_json() {
+        let (_server, port, _state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let (_player_ws, _) = add_player(port, &room_code, "AJ").await;
+
+        let url = format!("http://127.0.0.1:{}/health", port);
+        let response = reqwest::Client::new()
+            .get(&url)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .expect("Health check failed");
+
+        assert_eq!(response.status(), 200);
+        let json: serde_json::Value = response.json().await.expect("Health should be JSON");
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["room_count"], 1);
+        assert_eq!(json["total_players"], 1);
+        assert!(json["uptime_seconds"].is_u64());
+    }
+
     #[tokio::test]
     async fn test_create_room_via_http() {
         let (_server, port, state) = start_test_server().await;
@@ -108,10 +223,9 @@ mod gameplay_tests {
     }
 
     #[tokio::test]
-    async fn test_multiple_players_join() {
+    async fn test_single_join_sends_one_player_list_and_initial_state() {
         let (_server, port, state) = start_test_server().await;
         let room_code = create_room_http(port).await;
-
         let host_token = {
             let room_map = state.room_map.lock().await;
             room_map
@@ -123,39 +237,52 @@ mod gameplay_tests {
         let mut host_ws =
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
         let _initial = recv_msgs(&mut host_ws).await;
+        let count_lists = |msgs: &[WsMsg]| {
+            msgs.iter()
+                .filter(|m| matches!(m, WsMsg::PlayerList(_)))
+                .count()
+        };
 
-        let (_alice_ws, _alice_id) = add_player(port, &room_code, "Alice").await;
-        let _host_update1 = recv_msgs(&mut host_ws).await;
-
-        let (_bob_ws, _bob_id) = add_player(port, &room_code, "Bob").await;
-        let _host_update2 = recv_msgs(&mut host_ws).await;
-
-        let (_charlie_ws, _charlie_id) = add_player(port, &room_code, "Charlie").await;
-        let host_final = recv_msgs(&mut host_ws).await;
-
-        let player_list = host_final
-            .iter()
-            .find(|m| matches!(m, WsMsg::PlayerList { .. }));
-        if let Some(WsMsg::PlayerList(players)) = player_list {
-            assert_eq!(players.len(), 3, "Should have 3 players");
-            let names: Vec<&str> = players.iter().map(|p| p.name.as_str()).collect();
-            assert!(names.contains(&"Alice"));
-            assert!(names.contains(&"Bob"));
-            assert!(names.contains(&"Charlie"));
-        } else {
-            panic!("Should receive PlayerList");
-        }
+        let mut player_ws = connect_ws_client(port, &room_code, "?playerName=AJ").await;
+        let player_msgs = recv_msgs(&mut player_ws).await;
+        assert!(
+            matches!(
+                player_msgs.as_slice(),
+                [
+                    WsMsg::NewPlayer { .. },
+                    WsMsg::GameState {
+                        state: GameState::Start,
+                        ..
+                    },
+                    WsMsg::PlayerState {
+                        can_buzz: false,
+                        ..
+                    },
+                ]
+            ),
+            "Pre-game players get the board and their state, got {:?}",
+            player_msgs
+        );
+        assert_eq!(count_lists(&recv_msgs(&mut host_ws).await), 1);
 
-        let room_map = state.room_map.lock().await;
-        let room = room_map.get(&room_code).expect("Could not find room");
-        assert_eq!(room.players.len(), 3);
+        // Reserving over HTTP and then connecting is still a single join
+        let response = join_http(port, &room_code, "Sam").await;
+        let json: serde_json::Value = response.json().await.expect("Failed to parse response");
+        let token = json["token"].as_str().expect("No token in response");
+        let _sam_ws = connect_ws_client(
+            port,
+            &room_code,
+            &format!("?playerID={}&token={}", json["pid"], token),
+        )
+        .await;
+        assert_eq!(count_lists(&recv_msgs(&mut host_ws).await), 1);
     }
 
     #[tokio::test]
-    async fn test_game_flow_start_to_buzz() {
+    async fn test_one_player_list_per_join_before_and_during_game() {
         let (_server, port, state) = start_test_server().await;
         let room_code = create_room_http(port).await;
-
+        add_room_categories(state.as_ref(), &room_code).await;
         let host_token = {
             let room_map = state.room_map.lock().await;
             room_map
@@ -167,53 +294,35 @@ mod gameplay_tests {
         let mut host_ws =
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
         let _initial = recv_msgs(&mut host_ws).await;
+        let count_lists = |msgs: &[WsMsg]| {
+            msgs.iter()
+                .filter(|m| matches!(m, WsMsg::PlayerList(_)))
+                .count()
+        };
 
-        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
-        let _ = recv_msgs(&mut host_ws).await; // Consume host update
-
-        start_game(&mut host_ws, &mut [&mut player_ws]).await;
-
-        let start_msgs = send_msg_and_recv_all(&mut host_ws, &WsMsg::StartGame {}).await;
-        println!("After StartGame, host got: {:?}", start_msgs);
-
-        send_msg_and_recv_all(&mut host_ws, &WsMsg::HostReady {}).await;
-        let player_update = recv_msgs(&mut player_ws).await;
-
-        let buzz_state = player_update.iter().find(|m| {
-            if let WsMsg::GameState { state, .. } = m {
-                matches!(state, GameState::WaitingForBuzz)
-            } else {
-                false
-            }
-        });
-        assert!(
-            buzz_state.is_some(),
-            "Player should get WaitingForBuzz state"
-        );
-
-        send_msg_and_recv_all(&mut player_ws, &WsMsg::Buzz {}).await;
-        let host_buzz = recv_msgs(&mut host_ws).await;
+        let (mut alice_ws, _) = add_player(port, &room_code, "Alice").await;
+        let (mut bob_ws, _) = add_player(port, &room_code, "Bob").await;
+        assert_eq!(count_lists(&recv_msgs(&mut host_ws).await), 2);
 
-        let buzz_notification = host_buzz.iter().find(|m| matches!(m, WsMsg::Buzzed { .. }));
+        start_game(&mut host_ws, &mut [&mut alice_ws, &mut bob_ws]).await;
+        let (_charlie_ws, _) = add_player(port, &room_code, "Charlie").await;
+        assert_eq!(count_lists(&recv_msgs(&mut host_ws).await), 1);
+        let alice_msgs = recv_msgs(&mut alice_ws).await;
         assert!(
-            buzz_notification.is_some(),
-            "Host should receive PlayerBuzzed"
+            !alice_msgs
+                .iter()
+                .any(|m| matches!(m, WsMsg::GameState { .. })),
+            "A mid-game join doesn't rebroadcast the board, got {:?}",
+            alice_msgs
         );
-
-        if let Some(WsMsg::Buzzed { pid, .. }) = buzz_notification {
-            assert_eq!(*pid, player_id, "Correct player buzzed");
-        }
-
-        let room_map = state.room_map.lock().await;
-        let room = room_map.get(&room_code).expect("Could not find room");
-        assert!(matches!(room.state, GameState::Answer));
     }
 
     #[tokio::test]
-    async fn test_player_reconnect() {
+    async fn test_leave_room_removes_player_and_updates_host() {
+        use futures::StreamExt;
+
         let (_server, port, state) = start_test_server().await;
         let room_code = create_room_http(port).await;
-
         let host_token = {
             let room_map = state.room_map.lock().await;
             room_map
@@ -222,103 +331,38 @@ mod gameplay_tests {
                 .host_token
                 .clone()
         };
-        let mut _host_ws =
+        let mut host_ws =
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+        let (mut alice_ws, _) = add_player(port, &room_code, "Alice").await;
+        let (_bob_ws, bob_id) = add_player(port, &room_code, "Bob").await;
+        let _ = recv_msgs(&mut host_ws).await;
 
-        let (player_ws, player_id) = add_player(port, &room_code, "AJ").await;
-        let player_token = {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not find room");
-            room.players
-                .iter()
-                .find(|p| p.player.pid == player_id)
-                .expect("Could not find player")
-                .player
-                .token
-                .clone()
-        };
-
-        {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not find room");
-            assert_eq!(
-                room.players.len(),
-                1,
-                "Should have 1 player before disconnect"
-            );
-        }
-
-        drop(player_ws);
-        sleep(Duration::from_millis(100)).await;
-
-        {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not find room");
-            assert_eq!(
-                room.players.len(),
-                1,
-                "Should have 1 player after disconnect"
-            );
-        }
-
-        // Reconnect
-        let mut player_reconnect = connect_ws_client(
-            port,
-            &room_code,
-            &format!("?token={}&playerID={}", player_token, player_id),
-        )
-        .await;
-
-        let reconnect_msgs = recv_msgs(&mut player_reconnect).await;
-
-        let got_new_player = reconnect_msgs
-            .iter()
-            .any(|m| matches!(m, WsMsg::NewPlayer { .. }));
-        assert!(!got_new_player, "Should not get NewPlayer on reconnect");
-
-        let has_state = reconnect_msgs
-            .iter()
-            .any(|m| matches!(m, WsMsg::PlayerState { .. } | WsMsg::GameState { .. }));
-        assert!(has_state, "Should receive state on reconnect");
-
-        if let Some(WsMsg::PlayerState { pid, .. }) = reconnect_msgs
-            .iter()
-            .find(|m| matches!(m, WsMsg::PlayerState { .. }))
-        {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not find room ");
-            let player = room
-                .players
-                .iter()
-                .find(|p| &p.player.pid == pid)
-                .map(|p| &p.player)
-                .expect("Could not find player");
-            assert_eq!(
-                player.pid, player_id,
-                "Reconnected player should have same ID"
-            );
-            assert_eq!(
-                player.name, "AJ",
-                "Reconnected player should have same name"
-            );
-        }
+        send_msg_and_recv_all(&mut alice_ws, &WsMsg::LeaveRoom {}).await;
+        let host_msgs = recv_msgs(&mut host_ws).await;
+        assert!(
+            host_msgs.iter().any(|m| matches!(
+                m,
+                WsMsg::PlayerList(players) if players.len() == 1 && players[0].pid == bob_id
+            )),
+            "Host should get the list without Alice, got {:?}",
+            host_msgs
+        );
+        assert!(
+            alice_ws.next().await.is_none(),
+            "The leaving player's connection closes"
+        );
 
-        {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not find room");
-            assert_eq!(
-                room.players.len(),
-                1,
-                "Should still have 1 player after reconnect"
-            );
-        }
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert_eq!(room.players.len(), 1);
+        assert_eq!(room.players[0].player.name, "Bob");
     }
 
     #[tokio::test]
-    async fn test_correct_answer_gives_points() {
+    async fn test_multiple_players_join() {
         let (_server, port, state) = start_test_server().await;
         let room_code = create_room_http(port).await;
-        add_room_categories(state.as_ref(), &room_code).await;
 
         let host_token = {
             let room_map = state.room_map.lock().await;
@@ -332,32 +376,53 @@ mod gameplay_tests {
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
         let _initial = recv_msgs(&mut host_ws).await;
 
-        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
-        let _ = recv_msgs(&mut host_ws).await;
+        let (_alice_ws, _alice_id) = add_player(port, &room_code, "Alice").await;
+        let _host_update1 = recv_msgs(&mut host_ws).await;
 
-        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+        let (_bob_ws, _bob_id) = add_player(port, &room_code, "Bob").await;
+        let _host_update2 = recv_msgs(&mut host_ws).await;
 
-        play_question(&mut host_ws, &mut player_ws, 0, 0, true).await;
+        let (_charlie_ws, _charlie_id) = add_player(port, &room_code, "Charlie").await;
+        let host_final = recv_msgs(&mut host_ws).await;
 
-        let room_map = state.room_map.lock().await;
-        let score = get_player_score(&room_map, &room_code, player_id);
-        assert_eq!(score, 100, "Score should be 100 after correct answer");
+        let player_list = host_final
+            .iter()
+            .find(|m| matches!(m, WsMsg::PlayerList { .. }));
+        if let Some(WsMsg::PlayerList(players)) = player_list {
+            assert_eq!(players.len(), 3, "Should have 3 players");
+            let names: Vec<&str> = players.iter().map(|p| p.name.as_str()).collect();
+            assert!(names.contains(&"Alice"));
+            assert!(names.contains(&"Bob"));
+            assert!(names.contains(&"Charlie"));
+        } else {
+            panic!("Should receive PlayerList");
+        }
 
+        let room_map = state.room_map.lock().await;
         let room = room_map.get(&room_code).expect("Could not find room");
-        assert!(matches!(room.state, GameState::Selection));
+        assert_eq!(room.players.len(), 3);
+    }
+
+    async fn join_http(port: u16, room_code: &str, name: &str) -> reqwest::Response {
+        let url = format!("http://127.0.0.1:{}/api/v1/rooms/{}/join", port, room_code);
+        reqwest::Client::new()
+            .post(&url)
+            .json(&serde_json::json!({ "player_name": name }))
+            .send()
+            .await
+            .expect("Join request failed")
     }
 
     #[tokio::test]
-    async fn test_incorrect_answer_deducts_points() {
+    async fn test_join_over_http_then_connect() {
         let (_server, port, state) = start_test_server().await;
         let room_code = create_room_http(port).await;
-        add_room_categories(state.as_ref(), &room_code).await;
 
         let host_token = {
             let room_map = state.room_map.lock().await;
             room_map
                 .get(&room_code)
-                .expect("Could not find room code")
+                .expect("Could not find room")
                 .host_token
                 .clone()
         };
@@ -365,112 +430,186 @@ mod gameplay_tests {
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
         let _initial = recv_msgs(&mut host_ws).await;
 
-        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
-        let _ = recv_msgs(&mut host_ws).await;
-
-        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+        let response = join_http(port, &room_code, "AJ").await;
+        assert_eq!(response.status(), 200);
+        let json: serde_json::Value = response.json().await.expect("Failed to parse response");
+        let pid = json["pid"].as_u64().expect("No pid in response");
+        let token = json["token"].as_str().expect("No token in response");
 
-        play_question(&mut host_ws, &mut player_ws, 0, 0, false).await;
+        let host_msgs = recv_msgs(&mut host_ws).await;
+        assert!(
+            host_msgs
+                .iter()
+                .any(|m| matches!(m, WsMsg::PlayerList(players) if players.len() == 1)),
+            "Host should see the reserved player"
+        );
 
-        let room_map = state.room_map.lock().await;
-        let score = get_player_score(&room_map, &room_code, player_id);
-        assert_eq!(score, -100, "Score should be -100 after correct answer");
+        let mut player_ws = connect_ws_client(
+            port,
+            &room_code,
+            &format!("?playerID={}&token={}", pid, token),
+        )
+        .await;
+        let player_msgs = recv_msgs(&mut player_ws).await;
+        assert!(
+            player_msgs
+                .iter()
+                .any(|m| matches!(m, WsMsg::PlayerState { pid: id, .. } if u64::from(*id) == pid)),
+            "Player should be attached as an existing player, got {:?}",
+            player_msgs
+        );
+        assert!(
+            !player_msgs
+                .iter()
+                .any(|m| matches!(m, WsMsg::NewPlayer { .. })),
+            "No second identity should be created"
+        );
 
+        let room_map = state.room_map.lock().await;
         let room = room_map.get(&room_code).expect("Could not find room");
-        assert!(matches!(room.state, GameState::Selection));
+        assert_eq!(room.players.len(), 1);
+        assert!(matches!(
+            room.players[0].status,
+            madhacks2025::ConnectionStatus::Connected
+        ));
     }
 
     #[tokio::test]
-    async fn test_host_reconnect() {
-        let (_server, port, state) = start_test_server().await;
+    async fn test_join_over_http_is_rate_limited() {
+        let (_server, port, _state) =
+            start_test_server_with(std::sync::Arc::new(madhacks2025::AppState {
+                join_limiter: madhacks2025::rate_limit::RateLimiter::new(
+                    2,
+                    Duration::from_secs(60),
+                ),
+                ..madhacks2025::AppState::new()
+            }))
+            .await;
         let room_code = create_room_http(port).await;
 
-        let host_token = {
-            let room_map = state.room_map.lock().await;
-            room_map
-                .get(&room_code)
-                .expect("Could not find room")
-                .host_token
-                .clone()
-        };
-
-        let mut host_ws =
-            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
-        let _initial = recv_msgs(&mut host_ws).await;
-
-        let (mut player_ws, _player_id) = add_player(port, &room_code, "AJ").await;
-        let _ = recv_msgs(&mut host_ws).await;
-
-        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+        let mut statuses = Vec::new();
+        for name in ["A", "B", "C"] {
+            statuses.push(join_http(port, &room_code, name).await.status().as_u16());
+        }
+        assert_eq!(statuses, [200, 200, 429]);
+    }
 
-        send_msg_and_recv_all(
-            &mut host_ws,
-            &WsMsg::HostChoice {
-                category_index: 0,
-                question_index: 0,
-            },
+    #[tokio::test]
+    async fn test_unclaimed_reservations_expire() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "max_players": 2 } }),
         )
         .await;
-        let _ = recv_msgs(&mut player_ws).await;
-
-        let state_before = {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not find room");
-            room.state.clone()
-        };
-        assert!(matches!(state_before, GameState::QuestionReading));
+        let (_player_ws, player_id) = add_player(port, &room_code, "AJ").await;
 
-        // Host Disconnect
-        drop(host_ws);
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        assert_eq!(join_http(port, &room_code, "Ghost").await.status(), 200);
+        assert_eq!(
+            join_http(port, &room_code, "Sam").await.status(),
+            409,
+            "The reservation holds the last slot"
+        );
 
-        let mut host_reconnect =
-            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
-        let reconnect_msgs = recv_msgs(&mut host_reconnect).await;
+        {
+            let mut room_map = state.room_map.lock().await;
+            let room = room_map.get_mut(&room_code).expect("Could not find room");
+            for entry in &mut room.players {
+                entry.reserved_at = entry
+                    .reserved_at
+                    .map(|at| at - madhacks2025::handshake::RESERVATION_TTL);
+            }
+        }
+        assert_eq!(join_http(port, &room_code, "Sam").await.status(), 200);
 
-        let game_state_msg = reconnect_msgs
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        let names: Vec<&str> = room
+            .players
             .iter()
-            .find(|m| matches!(m, WsMsg::GameState { .. }));
-        assert!(
-            game_state_msg.is_some(),
-            "Host should receive GameState on reconnect"
-        );
+            .map(|p| p.player.name.as_str())
+            .collect();
+        assert_eq!(names, ["AJ", "Sam"]);
+        assert_eq!(room.players[0].player.pid, player_id);
+    }
+
+    async fn join_http_pid(port: u16, room_code: &str, name: &str) -> u32 {
+        let json: serde_json::Value = join_http(port, room_code, name)
+            .await
+            .json()
+            .await
+            .expect("Failed to parse response");
+        json["pid"]
+            .as_u64()
+            .and_then(|pid| pid.try_into().ok())
+            .expect("No pid in response")
+    }
+
+    #[tokio::test]
+    async fn test_player_ids_are_never_reused() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+
+        let aj = join_http_pid(port, &room_code, "AJ").await;
+        let sam = join_http_pid(port, &room_code, "Sam").await;
+        assert_ne!(aj, sam);
+        assert_ne!(aj, 0, "0 is the host's id");
 
-        if let Some(WsMsg::GameState {
-            state,
-            players,
-            current_question,
-            ..
-        }) = game_state_msg
         {
-            assert!(matches!(state, GameState::QuestionReading));
-            assert_eq!(players.len(), 1, "Should still have 1 player");
-            assert_eq!(
-                current_question,
-                &Some((0, 0)),
-                "Should have current question set"
-            );
+            let mut room_map = state.room_map.lock().await;
+            let room = room_map.get_mut(&room_code).expect("Could not find room");
+            room.players.retain(|p| p.player.pid != sam);
         }
 
-        send_msg_and_recv_all(&mut host_reconnect, &WsMsg::HostReady {}).await;
-        let player_ready = recv_msgs(&mut player_ws).await;
+        let charlie = join_http_pid(port, &room_code, "Charlie").await;
+        assert_ne!(charlie, sam, "A removed player's id must not be reused");
+        assert_ne!(charlie, aj);
+    }
 
-        let waiting_state = player_ready.iter().any(|m| {
-            matches!(
-                m,
-                WsMsg::GameState {
-                    state: GameState::WaitingForBuzz,
-                    ..
-                }
-            )
-        });
-        assert!(waiting_state, "Game should continue after host reconnects");
+    #[tokio::test]
+    async fn test_join_over_http_respects_room_limits() {
+        let (_server, port, _state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "max_players": 1 } }),
+        )
+        .await;
+
+        assert_eq!(join_http(port, &room_code, "AJ").await.status(), 200);
+        assert_eq!(join_http(port, &room_code, "Sam").await.status(), 409);
+        assert_eq!(join_http(port, "NOPE", "Sam").await.status(), 404);
     }
 
     #[tokio::test]
-    async fn test_full_game() {
+    async fn test_create_room_with_multiple_boards() {
         let (_server, port, state) = start_test_server().await;
-        let room_code = create_room_http(port).await;
+        let board = |title: &str, value: u32| {
+            serde_json::json!([{
+                "title": title,
+                "questions": [{ "question": "Q", "answer": "A", "value": value }],
+            }])
+        };
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "boards": [board("Jeopardy", 200), board("Double", 400)] }),
+        )
+        .await;
+
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert_eq!(room.boards.len(), 2);
+        assert_eq!(room.current_board, 0);
+        assert_eq!(room.categories[0].title, "Jeopardy");
+    }
+
+    #[tokio::test]
+    async fn test_players_start_with_configured_score() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "starting_score": 500 } }),
+        )
+        .await;
         add_room_categories(state.as_ref(), &room_code).await;
 
         let host_token = {
@@ -481,68 +620,68 @@ mod gameplay_tests {
                 .host_token
                 .clone()
         };
-
         let mut host_ws =
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
         let _initial = recv_msgs(&mut host_ws).await;
 
         let (mut aj_ws, aj_id) = add_player(port, &room_code, "AJ").await;
         let _ = recv_msgs(&mut host_ws).await;
+        start_game(&mut host_ws, &mut [&mut aj_ws]).await;
 
-        let (mut sam_ws, sam_id) = add_player(port, &room_code, "Sam").await;
-        let _ = recv_msgs(&mut host_ws).await;
+        // Joining mid-game uses the same starting score
+        let (_sam_ws, sam_id) = add_player(port, &room_code, "Sam").await;
 
-        start_game(&mut host_ws, &mut [&mut aj_ws, &mut sam_ws]).await;
+        let room_map = state.room_map.lock().await;
+        assert_eq!(get_player_score(&room_map, &room_code, aj_id), 500);
+        assert_eq!(get_player_score(&room_map, &room_code, sam_id), 500);
+    }
 
-        // Question 1: AJ buzzes and gets it correct (+100)
-        play_question(&mut host_ws, &mut aj_ws, 0, 0, true).await;
-        let _ = recv_msgs(&mut sam_ws).await;
+    #[tokio::test]
+    async fn test_players_get_distinct_colors() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
 
-        {
-            let room_map = state.room_map.lock().await;
-            assert_eq!(get_player_score(&room_map, &room_code, aj_id), 100);
+        let names = ["AJ", "Sam", "Riley", "Jo"];
+        let mut connections = Vec::new();
+        for name in names {
+            connections.push(add_player(port, &room_code, name).await);
         }
 
-        // Question 2: Sam buzzes and gets it incorrect (-200)
-        play_question(&mut host_ws, &mut sam_ws, 0, 1, false).await;
-        let _ = recv_msgs(&mut aj_ws).await;
-
-        {
-            let room_map = state.room_map.lock().await;
-            assert_eq!(get_player_score(&room_map, &room_code, aj_id), 100);
-            assert_eq!(get_player_score(&room_map, &room_code, sam_id), -200);
-        }
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        let colors: Vec<&str> = room
+            .players
+            .iter()
+            .map(|p| p.player.color.as_str())
+            .collect();
+        assert_eq!(colors, PLAYER_COLORS[..names.len()].to_vec());
+    }
 
-        // Question 2 again: AJ buzzes and gets it correct (+200 = 300 total)
-        play_question(&mut host_ws, &mut aj_ws, 0, 1, true).await;
-        let _ = recv_msgs(&mut sam_ws).await;
+    #[tokio::test]
+    async fn test_colors_stay_distinct_after_a_player_leaves() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
 
-        // Question 3: AJ buzzes and gets it correct (+400 = 600 total)
-        play_question(&mut host_ws, &mut aj_ws, 0, 2, true).await;
-        let _ = recv_msgs(&mut sam_ws).await;
+        let (mut aj_ws, _) = add_player(port, &room_code, "AJ").await;
+        let (_sam_ws, _) = add_player(port, &room_code, "Sam").await;
+        send_msg_and_recv_all(&mut aj_ws, &WsMsg::LeaveRoom {}).await;
+        let (_riley_ws, _) = add_player(port, &room_code, "Riley").await;
 
-        {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not find room");
-            assert_eq!(
-                get_player_score(&room_map, &room_code, aj_id),
-                600,
-                "AJ should have 600 points"
-            );
-            assert_eq!(
-                get_player_score(&room_map, &room_code, sam_id),
-                -200,
-                "Sam should have -200 points"
-            );
-            assert!(matches!(room.state, GameState::GameEnd));
-        }
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        let colors: Vec<&str> = room
+            .players
+            .iter()
+            .map(|p| p.player.color.as_str())
+            .collect();
+        assert_eq!(colors.len(), 2);
+        assert_ne!(colors[0], colors[1], "Riley mustn't reuse Sam's color");
     }
 
     #[tokio::test]
-    async fn test_concurrent_buzzes() {
+    async fn test_duplicate_names_are_suffixed() {
         let (_server, port, state) = start_test_server().await;
         let room_code = create_room_http(port).await;
-        add_room_categories(&state, &room_code).await;
 
         let host_token = {
             let room_map = state.room_map.lock().await;
@@ -556,95 +695,105 @@ mod gameplay_tests {
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
         let _initial = recv_msgs(&mut host_ws).await;
 
-        let (mut aj_ws, aj_id) = add_player(port, &room_code, "AJ").await;
+        let (_aj1_ws, _) = add_player(port, &room_code, "AJ").await;
         let _ = recv_msgs(&mut host_ws).await;
-
-        let (mut sam_ws, sam_id) = add_player(port, &room_code, "Sam").await;
+        let (_aj2_ws, _) = add_player(port, &room_code, "AJ").await;
         let _ = recv_msgs(&mut host_ws).await;
+        let (_aj3_ws, _) = add_player(port, &room_code, "aj").await;
+        let host_final = recv_msgs(&mut host_ws).await;
 
-        start_game(&mut host_ws, &mut [&mut aj_ws, &mut sam_ws]).await;
-
-        send_msg_and_recv_all(
-            &mut host_ws,
-            &WsMsg::HostChoice {
-                category_index: 0,
-                question_index: 0,
-            },
-        )
-        .await;
-
-        let _ = recv_msgs(&mut aj_ws).await;
-        let _ = recv_msgs(&mut sam_ws).await;
-
-        send_msg_and_recv_all(&mut host_ws, &WsMsg::HostReady {}).await;
-        let _ = recv_msgs(&mut aj_ws).await;
-        let _ = recv_msgs(&mut sam_ws).await;
-
-        let aj_buzz = tokio::spawn({
-            let mut ws = aj_ws;
-            async move {
-                send_msg_and_recv_all(&mut ws, &WsMsg::Buzz {}).await;
-                ws
-            }
-        });
-
-        let sam_buzz = tokio::spawn({
-            let mut ws = sam_ws;
-            async move {
-                send_msg_and_recv_all(&mut ws, &WsMsg::Buzz {}).await;
-                ws
-            }
-        });
-
-        let _aj_ws = aj_buzz.await.expect("Could not find AJ websocket");
-        let _sam_ws = sam_buzz.await.expect("Could not find Sam websocket");
-
-        let host_msgs = recv_msgs(&mut host_ws).await;
-        let buzz_count = host_msgs
-            .iter()
-            .filter(|m| matches!(m, WsMsg::Buzzed { .. }))
-            .count();
-        assert_eq!(buzz_count, 1, "Host should receive exactly one buzz");
-
-        let buzzed_player = host_msgs
+        let players = host_final
             .iter()
+            .rev()
             .find_map(|m| {
-                if let WsMsg::Buzzed { pid, .. } = m {
-                    Some(*pid)
+                if let WsMsg::PlayerList(players) = m {
+                    Some(players)
                 } else {
                     None
                 }
             })
-            .expect("Should have a buzzed player");
+            .expect("Should receive PlayerList");
+
+        let names: Vec<&str> = players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["AJ", "AJ (2)", "aj (3)"]);
+    }
+
+    #[tokio::test]
+    async fn test_room_full_rejects_join() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "max_players": 2 } }),
+        )
+        .await;
+
+        // Filling the room up to exactly the cap works
+        let (_p1_ws, _) = add_player(port, &room_code, "P1").await;
+        let (_p2_ws, _) = add_player(port, &room_code, "P2").await;
+
+        // One over the cap is turned away
+        let mut p3_ws = connect_ws_client(port, &room_code, "?playerName=P3").await;
+        let msgs = recv_msgs(&mut p3_ws).await;
 
         assert!(
-            buzzed_player == aj_id || buzzed_player == sam_id,
-            "Buzzed player should be either Alice or Bob"
+            msgs.iter()
+                .any(|m| matches!(m, WsMsg::Error { code, .. } if code == "room_full")),
+            "Should receive room_full error, got {:?}",
+            msgs
+        );
+        assert!(
+            !msgs.iter().any(|m| matches!(m, WsMsg::NewPlayer { .. })),
+            "Should not be assigned an id"
         );
 
-        {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not find room");
-            assert_eq!(
-                room.current_buzzer,
-                Some(buzzed_player),
-                "Only one player should be the buzzer"
-            );
-
-            let buzzer = room
-                .players
-                .iter()
-                .find(|p| p.player.pid == buzzed_player)
-                .expect("Could not find buzzed player");
-            assert!(buzzer.player.buzzed, "Buzzer should be marked as buzzed");
-        }
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert_eq!(room.players.len(), 2, "Room should stay at the cap");
     }
 
     #[tokio::test]
-    async fn test_concurrent_player_joins() {
+    async fn test_reconnect_allowed_when_room_full() {
         let (_server, port, state) = start_test_server().await;
-        let room_code = create_room_http(port).await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "max_players": 1 } }),
+        )
+        .await;
+
+        let (player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let player_token = {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            room.players[0].player.token.clone()
+        };
+
+        drop(player_ws);
+        sleep(Duration::from_millis(100)).await;
+
+        let mut reconnect_ws = connect_ws_client(
+            port,
+            &room_code,
+            &format!("?token={}&playerID={}", player_token, player_id),
+        )
+        .await;
+        let msgs = recv_msgs(&mut reconnect_ws).await;
+
+        assert!(
+            msgs.iter()
+                .any(|m| matches!(m, WsMsg::PlayerState { pid, .. } if *pid == player_id)),
+            "Existing player should reconnect at capacity"
+        );
+        assert!(!msgs.iter().any(|m| matches!(m, WsMsg::Error { .. })));
+    }
 
+    #[tokio::test]
+    async fn test_locked_room_rejects_new_players_but_not_reconnects() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "auto_lock": true } }),
+        )
+        .await;
         let host_token = {
             let room_map = state.room_map.lock().await;
             room_map
@@ -655,57 +804,116 @@ mod gameplay_tests {
         };
         let mut host_ws =
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
-        let _initial = recv_msgs(&mut host_ws).await;
+        let (player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let player_token = {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            room.players[0].player.token.clone()
+        };
+        send_msg_and_recv_all(&mut host_ws, &WsMsg::StartGame {}).await;
 
-        let mut join_handles = vec![];
-        for i in 0..5 {
-            let room_code = room_code.clone();
-            let handle = tokio::spawn(async move {
-                let name = format!("Player{}", i);
-                add_player(port, &room_code, &name).await
-            });
-            join_handles.push(handle);
-        }
+        let mut late_ws = connect_ws_client(port, &room_code, "?playerName=Sam").await;
+        let msgs = recv_msgs(&mut late_ws).await;
+        assert!(
+            matches!(&msgs[..], [WsMsg::Error { code, .. }] if code == "room_locked"),
+            "Got {:?}",
+            msgs
+        );
+        let url = format!("http://127.0.0.1:{}/api/v1/rooms/{}/join", port, room_code);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .json(&serde_json::json!({ "player_name": "Sam" }))
+            .send()
+            .await
+            .expect("Join request failed");
+        assert_eq!(response.status(), 409);
 
-        let mut player_ids = vec![];
-        for handle in join_handles {
-            let (_ws, id) = handle.await.expect("Could not find ws handle");
-            player_ids.push(id);
+        drop(player_ws);
+        sleep(Duration::from_millis(100)).await;
+        let mut reconnect_ws = connect_ws_client(
+            port,
+            &room_code,
+            &format!("?token={}&playerID={}", player_token, player_id),
+        )
+        .await;
+        let msgs = recv_msgs(&mut reconnect_ws).await;
+        assert!(
+            msgs.iter()
+                .any(|m| matches!(m, WsMsg::PlayerState { pid, .. } if *pid == player_id)),
+            "Existing player should reconnect to a locked room"
+        );
+
+        send_msg_and_recv_all(&mut host_ws, &WsMsg::LockRoom { locked: false }).await;
+        let (_sam_ws, _) = add_player(port, &room_code, "Sam").await;
+    }
+
+    #[tokio::test]
+    async fn test_handshake_errors_are_reported() {
+        let (_server, port, _state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let (_player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+
+        let cases = [
+            ("".to_string(), "missing_credentials"),
+            ("?token=not-a-real-token".to_string(), "invalid_token"),
+            (
+                format!("?token=not-a-real-token&playerID={}", player_id),
+                "invalid_token",
+            ),
+            (
+                "?token=whatever&playerID=9999".to_string(),
+                "player_not_found",
+            ),
+        ];
+
+        for (query, expected_code) in cases {
+            let mut ws = connect_ws_client(port, &room_code, &query).await;
+            let msgs = recv_msgs(&mut ws).await;
+
+            assert!(
+                matches!(
+                    &msgs[..],
+                    [WsMsg::Error { code, message }] if code == expected_code && !message.is_empty()
+                ),
+                "Query {:?} should produce a single {} error, got {:?}",
+                query,
+                expected_code,
+                msgs
+            );
         }
+    }
 
-        sleep(Duration::from_millis(200)).await;
+    #[tokio::test]
+    async fn test_repeated_bad_tokens_are_refused() {
+        let (_server, port, _state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let (_player_ws, player_id) = add_player(port, &room_code, "AJ").await;
 
-        {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not find room");
-            assert_eq!(room.players.len(), 5, "Should have 5 players");
+        let guess = format!("?token=guessed&playerID={}", player_id);
+        for _ in 0..madhacks2025::handshake::MAX_HANDSHAKE_FAILURES {
+            let mut ws = connect_ws_client(port, &room_code, &guess).await;
+            let msgs = recv_msgs(&mut ws).await;
+            assert!(matches!(&msgs[..], [WsMsg::Error { code, .. }] if code == "invalid_token"));
         }
 
-        let mut unique_ids = player_ids.clone();
-        unique_ids.sort();
-        unique_ids.dedup();
-        assert_eq!(
-            unique_ids.len(),
-            player_ids.len(),
-            "All player IDs should be unique"
+        let url = format!(
+            "ws://127.0.0.1:{}/api/v1/rooms/{}/ws?playerName=Sam",
+            port, room_code
         );
-
-        let final_msgs = recv_msgs(&mut host_ws).await;
-        let final_list = final_msgs.iter().rev().find_map(|m| {
-            if let WsMsg::PlayerList(players) = m {
-                Some(players)
-            } else {
-                None
+        match tokio_tungstenite::connect_async(&url).await {
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status(), 429);
             }
-        });
-
-        if let Some(players) = final_list {
-            assert_eq!(players.len(), 5, "Final player list should have 5 players");
+            other => panic!("Connection should be refused, got {:?}", other.map(|_| ())),
         }
+
+        // Another room doesn't share the count
+        let other_room = create_room_http(port).await;
+        let (_other_ws, _) = add_player(port, &other_room, "Sam").await;
     }
 
     #[tokio::test]
-    async fn test_heartbeat_roundtrip() {
+    async fn test_game_flow_start_to_buzz() {
         let (_server, port, state) = start_test_server().await;
         let room_code = create_room_http(port).await;
 
@@ -717,57 +925,53 @@ mod gameplay_tests {
                 .host_token
                 .clone()
         };
-        let mut _host_ws =
+        let mut host_ws =
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
         let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await; // Consume host update
 
-        let url = format!("http://127.0.0.1:{}/api/v1/rooms/{}/cpr", port, room_code);
-        let _response = reqwest::get(&url).await.expect("CPR request failed");
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
 
-        let player_msgs = recv_msgs(&mut player_ws).await;
+        let start_msgs = send_msg_and_recv_all(&mut host_ws, &WsMsg::StartGame {}).await;
+        println!("After StartGame, host got: {:?}", start_msgs);
 
-        let do_heartbeat = player_msgs.iter().find_map(|m| {
-            if let WsMsg::DoHeartbeat { hbid, t_sent } = m {
-                Some((*hbid, *t_sent))
+        send_msg_and_recv_all(&mut host_ws, &WsMsg::HostReady {}).await;
+        let player_update = recv_msgs(&mut player_ws).await;
+
+        let buzz_state = player_update.iter().find(|m| {
+            if let WsMsg::GameState { state, .. } = m {
+                matches!(state, GameState::WaitingForBuzz)
             } else {
-                None
+                false
             }
         });
+        assert!(
+            buzz_state.is_some(),
+            "Player should get WaitingForBuzz state"
+        );
 
-        assert!(do_heartbeat.is_some(), "Player should receive DoHeartbeat");
-
-        let (hbid, t_sent) = do_heartbeat.expect("Could not do heartbeat");
-
-        let t_dohb_recv = PlayerEntry::time_ms();
-        let got_msgs =
-            send_msg_and_recv_all(&mut player_ws, &WsMsg::Heartbeat { hbid, t_dohb_recv }).await;
-
-        let got_heartbeat = got_msgs
-            .iter()
-            .any(|m| matches!(m, WsMsg::GotHeartbeat { hbid: id } if *id == hbid));
-
-        assert!(got_heartbeat, "Player should receive GotHeartbeat");
-
-        let t_lat = PlayerEntry::time_ms() - t_sent;
-        send_msg_and_recv_all(&mut player_ws, &WsMsg::LatencyOfHeartbeat { hbid, t_lat }).await;
+        send_msg_and_recv_all(&mut player_ws, &WsMsg::Buzz { seq: None }).await;
+        let host_buzz = recv_msgs(&mut host_ws).await;
 
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-        {
-            let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not get room");
-            let player = room
-                .players
-                .iter()
-                .find(|p| p.player.pid == player_id)
-                .expect("Could not find player");
+        let buzz_notification = host_buzz.iter().find(|m| matches!(m, WsMsg::Buzzed { .. }));
+        assert!(
+            buzz_notification.is_some(),
+            "Host should receive PlayerBuzzed"
+        );
 
-            let latency = player.latency().expect("Could not get latency");
-            assert!(latency > 0, "Latency should be recorded");
+        if let Some(WsMsg::Buzzed { pid, .. }) = buzz_notification {
+            assert_eq!(*pid, player_id, "Correct player buzzed");
         }
+
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert!(matches!(room.state, GameState::Answer));
     }
 
     #[tokio::test]
-    async fn test_heartbeat_with_invalid_hbid() {
+    async fn test_player_reconnect() {
         let (_server, port, state) = start_test_server().await;
         let room_code = create_room_http(port).await;
 
@@ -775,47 +979,1345 @@ mod gameplay_tests {
             let room_map = state.room_map.lock().await;
             room_map
                 .get(&room_code)
-                .expect("Could not get room")
+                .expect("Could not find room")
                 .host_token
                 .clone()
         };
         let mut _host_ws =
             connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
 
-        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let (player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let player_token = {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            room.players
+                .iter()
+                .find(|p| p.player.pid == player_id)
+                .expect("Could not find player")
+                .player
+                .token
+                .clone()
+        };
 
-        let invalid_hbid = 99999;
-        let t_dohb_recv = PlayerEntry::time_ms();
-        send_msg_and_recv_all(
-            &mut player_ws,
-            &WsMsg::Heartbeat {
-                hbid: invalid_hbid,
-                t_dohb_recv,
-            },
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(
+                room.players.len(),
+                1,
+                "Should have 1 player before disconnect"
+            );
+        }
+
+        drop(player_ws);
+        sleep(Duration::from_millis(100)).await;
+
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(
+                room.players.len(),
+                1,
+                "Should have 1 player after disconnect"
+            );
+        }
+
+        // Reconnect
+        let mut player_reconnect = connect_ws_client(
+            port,
+            &room_code,
+            &format!("?token={}&playerID={}", player_token, player_id),
         )
         .await;
 
-        let got_msgs = recv_msgs(&mut player_ws).await;
-        let got_heartbeat = got_msgs
+        let reconnect_msgs = recv_msgs(&mut player_reconnect).await;
+
+        let got_new_player = reconnect_msgs
             .iter()
-            .any(|m| matches!(m, WsMsg::GotHeartbeat { .. }));
+            .any(|m| matches!(m, WsMsg::NewPlayer { .. }));
+        assert!(!got_new_player, "Should not get NewPlayer on reconnect");
 
-        assert!(
-            !got_heartbeat,
-            "Should not receive GotHeartbeat for invalid hbid"
-        );
+        let has_state = reconnect_msgs
+            .iter()
+            .any(|m| matches!(m, WsMsg::PlayerState { .. } | WsMsg::GameState { .. }));
+        assert!(has_state, "Should receive state on reconnect");
 
-        // Latency should remain 0
+        if let Some(WsMsg::PlayerState { pid, .. }) = reconnect_msgs
+            .iter()
+            .find(|m| matches!(m, WsMsg::PlayerState { .. }))
         {
             let room_map = state.room_map.lock().await;
-            let room = room_map.get(&room_code).expect("Could not get room");
+            let room = room_map.get(&room_code).expect("Could not find room ");
             let player = room
                 .players
                 .iter()
-                .find(|p| p.player.pid == player_id)
+                .find(|p| &p.player.pid == pid)
+                .map(|p| &p.player)
                 .expect("Could not find player");
             assert_eq!(
-                player.latency().expect("Could not get latency"),
+                player.pid, player_id,
+                "Reconnected player should have same ID"
+            );
+            assert_eq!(
+                player.name, "AJ",
+                "Reconnected player should have same name"
+            );
+        }
+
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(
+                room.players.len(),
+                1,
+                "Should still have 1 player after reconnect"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_rotates_token_when_enabled() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "rotate_tokens": true } }),
+        )
+        .await;
+        let (player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let old_token = {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            room.players[0].player.token.clone()
+        };
+        drop(player_ws);
+        sleep(Duration::from_millis(100)).await;
+
+        let query = format!("?token={}&playerID={}", old_token, player_id);
+        let mut player_ws = connect_ws_client(port, &room_code, &query).await;
+        let msgs = recv_msgs(&mut player_ws).await;
+        let new_token = msgs
+            .iter()
+            .find_map(|m| match m {
+                WsMsg::TokenRotated { token } => Some(token.clone()),
+                _ => None,
+            })
+            .expect("Should receive a new token");
+        assert_ne!(new_token, old_token);
+        assert!(
+            msgs.iter().any(|m| matches!(m, WsMsg::PlayerState { .. })),
+            "Reconnect still succeeds: {:?}",
+            msgs
+        );
+        drop(player_ws);
+        sleep(Duration::from_millis(100)).await;
+
+        let mut stale_ws = connect_ws_client(port, &room_code, &query).await;
+        let msgs = recv_msgs(&mut stale_ws).await;
+        assert!(
+            matches!(&msgs[..], [WsMsg::Error { code, .. }] if code == "invalid_token"),
+            "Old token should be rejected, got {:?}",
+            msgs
+        );
+
+        let query = format!("?token={}&playerID={}", new_token, player_id);
+        let mut player_ws = connect_ws_client(port, &room_code, &query).await;
+        let msgs = recv_msgs(&mut player_ws).await;
+        assert!(msgs.iter().any(|m| matches!(m, WsMsg::PlayerState { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_reconnecting_player_replays_missed_events() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let (mut chatty_ws, _) = add_player(port, &room_code, "AJ").await;
+        let (quiet_ws, quiet_id) = add_player(port, &room_code, "Sam").await;
+        let quiet_token = {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            room.players
+                .iter()
+                .find(|p| p.player.pid == quiet_id)
+                .expect("Could not find player")
+                .player
+                .token
+                .clone()
+        };
+
+        drop(quiet_ws);
+        sleep(Duration::from_millis(100)).await;
+        for text in ["first", "second"] {
+            send_msg_and_recv_all(
+                &mut chatty_ws,
+                &WsMsg::Chat {
+                    text: text.to_string(),
+                },
+            )
+            .await;
+        }
+
+        let mut quiet_ws = connect_ws_client(
+            port,
+            &room_code,
+            &format!("?token={}&playerID={}", quiet_token, quiet_id),
+        )
+        .await;
+        let _ = recv_msgs(&mut quiet_ws).await;
+
+        let replayed = |msgs: Vec<WsMsg>| {
+            msgs.into_iter()
+                .find_map(|m| match m {
+                    WsMsg::Replay { events } => Some(events),
+                    _ => None,
+                })
+                .expect("Should get a Replay")
+        };
+        let events = replayed(
+            send_msg_and_recv_all(&mut quiet_ws, &WsMsg::ResumeFrom { last_seq: 0 }).await,
+        );
+        let texts: Vec<&str> = events
+            .iter()
+            .filter_map(|e| match &e.event {
+                WsMsg::ChatMessage { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, ["first", "second"]);
+
+        let first_seq = events
+            .iter()
+            .find(|e| matches!(&e.event, WsMsg::ChatMessage { text, .. } if text == "first"))
+            .expect("First chat should be replayed")
+            .seq;
+        let events = replayed(
+            send_msg_and_recv_all(
+                &mut quiet_ws,
+                &WsMsg::ResumeFrom {
+                    last_seq: first_seq,
+                },
+            )
+            .await,
+        );
+        assert!(events.iter().all(|e| e.seq > first_seq));
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(&e.event, WsMsg::ChatMessage { text, .. } if text == "second"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_envelope_seqs_increase_across_a_game() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(&state, &room_code).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let mut enveloped_ws =
+            connect_ws_client(port, &room_code, "?playerName=AJ&envelope=true").await;
+        let mut received = recv_msgs(&mut enveloped_ws).await;
+        let (mut bare_ws, _) = add_player(port, &room_code, "Sam").await;
+        let mut bare_received = Vec::new();
+
+        for (from_host, msg) in [
+            (true, WsMsg::StartGame {}),
+            (
+                true,
+                WsMsg::HostChoice {
+                    category_index: 0,
+                    question_index: 0,
+                },
+            ),
+            (false, WsMsg::QuestionLoaded {}),
+            (true, WsMsg::HostReady {}),
+            (false, WsMsg::Buzz { seq: None }),
+            (true, WsMsg::host_checked(true)),
+            (true, WsMsg::host_continue()),
+        ] {
+            if from_host {
+                send_msg_and_recv_all(&mut host_ws, &msg).await;
+                received.extend(recv_msgs(&mut enveloped_ws).await);
+            } else {
+                received.extend(send_msg_and_recv_all(&mut enveloped_ws, &msg).await);
+                send_msg_and_recv_all(&mut bare_ws, &WsMsg::QuestionLoaded {}).await;
+            }
+            bare_received.extend(recv_msgs(&mut bare_ws).await);
+        }
+
+        // Witnesses are held back on purpose, so only they may arrive late
+        let seqs: Vec<u64> = received
+            .iter()
+            .filter_map(|m| match m {
+                WsMsg::Envelope { seq, event, .. } if !matches!(**event, WsMsg::Witness { .. }) => {
+                    Some(*seq)
+                }
+                _ => None,
+            })
+            .collect();
+        assert!(
+            seqs.len() > 5,
+            "Game events should be enveloped: {:?}",
+            received
+        );
+        assert!(
+            seqs.windows(2).all(|w| w[0] < w[1]),
+            "Seqs should increase: {:?}",
+            seqs
+        );
+        assert!(
+            received
+                .iter()
+                .any(|m| matches!(m, WsMsg::Envelope { event, .. } if matches!(**event, WsMsg::GameState { .. })))
+        );
+        assert!(
+            !bare_received
+                .iter()
+                .any(|m| matches!(m, WsMsg::Envelope { .. })),
+            "Clients that didn't opt in get bare events"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_question_reaches_players() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "witness_base_delay_ms": 0 } }),
+        )
+        .await;
+        add_room_categories(&state, &room_code).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+
+        send_msg_and_recv_all(
+            &mut host_ws,
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+        )
+        .await;
+        let _ = recv_msgs(&mut player_ws).await;
+
+        send_msg_and_recv_all(&mut host_ws, &WsMsg::ReadQuestion {}).await;
+        let msgs = recv_msgs(&mut player_ws).await;
+        assert!(
+            msgs.iter().any(|m| matches!(
+                m,
+                WsMsg::ReadQuestionNow { estimated_duration_ms, .. } if *estimated_duration_ms > 0
+            )),
+            "Players should be cued to read: {:?}",
+            msgs
+        );
+    }
+
+    #[tokio::test]
+    async fn test_correct_answer_gives_points() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(state.as_ref(), &room_code).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
+        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+
+        play_question(&mut host_ws, &mut player_ws, 0, 0, true).await;
+
+        let room_map = state.room_map.lock().await;
+        let score = get_player_score(&room_map, &room_code, player_id);
+        assert_eq!(score, 100, "Score should be 100 after correct answer");
+
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert!(matches!(room.state, GameState::Selection));
+    }
+
+    #[tokio::test]
+    async fn test_incorrect_answer_deducts_points() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(state.as_ref(), &room_code).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room code")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
+        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+
+        play_question(&mut host_ws, &mut player_ws, 0, 0, false).await;
+
+        let room_map = state.room_map.lock().await;
+        let score = get_player_score(&room_map, &room_code, player_id);
+        assert_eq!(score, -100, "Score should be -100 after correct answer");
+
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert!(matches!(room.state, GameState::Selection));
+    }
+
+    #[tokio::test]
+    async fn test_players_never_receive_answers() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(state.as_ref(), &room_code).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _ = recv_msgs(&mut host_ws).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        let host_msgs = send_msg_and_recv_all(&mut host_ws, &WsMsg::StartGame {}).await;
+        let player_msgs = recv_msgs(&mut player_ws).await;
+
+        let game_state_json = |msgs: &[WsMsg]| {
+            let msg = msgs
+                .iter()
+                .find(|m| matches!(m, WsMsg::GameState { .. }))
+                .expect("Should receive GameState");
+            serde_json::to_string(msg).expect("Failed to serialize")
+        };
+        assert!(game_state_json(&host_msgs).contains("Answer 1"));
+        let player_json = game_state_json(&player_msgs);
+        assert!(player_json.contains("Question 1"));
+        assert!(
+            !player_json.contains("Answer 1"),
+            "Players must not see answers: {}",
+            player_json
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transfer_host_rotates_token() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let old_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", old_token)).await;
+        let _ = recv_msgs(&mut host_ws).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        let msgs = send_msg_and_recv_all(
+            &mut host_ws,
+            &WsMsg::TransferHost {
+                new_host_token: true,
+            },
+        )
+        .await;
+        let new_token = msgs
+            .iter()
+            .find_map(|m| match m {
+                WsMsg::HostToken { token } => Some(token.clone()),
+                _ => None,
+            })
+            .expect("Host should get the new token");
+        assert_ne!(new_token, old_token);
+        assert!(
+            recv_msgs(&mut player_ws).await.is_empty(),
+            "Players hear nothing about it"
+        );
+
+        let mut stale_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", old_token)).await;
+        let msgs = recv_msgs(&mut stale_ws).await;
+        assert!(
+            matches!(&msgs[..], [WsMsg::Error { code, .. }] if code == "invalid_token"),
+            "Old token should no longer authenticate, got {:?}",
+            msgs
+        );
+
+        let mut new_host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", new_token)).await;
+        let msgs = recv_msgs(&mut new_host_ws).await;
+        assert!(msgs.iter().any(|m| matches!(m, WsMsg::PlayerList(_))));
+
+        // Players can't take over the room
+        let msgs = send_msg_and_recv_all(
+            &mut player_ws,
+            &WsMsg::TransferHost {
+                new_host_token: true,
+            },
+        )
+        .await;
+        assert!(!msgs.iter().any(|m| matches!(m, WsMsg::HostToken { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_players_told_when_host_drops_and_returns() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _ = recv_msgs(&mut host_ws).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+
+        drop(host_ws);
+        let msgs = recv_msgs(&mut player_ws).await;
+        assert!(
+            msgs.iter().any(|m| matches!(m, WsMsg::HostDisconnected {})),
+            "Player should hear the host dropped, got {:?}",
+            msgs
+        );
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert!(room.hosts.is_empty());
+        }
+
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _ = recv_msgs(&mut host_ws).await;
+        let msgs = recv_msgs(&mut player_ws).await;
+        assert!(
+            msgs.iter().any(|m| matches!(m, WsMsg::HostReconnected {})),
+            "Player should hear the host is back, got {:?}",
+            msgs
+        );
+    }
+
+    #[tokio::test]
+    async fn test_every_host_connection_hears_buzzes() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(state.as_ref(), &room_code).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+
+        let query = format!("?token={}", host_token);
+        let mut reader_ws = connect_ws_client(port, &room_code, &query).await;
+        let _ = recv_msgs(&mut reader_ws).await;
+        let mut scorer_ws = connect_ws_client(port, &room_code, &query).await;
+        let _ = recv_msgs(&mut scorer_ws).await;
+        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut scorer_ws).await;
+
+        start_game(&mut reader_ws, &mut [&mut player_ws]).await;
+        send_msg_and_recv_all(
+            &mut reader_ws,
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+        )
+        .await;
+        send_msg_and_recv_all(&mut player_ws, &WsMsg::QuestionLoaded {}).await;
+        send_msg_and_recv_all(&mut reader_ws, &WsMsg::HostReady {}).await;
+        let _ = recv_msgs(&mut scorer_ws).await;
+
+        send_msg_and_recv_all(&mut player_ws, &WsMsg::Buzz { seq: None }).await;
+        for host_ws in [&mut reader_ws, &mut scorer_ws] {
+            let msgs = recv_msgs(host_ws).await;
+            assert!(
+                msgs.iter()
+                    .any(|m| matches!(m, WsMsg::Buzzed { pid, .. } if *pid == player_id)),
+                "Both hosts should see the buzz, got {:?}",
+                msgs
+            );
+        }
+
+        // The scorer rules, and the game only waits on the host once both
+        // sockets are gone
+        send_msg_and_recv_all(&mut scorer_ws, &WsMsg::host_checked(true)).await;
+        drop(reader_ws);
+        let msgs = recv_msgs(&mut player_ws).await;
+        assert!(
+            !msgs.iter().any(|m| matches!(m, WsMsg::HostDisconnected {})),
+            "{:?}",
+            msgs
+        );
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(room.state, GameState::AnswerReveal);
+            assert_eq!(room.hosts.len(), 1);
+        }
+
+        drop(scorer_ws);
+        let msgs = recv_msgs(&mut player_ws).await;
+        assert!(msgs.iter().any(|m| matches!(m, WsMsg::HostDisconnected {})));
+    }
+
+    #[tokio::test]
+    async fn test_host_reconnect() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(state.as_ref(), &room_code).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
+        let (mut player_ws, _player_id) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+
+        send_msg_and_recv_all(
+            &mut host_ws,
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+        )
+        .await;
+        let _ = recv_msgs(&mut player_ws).await;
+
+        let state_before = {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            room.state.clone()
+        };
+        assert!(matches!(state_before, GameState::QuestionReading));
+
+        // Host Disconnect
+        drop(host_ws);
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut host_reconnect =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let reconnect_msgs = recv_msgs(&mut host_reconnect).await;
+
+        let game_state_msg = reconnect_msgs
+            .iter()
+            .find(|m| matches!(m, WsMsg::GameState { .. }));
+        assert!(
+            game_state_msg.is_some(),
+            "Host should receive GameState on reconnect"
+        );
+
+        if let Some(WsMsg::GameState {
+            state,
+            players,
+            current_question,
+            ..
+        }) = game_state_msg
+        {
+            assert!(matches!(state, GameState::QuestionReading));
+            assert_eq!(players.len(), 1, "Should still have 1 player");
+            assert_eq!(
+                current_question,
+                &Some((0, 0)),
+                "Should have current question set"
+            );
+        }
+
+        send_msg_and_recv_all(&mut player_ws, &WsMsg::QuestionLoaded {}).await;
+        send_msg_and_recv_all(&mut host_reconnect, &WsMsg::HostReady {}).await;
+        let player_ready = recv_msgs(&mut player_ws).await;
+
+        let waiting_state = player_ready.iter().any(|m| {
+            matches!(
+                m,
+                WsMsg::GameState {
+                    state: GameState::WaitingForBuzz,
+                    ..
+                }
+            )
+        });
+        assert!(waiting_state, "Game should continue after host reconnects");
+    }
+
+    #[tokio::test]
+    async fn test_full_game() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(state.as_ref(), &room_code).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
+        let (mut aj_ws, aj_id) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        let (mut sam_ws, sam_id) = add_player(port, &room_code, "Sam").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        start_game(&mut host_ws, &mut [&mut aj_ws, &mut sam_ws]).await;
+
+        // Question 1: AJ buzzes and gets it correct (+100)
+        play_question_with(&mut host_ws, &mut aj_ws, &mut [&mut sam_ws], 0, 0, true).await;
+        let _ = recv_msgs(&mut sam_ws).await;
+
+        {
+            let room_map = state.room_map.lock().await;
+            assert_eq!(get_player_score(&room_map, &room_code, aj_id), 100);
+        }
+
+        // Question 2: Sam buzzes and gets it incorrect (-200)
+        play_question_with(&mut host_ws, &mut sam_ws, &mut [&mut aj_ws], 0, 1, false).await;
+        let _ = recv_msgs(&mut aj_ws).await;
+
+        {
+            let room_map = state.room_map.lock().await;
+            assert_eq!(get_player_score(&room_map, &room_code, aj_id), 100);
+            assert_eq!(get_player_score(&room_map, &room_code, sam_id), -200);
+        }
+
+        // Question 2 again: AJ buzzes and gets it correct (+200 = 300 total)
+        play_question_with(&mut host_ws, &mut aj_ws, &mut [&mut sam_ws], 0, 1, true).await;
+        let _ = recv_msgs(&mut sam_ws).await;
+
+        // Question 3: AJ buzzes and gets it correct (+400 = 600 total)
+        play_question_with(&mut host_ws, &mut aj_ws, &mut [&mut sam_ws], 0, 2, true).await;
+        let _ = recv_msgs(&mut sam_ws).await;
+
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(
+                get_player_score(&room_map, &room_code, aj_id),
+                600,
+                "AJ should have 600 points"
+            );
+            assert_eq!(
+                get_player_score(&room_map, &room_code, sam_id),
+                -200,
+                "Sam should have -200 points"
+            );
+            assert!(matches!(room.state, GameState::GameEnd));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_buzzes() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(&state, &room_code).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
+        let (mut aj_ws, aj_id) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        let (mut sam_ws, sam_id) = add_player(port, &room_code, "Sam").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        start_game(&mut host_ws, &mut [&mut aj_ws, &mut sam_ws]).await;
+
+        send_msg_and_recv_all(
+            &mut host_ws,
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+        )
+        .await;
+
+        let _ = recv_msgs(&mut aj_ws).await;
+        let _ = recv_msgs(&mut sam_ws).await;
+
+        send_msg_and_recv_all(&mut aj_ws, &WsMsg::QuestionLoaded {}).await;
+        send_msg_and_recv_all(&mut sam_ws, &WsMsg::QuestionLoaded {}).await;
+        send_msg_and_recv_all(&mut host_ws, &WsMsg::HostReady {}).await;
+        let _ = recv_msgs(&mut aj_ws).await;
+        let _ = recv_msgs(&mut sam_ws).await;
+
+        let aj_buzz = tokio::spawn({
+            let mut ws = aj_ws;
+            async move {
+                send_msg_and_recv_all(&mut ws, &WsMsg::Buzz { seq: None }).await;
+                ws
+            }
+        });
+
+        let sam_buzz = tokio::spawn({
+            let mut ws = sam_ws;
+            async move {
+                send_msg_and_recv_all(&mut ws, &WsMsg::Buzz { seq: None }).await;
+                ws
+            }
+        });
+
+        let _aj_ws = aj_buzz.await.expect("Could not find AJ websocket");
+        let _sam_ws = sam_buzz.await.expect("Could not find Sam websocket");
+
+        let host_msgs = recv_msgs(&mut host_ws).await;
+        let buzz_count = host_msgs
+            .iter()
+            .filter(|m| matches!(m, WsMsg::Buzzed { .. }))
+            .count();
+        assert_eq!(buzz_count, 1, "Host should receive exactly one buzz");
+
+        let buzzed_player = host_msgs
+            .iter()
+            .find_map(|m| {
+                if let WsMsg::Buzzed { pid, .. } = m {
+                    Some(*pid)
+                } else {
+                    None
+                }
+            })
+            .expect("Should have a buzzed player");
+
+        assert!(
+            buzzed_player == aj_id || buzzed_player == sam_id,
+            "Buzzed player should be either Alice or Bob"
+        );
+
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(
+                room.current_buzzer,
+                Some(buzzed_player),
+                "Only one player should be the buzzer"
+            );
+
+            let buzzer = room
+                .players
+                .iter()
+                .find(|p| p.player.pid == buzzed_player)
+                .expect("Could not find buzzed player");
+            assert!(buzzer.player.buzzed, "Buzzer should be marked as buzzed");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_player_joins() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
+        let mut join_handles = vec![];
+        for i in 0..5 {
+            let room_code = room_code.clone();
+            let handle = tokio::spawn(async move {
+                let name = format!("Player{}", i);
+                add_player(port, &room_code, &name).await
+            });
+            join_handles.push(handle);
+        }
+
+        let mut player_ids = vec![];
+        for handle in join_handles {
+            let (_ws, id) = handle.await.expect("Could not find ws handle");
+            player_ids.push(id);
+        }
+
+        sleep(Duration::from_millis(200)).await;
+
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(room.players.len(), 5, "Should have 5 players");
+        }
+
+        let mut unique_ids = player_ids.clone();
+        unique_ids.sort();
+        unique_ids.dedup();
+        assert_eq!(
+            unique_ids.len(),
+            player_ids.len(),
+            "All player IDs should be unique"
+        );
+
+        let final_msgs = recv_msgs(&mut host_ws).await;
+        let final_list = final_msgs.iter().rev().find_map(|m| {
+            if let WsMsg::PlayerList(players) = m {
+                Some(players)
+            } else {
+                None
+            }
+        });
+
+        if let Some(players) = final_list {
+            assert_eq!(players.len(), 5, "Final player list should have 5 players");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buzzing_opens_after_question_load_timeout() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "question_load_timeout_ms": 1000 } }),
+        )
+        .await;
+        add_room_categories(state.as_ref(), &room_code).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+        send_msg_and_recv_all(
+            &mut host_ws,
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+        )
+        .await;
+        let _ = recv_msgs(&mut player_ws).await;
+
+        // The player never acks, so buzzing waits for the fallback
+        send_msg_and_recv_all(&mut host_ws, &WsMsg::HostReady {}).await;
+        let early = recv_msgs(&mut player_ws).await;
+        assert!(
+            !early.iter().any(|m| matches!(
+                m,
+                WsMsg::GameState {
+                    state: GameState::WaitingForBuzz,
+                    ..
+                }
+            )),
+            "Buzzing should not open before the player loads the question"
+        );
+
+        sleep(Duration::from_millis(1000)).await;
+        let late = recv_msgs(&mut player_ws).await;
+        assert!(
+            late.iter().any(|m| matches!(
+                m,
+                WsMsg::GameState {
+                    state: GameState::WaitingForBuzz,
+                    ..
+                }
+            )),
+            "Buzzing should open once the timeout elapses"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_spectator_watches_but_cannot_buzz() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "max_players": 1 } }),
+        )
+        .await;
+        add_room_categories(state.as_ref(), &room_code).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+        let host_msgs = recv_msgs(&mut host_ws).await;
+
+        // Spectators don't take a player slot
+        let mut spectator_ws = connect_ws_client(port, &room_code, "?spectator=true").await;
+        let initial = recv_msgs(&mut spectator_ws).await;
+        assert!(
+            matches!(initial.first(), Some(WsMsg::GameState { .. })),
+            "Spectator should get the current GameState, got {:?}",
+            initial
+        );
+        assert!(
+            recv_msgs(&mut host_ws).await.is_empty(),
+            "Spectator should not show up in the player list"
+        );
+        assert!(
+            host_msgs
+                .iter()
+                .any(|m| matches!(m, WsMsg::PlayerList(players) if players.len() == 1))
+        );
+
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+        send_msg_and_recv_all(
+            &mut host_ws,
+            &WsMsg::HostChoice {
+                category_index: 0,
+                question_index: 0,
+            },
+        )
+        .await;
+        send_msg_and_recv_all(&mut player_ws, &WsMsg::QuestionLoaded {}).await;
+        send_msg_and_recv_all(&mut host_ws, &WsMsg::HostReady {}).await;
+        let _ = recv_msgs(&mut player_ws).await;
+
+        let watched = recv_msgs(&mut spectator_ws).await;
+        assert!(watched.iter().any(|m| matches!(
+            m,
+            WsMsg::GameState {
+                state: GameState::WaitingForBuzz,
+                ..
+            }
+        )));
+        assert!(
+            !watched
+                .iter()
+                .any(|m| matches!(m, WsMsg::PlayerState { .. })),
+            "Spectators never get PlayerState"
+        );
+
+        send_msg_and_recv_all(&mut spectator_ws, &WsMsg::Buzz { seq: None }).await;
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(room.state, GameState::WaitingForBuzz);
+            assert_eq!(room.current_buzzer, None, "Spectator buzz must be ignored");
+        }
+
+        send_msg_and_recv_all(&mut player_ws, &WsMsg::Buzz { seq: None }).await;
+        let watched = recv_msgs(&mut spectator_ws).await;
+        assert!(
+            watched
+                .iter()
+                .any(|m| matches!(m, WsMsg::Buzzed { name, .. } if name == "AJ")),
+            "Spectators see who buzzed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_known_client_id_rejoins_under_prior_name() {
+        let (_server, port, state) = start_test_server().await;
+        let client_id = "3f2504e0-4f89-11d3-9a0c-0305e82c3301";
+
+        let first_room = create_room_http(port).await;
+        let first_ws = connect_ws_client(
+            port,
+            &first_room,
+            &format!("?playerName=Regular&clientId={}", client_id),
+        )
+        .await;
+        drop(first_ws);
+
+        // Next week, in a new room, without typing a name
+        let second_room = create_room_http(port).await;
+        let mut second_ws =
+            connect_ws_client(port, &second_room, &format!("?clientId={}", client_id)).await;
+        let msgs = recv_msgs(&mut second_ws).await;
+        assert!(
+            msgs.iter().any(|m| matches!(m, WsMsg::NewPlayer { .. })),
+            "{:?}",
+            msgs
+        );
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&second_room).expect("Could not find room");
+            assert_eq!(room.players[0].player.name, "Regular");
+        }
+
+        // Unknown ids get nothing prefilled
+        let third_room = create_room_http(port).await;
+        let mut stranger_ws = connect_ws_client(
+            port,
+            &third_room,
+            "?clientId=00000000-0000-0000-0000-000000000000",
+        )
+        .await;
+        let msgs = recv_msgs(&mut stranger_ws).await;
+        assert!(
+            msgs.iter()
+                .any(|m| matches!(m, WsMsg::Error { code, .. } if code == "missing_credentials")),
+            "{:?}",
+            msgs
+        );
+    }
+
+    #[tokio::test]
+    async fn test_display_gets_board_but_not_chat() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(state.as_ref(), &room_code).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _ = recv_msgs(&mut host_ws).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+
+        let mut display_ws = connect_ws_client(port, &room_code, "?display=true").await;
+        let initial = recv_msgs(&mut display_ws).await;
+        assert!(
+            matches!(initial.as_slice(), [WsMsg::GameState { .. }]),
+            "Display should get the current GameState, got {:?}",
+            initial
+        );
+        let mut spectator_ws = connect_ws_client(port, &room_code, "?spectator=true").await;
+        let _ = recv_msgs(&mut spectator_ws).await;
+
+        send_msg_and_recv_all(
+            &mut player_ws,
+            &WsMsg::Chat {
+                text: "hello".to_string(),
+            },
+        )
+        .await;
+        let watched = recv_msgs(&mut spectator_ws).await;
+        assert!(
+            watched
+                .iter()
+                .any(|m| matches!(m, WsMsg::ChatMessage { text, .. } if text == "hello")),
+            "A spectator in the same room follows the chat, got {:?}",
+            watched
+        );
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+        let shown = recv_msgs(&mut display_ws).await;
+        assert!(
+            shown.iter().any(|m| matches!(
+                m,
+                WsMsg::GameState {
+                    state: GameState::Selection,
+                    ..
+                }
+            )),
+            "{:?}",
+            shown
+        );
+        assert!(
+            shown
+                .iter()
+                .all(|m| matches!(m, WsMsg::GameState { .. } | WsMsg::Buzzed { .. })),
+            "Display only gets the board and buzzes, got {:?}",
+            shown
+        );
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_eq!(room.displays.len(), 1);
+            assert_eq!(room.players.len(), 1, "Displays aren't players");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_roundtrip() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut _host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+
+        let url = format!("http://127.0.0.1:{}/api/v1/rooms/{}/cpr", port, room_code);
+        let _response = reqwest::get(&url).await.expect("CPR request failed");
+
+        let player_msgs = recv_msgs(&mut player_ws).await;
+
+        let do_heartbeat = player_msgs.iter().find_map(|m| {
+            if let WsMsg::DoHeartbeat { hbid, t_sent } = m {
+                Some((*hbid, *t_sent))
+            } else {
+                None
+            }
+        });
+
+        assert!(do_heartbeat.is_some(), "Player should receive DoHeartbeat");
+
+        let (hbid, t_sent) = do_heartbeat.expect("Could not do heartbeat");
+
+        let t_dohb_recv = PlayerEntry::time_ms();
+        let got_msgs =
+            send_msg_and_recv_all(&mut player_ws, &WsMsg::Heartbeat { hbid, t_dohb_recv }).await;
+
+        let got_heartbeat = got_msgs
+            .iter()
+            .any(|m| matches!(m, WsMsg::GotHeartbeat { hbid: id } if *id == hbid));
+
+        assert!(got_heartbeat, "Player should receive GotHeartbeat");
+
+        let t_lat = PlayerEntry::time_ms() - t_sent;
+        send_msg_and_recv_all(&mut player_ws, &WsMsg::LatencyOfHeartbeat { hbid, t_lat }).await;
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not get room");
+            let player = room
+                .players
+                .iter()
+                .find(|p| p.player.pid == player_id)
+                .expect("Could not find player");
+
+            let latency = player.latency().expect("Could not get latency");
+            assert!(latency > 0, "Latency should be recorded");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_with_invalid_hbid() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not get room")
+                .host_token
+                .clone()
+        };
+        let mut _host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+
+        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+
+        let invalid_hbid = 99999;
+        let t_dohb_recv = PlayerEntry::time_ms();
+        send_msg_and_recv_all(
+            &mut player_ws,
+            &WsMsg::Heartbeat {
+                hbid: invalid_hbid,
+                t_dohb_recv,
+            },
+        )
+        .await;
+
+        let got_msgs = recv_msgs(&mut player_ws).await;
+        let got_heartbeat = got_msgs
+            .iter()
+            .any(|m| matches!(m, WsMsg::GotHeartbeat { .. }));
+
+        assert!(
+            !got_heartbeat,
+            "Should not receive GotHeartbeat for invalid hbid"
+        );
+
+        // Latency should remain 0
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not get room");
+            let player = room
+                .players
+                .iter()
+                .find(|p| p.player.pid == player_id)
+                .expect("Could not find player");
+            assert_eq!(
+                player.latency().expect("Could not get latency"),
                 0,
                 "Latency should remain 0 with invalid hbid"
             );
@@ -823,110 +2325,1227 @@ mod gameplay_tests {
     }
 }
 
-mod room_cleanup {
+mod room_cleanup {
+    use std::sync::Arc;
+
+    use madhacks2025::{AppState, HostEntry, Room, cleanup_inactive_rooms};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_active_room_not_cleaned_up() {
+        let state = Arc::new(AppState::with_ttl(Duration::from_secs(60)));
+        let mut room_map = state.room_map.lock().await;
+
+        let room = Room::new("TEST01".to_string(), "token".to_string());
+        room_map.insert("TEST01".to_string(), room);
+        drop(room_map);
+
+        cleanup_inactive_rooms(&state).await;
+
+        let room_map = state.room_map.lock().await;
+        assert!(
+            room_map.contains_key("TEST01"),
+            "Active room should not be removed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inactive_room_cleaned_up() {
+        let state = Arc::new(AppState::with_ttl(Duration::from_millis(100)));
+        let mut room_map = state.room_map.lock().await;
+
+        let room = Room::new("TEST01".to_string(), "token".to_string());
+        room_map.insert("TEST01".to_string(), room);
+        drop(room_map);
+
+        // Total time waited: 1s
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        cleanup_inactive_rooms(&state).await;
+
+        let room_map = state.room_map.lock().await;
+        assert!(
+            !room_map.contains_key("TEST01"),
+            "Inactive room should be removed"
+        );
+        assert_eq!(
+            state
+                .metrics
+                .rooms_cleaned_up
+                .load(std::sync::atomic::Ordering::Relaxed),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_touch_extends_room_lifetime() {
+        let state = Arc::new(AppState::with_ttl(Duration::from_millis(100)));
+        let mut room_map = state.room_map.lock().await;
+
+        let room = Room::new("TEST01".to_string(), "token".to_string());
+        room_map.insert("TEST01".to_string(), room);
+        drop(room_map);
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+
+        let mut room_map = state.room_map.lock().await;
+        room_map
+            .get_mut("TEST01")
+            .expect("TEST01 should be in room map")
+            .touch();
+        drop(room_map);
+
+        // Total time waited: 160ms
+        tokio::time::sleep(Duration::from_millis(80)).await;
+
+        cleanup_inactive_rooms(&state).await;
+
+        let room_map = state.room_map.lock().await;
+        assert!(
+            room_map.contains_key("TEST01"),
+            "Touched room should not be removed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_room_ttl_override_outlives_global_ttl() {
+        let state = Arc::new(AppState::with_ttl(Duration::from_millis(100)));
+        let mut room_map = state.room_map.lock().await;
+
+        let mut kept = Room::new("KEPT01".to_string(), "t1".to_string());
+        kept.handle_message(&WsMsg::SetRoomTtl { seconds: 60 }, None);
+        room_map.insert("KEPT01".to_string(), kept);
+        room_map.insert(
+            "STALE1".to_string(),
+            Room::new("STALE1".to_string(), "t2".to_string()),
+        );
+        drop(room_map);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        cleanup_inactive_rooms(&state).await;
+
+        let room_map = state.room_map.lock().await;
+        assert!(
+            room_map.contains_key("KEPT01"),
+            "Room with a longer TTL should survive"
+        );
+        assert!(!room_map.contains_key("STALE1"));
+    }
+
+    #[tokio::test]
+    async fn test_expiry_warning_fires_once_before_cleanup() {
+        let state = Arc::new(AppState {
+            expiry_warning: Duration::from_millis(300),
+            ..AppState::with_ttl(Duration::from_millis(400))
+        });
+        let (host_tx, host_rx) = tokio_mpmc::channel(8);
+        let mut room = Room::new("TEST01".to_string(), "token".to_string());
+        room.hosts.push(HostEntry::new(0, host_tx));
+        state
+            .room_map
+            .lock()
+            .await
+            .insert("TEST01".to_string(), room);
+
+        fn is_warning(msg: Option<WsMsg>) -> bool {
+            matches!(msg, Some(WsMsg::Envelope { event, .. })
+                if matches!(*event, WsMsg::RoomExpiringSoon { .. }))
+        }
+
+        cleanup_inactive_rooms(&state).await;
+        assert!(host_rx.is_empty(), "Too early to warn");
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        cleanup_inactive_rooms(&state).await;
+        assert!(is_warning(host_rx.recv().await.expect("recv")));
+        cleanup_inactive_rooms(&state).await;
+        assert!(host_rx.is_empty(), "Warned only once");
+        assert!(state.room_map.lock().await.contains_key("TEST01"));
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        cleanup_inactive_rooms(&state).await;
+        assert!(!state.room_map.lock().await.contains_key("TEST01"));
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_only_inactive_rooms() {
+        let state = Arc::new(AppState::with_ttl(Duration::from_millis(150)));
+        let mut room_map = state.room_map.lock().await;
+
+        room_map.insert(
+            "ACTIVE".to_string(),
+            Room::new("ACTIVE".to_string(), "t1".to_string()),
+        );
+        room_map.insert(
+            "STALE1".to_string(),
+            Room::new("STALE1".to_string(), "t2".to_string()),
+        );
+
+        // Wait a bit to allow STALE1 to expire before ACTIVE
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        room_map
+            .get_mut("ACTIVE")
+            .expect("ACTIVE should be in room map")
+            .touch();
+        drop(room_map);
+
+        // Wait for STALE1 to expire
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        cleanup_inactive_rooms(&state).await;
+
+        let room_map = state.room_map.lock().await;
+        assert!(room_map.contains_key("ACTIVE"));
+        assert!(!room_map.contains_key("STALE1"));
+    }
+}
+
+mod event_stream {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sse_receives_game_events() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(state.as_ref(), &room_code).await;
+
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _initial = recv_msgs(&mut host_ws).await;
+
+        let mut events = connect_sse_client(port, &room_code).await;
+        assert_eq!(events.status(), 200);
+        let mut buffer = String::new();
+        let initial = recv_sse_msgs(&mut events, &mut buffer).await;
+        assert!(
+            matches!(
+                initial.first(),
+                Some(WsMsg::GameState {
+                    state: GameState::Start,
+                    ..
+                })
+            ),
+            "Stream should open with the current GameState, got {:?}",
+            initial
+        );
+
+        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let _ = recv_msgs(&mut host_ws).await;
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+        play_question(&mut host_ws, &mut player_ws, 0, 0, true).await;
+
+        let received = recv_sse_msgs(&mut events, &mut buffer).await;
+        assert!(
+            received
+                .iter()
+                .any(|m| matches!(m, WsMsg::Buzzed { pid, .. } if *pid == player_id)),
+            "Observers should see the buzz"
+        );
+        let scored = received.iter().any(|m| match m {
+            WsMsg::GameState { players, .. } => {
+                players.iter().any(|p| p.pid == player_id && p.score == 100)
+            }
+            _ => false,
+        });
+        assert!(scored, "Observers should see the updated score");
+    }
+
+    #[tokio::test]
+    async fn test_sse_closes_when_room_removed() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+
+        let mut events = connect_sse_client(port, &room_code).await;
+        let mut buffer = String::new();
+        let _initial = recv_sse_msgs(&mut events, &mut buffer).await;
+
+        state.room_map.lock().await.remove(&room_code);
+
+        let end = tokio::time::timeout(Duration::from_secs(1), events.chunk())
+            .await
+            .expect("Stream should end once the room is gone")
+            .expect("Stream should close cleanly");
+        assert!(end.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sse_never_carries_player_tokens() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let (_player_ws, _) = add_player(port, &room_code, "AJ").await;
+        let token = {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            room.players[0].player.token.clone()
+        };
+
+        let mut events = connect_sse_client(port, &room_code).await;
+        let mut raw = String::new();
+        while let Ok(Ok(Some(chunk))) =
+            tokio::time::timeout(Duration::from_millis(200), events.chunk()).await
+        {
+            raw.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        assert!(raw.contains("AJ"), "Stream should list the player: {}", raw);
+        assert!(!raw.contains(&token), "Token leaked to observers: {}", raw);
+    }
+
+    #[tokio::test]
+    async fn test_sse_unknown_room() {
+        let (_server, port, _state) = start_test_server().await;
+
+        let events = connect_sse_client(port, "NOPE").await;
+        assert_eq!(events.status(), 404);
+    }
+}
+
+mod board {
+    use madhacks2025::AppState;
+
+    use super::*;
+
+    async fn host_token(state: &AppState, room_code: &str) -> String {
+        let room_map = state.room_map.lock().await;
+        room_map
+            .get(room_code)
+            .expect("Could not find room")
+            .host_token
+            .clone()
+    }
+
+    async fn import_board(
+        port: u16,
+        room_code: &str,
+        token: &str,
+        board: serde_json::Value,
+    ) -> reqwest::Response {
+        let url = format!("http://127.0.0.1:{}/api/v1/rooms/{}/board", port, room_code);
+        reqwest::Client::new()
+            .post(&url)
+            .bearer_auth(token)
+            .json(&board)
+            .send()
+            .await
+            .expect("Board import failed")
+    }
+
+    fn sample_board(value: u32) -> serde_json::Value {
+        serde_json::json!([{
+            "title": "Imported",
+            "questions": [{ "question": "Q", "answer": "A", "value": value, "answered": true }],
+        }])
+    }
+
+    #[tokio::test]
+    async fn test_question_media_survives_import_and_game_state() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let token = host_token(&state, &room_code).await;
+        let board = |url: &str| {
+            serde_json::json!([{
+                "title": "Landmarks",
+                "questions": [{
+                    "question": "Name this tower",
+                    "answer": "Eiffel",
+                    "value": 200,
+                    "mediaUrl": url,
+                    "mediaType": "image/jpeg",
+                }],
+            }])
+        };
+
+        let response = import_board(port, &room_code, &token, board("javascript:alert(1)")).await;
+        assert_eq!(response.status(), 400, "Only http(s) media is allowed");
+
+        let url = "https://example.com/tower.jpg";
+        let response = import_board(port, &room_code, &token, board(url)).await;
+        assert_eq!(response.status(), 204);
+
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+        let mut host_ws = connect_ws_client(port, &room_code, &format!("?token={}", token)).await;
+        let _ = recv_msgs(&mut host_ws).await;
+        send_msg_and_recv_all(&mut host_ws, &WsMsg::StartGame {}).await;
+        let msgs = recv_msgs(&mut player_ws).await;
+        let question = msgs
+            .iter()
+            .find_map(|m| match m {
+                WsMsg::GameState { categories, .. } => Some(categories[0].questions[0].clone()),
+                _ => None,
+            })
+            .expect("Players should get the board");
+        assert_eq!(question.media_url.as_deref(), Some(url));
+        assert_eq!(question.media_type.as_deref(), Some("image/jpeg"));
+        assert!(question.answer.is_empty(), "Answers stay hidden");
+    }
+
+    #[tokio::test]
+    async fn test_create_room_validates_boards() {
+        let (_server, port, state) = start_test_server().await;
+        let board = |url: &str| {
+            serde_json::json!([{
+                "title": "Landmarks",
+                "questions": [{ "question": "Q", "answer": "A", "value": 200, "mediaUrl": url }],
+            }])
+        };
+        let create = |body: serde_json::Value| async move {
+            reqwest::Client::new()
+                .post(format!("http://127.0.0.1:{}/api/v1/rooms/create", port))
+                .json(&body)
+                .send()
+                .await
+                .expect("Failed to create room")
+        };
+
+        let response =
+            create(serde_json::json!({ "categories": board("javascript:alert(1)") })).await;
+        assert_eq!(response.status(), 400);
+        let response = create(serde_json::json!({
+            "boards": [board("https://example.com/a.jpg"), board("data:text/html,hi")],
+        }))
+        .await;
+        assert_eq!(response.status(), 400, "Every round is checked");
+        assert!(state.room_map.lock().await.is_empty());
+
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "categories": board("  https://example.com/a.jpg ") }),
+        )
+        .await;
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert_eq!(
+            room.categories[0].questions[0].media_url.as_deref(),
+            Some("https://example.com/a.jpg")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_board_replaces_categories() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let token = host_token(&state, &room_code).await;
+
+        let response = import_board(port, &room_code, &token, sample_board(300)).await;
+        assert_eq!(response.status(), 204);
+
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert_eq!(room.categories[0].title, "Imported");
+        assert_eq!(room.categories[0].questions[0].value, 300);
+        assert!(!room.categories[0].questions[0].answered);
+    }
+
+    #[tokio::test]
+    async fn test_import_board_rejects_overlong_text() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let token = host_token(&state, &room_code).await;
+        let long = "x".repeat(state.max_board_text_chars + 1);
+        let board = serde_json::json!([
+            { "title": "Fine", "questions": [{ "question": "Q", "answer": "A", "value": 200 }] },
+            { "title": "Long", "questions": [
+                { "question": "Q", "answer": "A", "value": 200 },
+                { "question": long, "answer": "A", "value": 400 },
+            ] },
+        ]);
+
+        let response = import_board(port, &room_code, &token, board.clone()).await;
+        assert_eq!(response.status(), 400);
+        let issues: serde_json::Value = response.json().await.expect("Issues should be JSON");
+        assert_eq!(
+            issues,
+            serde_json::json!([{
+                "category": 1,
+                "question": 1,
+                "field": "question",
+                "length": state.max_board_text_chars + 1,
+            }])
+        );
+
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert!(
+                room.categories.iter().all(|c| c.title != "Fine"),
+                "Board should be unchanged"
+            );
+        }
+
+        let response = reqwest::Client::new()
+            .post(format!("http://127.0.0.1:{}/api/v1/rooms/create", port))
+            .json(&serde_json::json!({ "categories": board }))
+            .send()
+            .await
+            .expect("Failed to create room");
+        assert_eq!(response.status(), 400, "Room creation is capped too");
+    }
+
+    #[tokio::test]
+    async fn test_import_board_rejects_bad_requests() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let token = host_token(&state, &room_code).await;
+
+        let wrong_token = import_board(port, &room_code, "nope", sample_board(300)).await;
+        assert_eq!(wrong_token.status(), 401);
+
+        let zero_value = import_board(port, &room_code, &token, sample_board(0)).await;
+        assert_eq!(zero_value.status(), 400);
+
+        let empty = serde_json::json!([{ "title": "Empty", "questions": [] }]);
+        let empty_category = import_board(port, &room_code, &token, empty).await;
+        assert_eq!(empty_category.status(), 400);
+
+        let missing = import_board(port, "NOPE", &token, sample_board(300)).await;
+        assert_eq!(missing.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_import_board_flags_broken_value_ladder() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let token = host_token(&state, &room_code).await;
+        let broken = serde_json::json!([{
+            "title": "Typos",
+            "questions": [
+                { "question": "Q", "answer": "A", "value": 200 },
+                { "question": "Q", "answer": "A", "value": 400 },
+                { "question": "Q", "answer": "A", "value": 40 },
+            ],
+        }]);
+
+        let url = format!(
+            "http://127.0.0.1:{}/api/v1/rooms/{}/board?strict=true",
+            port, room_code
+        );
+        let rejected = reqwest::Client::new()
+            .post(&url)
+            .bearer_auth(&token)
+            .json(&broken)
+            .send()
+            .await
+            .expect("Board import failed");
+        assert_eq!(rejected.status(), 400);
+        let issues: serde_json::Value = rejected.json().await.expect("Issues should be JSON");
+        assert_eq!(issues[0]["category"], 0);
+        assert_eq!(issues[0]["question"], 2);
+        {
+            let room_map = state.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Could not find room");
+            assert_ne!(
+                room.categories.first().map(|c| c.title.as_str()),
+                Some("Typos")
+            );
+        }
+
+        // Without the flag the board goes in, with the same issues as warnings
+        let warned = import_board(port, &room_code, &token, broken).await;
+        assert_eq!(warned.status(), 200);
+        let warnings: serde_json::Value = warned.json().await.expect("Warnings should be JSON");
+        assert_eq!(warnings, issues);
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert_eq!(room.categories[0].title, "Typos");
+    }
+
+    #[tokio::test]
+    async fn test_import_board_csv() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let token = host_token(&state, &room_code).await;
+        let url = format!(
+            "http://127.0.0.1:{}/api/v1/rooms/{}/board.csv",
+            port, room_code
+        );
+        let post_csv = |body: &'static str| {
+            reqwest::Client::new()
+                .post(&url)
+                .bearer_auth(&token)
+                .header("Content-Type", "text/csv")
+                .body(body)
+                .send()
+        };
+
+        let bad = post_csv("category,question,answer,value\nScience,Q,A,100\nScience,Q,A,-5\n")
+            .await
+            .expect("CSV import failed");
+        assert_eq!(bad.status(), 400);
+        let error: serde_json::Value = bad.json().await.expect("Error should be JSON");
+        assert_eq!(error["row"], 3);
+
+        let good = post_csv("Science,\"Is water wet, really?\",Yes,100\nHistory,Q,A,200\n")
+            .await
+            .expect("CSV import failed");
+        assert_eq!(good.status(), 204);
+
+        let room_map = state.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert_eq!(room.categories.len(), 2);
+        assert_eq!(
+            room.categories[0].questions[0].question,
+            "Is water wet, really?"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_board_round_trips_without_play_state() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(state.as_ref(), &room_code).await;
+        let token = host_token(&state, &room_code).await;
+        {
+            // Play the last question first; export order must not follow play order
+            let mut room_map = state.room_map.lock().await;
+            let room = room_map.get_mut(&room_code).expect("Could not find room");
+            room.categories[0].questions[2].answered = true;
+        }
+
+        let url = format!("http://127.0.0.1:{}/api/v1/rooms/{}/board", port, room_code);
+        let unauthorized = reqwest::Client::new()
+            .get(&url)
+            .send()
+            .await
+            .expect("Board export failed");
+        assert_eq!(unauthorized.status(), 401);
+
+        let exported: serde_json::Value = reqwest::Client::new()
+            .get(&url)
+            .bearer_auth(&token)
+            .send()
+            .await
+            .expect("Board export failed")
+            .json()
+            .await
+            .expect("Export should be JSON");
+        let questions = exported[0]["questions"]
+            .as_array()
+            .expect("Questions should be an array");
+        let values: Vec<u64> = questions
+            .iter()
+            .map(|q| q["value"].as_u64().expect("Value should be a number"))
+            .collect();
+        assert_eq!(values, [100, 200, 300]);
+        assert!(questions.iter().all(|q| q.get("answered").is_none()));
+
+        let reimport = import_board(port, &room_code, &token, exported).await;
+        assert_eq!(reimport.status(), 204);
+    }
+
+    #[tokio::test]
+    async fn test_import_board_after_start_conflicts() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let token = host_token(&state, &room_code).await;
+
+        let mut host_ws = connect_ws_client(port, &room_code, &format!("?token={}", token)).await;
+        let _ = recv_msgs(&mut host_ws).await;
+        send_msg_and_recv_all(&mut host_ws, &WsMsg::StartGame {}).await;
+
+        let response = import_board(port, &room_code, &token, sample_board(300)).await;
+        assert_eq!(response.status(), 409);
+    }
+}
+
+mod keepalive {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+    use madhacks2025::AppState;
+    use tokio_tungstenite::tungstenite::Message;
+
+    use super::*;
+
+    // Slower than recv_msgs' 100ms quiet period so it still returns
+    fn fast_ping_state() -> Arc<AppState> {
+        Arc::new(AppState {
+            ping_interval: Duration::from_millis(150),
+            ..AppState::new()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_server_pings_idle_sockets() {
+        let (_server, port, _state) = start_test_server_with(fast_ping_state()).await;
+        let room_code = create_room_http(port).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+
+        let frame = tokio::time::timeout(Duration::from_secs(1), player_ws.next())
+            .await
+            .expect("Server should ping within the interval");
+        assert!(matches!(frame, Some(Ok(Message::Ping(_)))));
+
+        // Reading answers the pings, so the connection stays up
+        for _ in 0..6 {
+            let _ = recv_msgs(&mut player_ws).await;
+        }
+        let msgs = send_msg_and_recv_all(
+            &mut player_ws,
+            &WsMsg::Chat {
+                text: "still here".to_string(),
+            },
+        )
+        .await;
+        assert!(
+            msgs.iter().any(|m| matches!(m, WsMsg::ChatMessage { .. })),
+            "Connection should still be open"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_silent_socket_is_dropped() {
+        let (_server, port, _state) = start_test_server_with(fast_ping_state()).await;
+        let room_code = create_room_http(port).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+
+        // Not reading means the client never answers the pings
+        tokio::time::sleep(Duration::from_millis(800)).await;
+
+        let closed = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                match player_ws.next().await {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        })
+        .await;
+        assert!(closed.is_ok(), "Server should drop the silent connection");
+    }
+}
+
+mod debug_commands {
+    use std::sync::Arc;
+
+    use madhacks2025::AppState;
+
+    use super::*;
+
+    async fn force_selection(state: Arc<AppState>) -> GameState {
+        let (_server, port, state) = start_test_server_with(state).await;
+        let room_code = create_room_http(port).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        send_msg_and_recv_all(
+            &mut host_ws,
+            &WsMsg::ForceState {
+                state: GameState::Selection,
+            },
+        )
+        .await;
+
+        let room_map = state.room_map.lock().await;
+        room_map
+            .get(&room_code)
+            .expect("Could not find room")
+            .state
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_force_state_ignored_by_default() {
+        let state = force_selection(Arc::new(AppState::new())).await;
+        assert_eq!(state, GameState::Start);
+    }
+
+    #[tokio::test]
+    async fn test_force_state_with_debug_commands() {
+        let state = force_selection(Arc::new(AppState {
+            debug_commands: true,
+            ..AppState::new()
+        }))
+        .await;
+        assert_eq!(state, GameState::Selection);
+    }
+}
+
+mod message_limits {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[tokio::test]
+    async fn test_oversized_text_is_rejected_without_parsing() {
+        let (_server, port, _state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+
+        let huge_chat = serde_json::to_string(&WsMsg::Chat {
+            text: "a".repeat(20 * 1024),
+        })
+        .expect("Failed to serialize");
+        player_ws
+            .send(Message::Text(huge_chat.into()))
+            .await
+            .expect("Failed to send");
+        let msgs = recv_msgs(&mut player_ws).await;
+        assert!(
+            matches!(&msgs[..], [WsMsg::Error { code, .. }] if code == "message_too_large"),
+            "Got {:?}",
+            msgs
+        );
+
+        player_ws
+            .send(Message::Binary(vec![1, 2, 3].into()))
+            .await
+            .expect("Failed to send");
+        let msgs = recv_msgs(&mut player_ws).await;
+        assert!(matches!(&msgs[..], [WsMsg::Error { code, .. }] if code == "unsupported_message"));
+
+        let msgs = send_msg_and_recv_all(
+            &mut player_ws,
+            &WsMsg::Chat {
+                text: "still here".to_string(),
+            },
+        )
+        .await;
+        assert!(
+            msgs.iter().any(|m| matches!(m, WsMsg::ChatMessage { .. })),
+            "Connection should stay open"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_malformed_message_gets_an_error() {
+        let (_server, port, _state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+
+        for garbage in ["not json at all", r#"{"NoSuchCommand":{}}"#] {
+            player_ws
+                .send(Message::Text(garbage.into()))
+                .await
+                .expect("Failed to send");
+            let msgs = recv_msgs(&mut player_ws).await;
+            assert!(
+                matches!(
+                    &msgs[..],
+                    [WsMsg::Error { code, message }]
+                        if code == "invalid_message" && !message.is_empty() && message.len() <= 200
+                ),
+                "{:?} got {:?}",
+                garbage,
+                msgs
+            );
+        }
+
+        let msgs = send_msg_and_recv_all(&mut player_ws, &WsMsg::RequestState {}).await;
+        assert!(
+            msgs.iter().any(|m| matches!(m, WsMsg::PlayerState { .. })),
+            "Connection should stay open"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_frames_over_the_hard_cap_close_the_connection() {
+        let (_server, port, _state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "AJ").await;
+
+        let _ = player_ws
+            .send(Message::Text("a".repeat(256 * 1024).into()))
+            .await;
+        let closed = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                match player_ws.next().await {
+                    None | Some(Err(_)) | Some(Ok(Message::Close(_))) => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        })
+        .await;
+        assert!(closed.is_ok(), "Server should drop the connection");
+    }
+}
+
+mod admin {
     use std::sync::Arc;
 
-    use madhacks2025::{AppState, Room, cleanup_inactive_rooms};
+    use madhacks2025::AppState;
 
     use super::*;
 
-    #[tokio::test]
-    async fn test_active_room_not_cleaned_up() {
-        let state = Arc::new(AppState::with_ttl(Duration::from_secs(60)));
-        let mut room_map = state.room_map.lock().await;
+    async fn list_rooms(port: u16, auth: Option<&str>) -> reqwest::Response {
+        let url = format!("http://127.0.0.1:{}/api/v1/admin/rooms", port);
+        let mut request = reqwest::Client::new().get(&url);
+        if let Some(auth) = auth {
+            request = request.header("Authorization", auth);
+        }
+        request.send().await.expect("Admin request failed")
+    }
 
-        let room = Room::new("TEST01".to_string(), "token".to_string());
-        room_map.insert("TEST01".to_string(), room);
-        drop(room_map);
+    #[tokio::test]
+    async fn test_admin_rooms_lists_rooms_without_tokens() {
+        let (_server, port, _state) = start_test_server_with(Arc::new(AppState {
+            admin_token: Some("letmein".to_string()),
+            ..AppState::new()
+        }))
+        .await;
+        let room_code = create_room_http(port).await;
+        let (_player_ws, _) = add_player(port, &room_code, "AJ").await;
 
-        cleanup_inactive_rooms(&state).await;
+        let response = list_rooms(port, Some("Bearer letmein")).await;
+        assert_eq!(response.status(), 200);
+        let body = response.text().await.expect("Failed to read body");
+        assert!(!body.contains("token"), "Tokens must never leak: {}", body);
 
-        let room_map = state.room_map.lock().await;
-        assert!(
-            room_map.contains_key("TEST01"),
-            "Active room should not be removed"
+        let rooms: serde_json::Value = serde_json::from_str(&body).expect("Invalid JSON");
+        assert_eq!(
+            rooms,
+            serde_json::json!([{
+                "code": room_code,
+                "player_count": 1,
+                "state": "start",
+                "idle_secs": 0,
+            }])
         );
     }
 
     #[tokio::test]
-    async fn test_inactive_room_cleaned_up() {
-        let state = Arc::new(AppState::with_ttl(Duration::from_millis(100)));
-        let mut room_map = state.room_map.lock().await;
+    async fn test_admin_rooms_requires_token() {
+        let (_server, port, _state) = start_test_server_with(Arc::new(AppState {
+            admin_token: Some("letmein".to_string()),
+            ..AppState::new()
+        }))
+        .await;
 
-        let room = Room::new("TEST01".to_string(), "token".to_string());
-        room_map.insert("TEST01".to_string(), room);
-        drop(room_map);
+        assert_eq!(list_rooms(port, None).await.status(), 401);
+        assert_eq!(list_rooms(port, Some("Bearer wrong")).await.status(), 401);
+        assert_eq!(list_rooms(port, Some("letmein")).await.status(), 401);
+    }
 
-        // Total time waited: 1s
-        tokio::time::sleep(Duration::from_secs(1)).await;
-        cleanup_inactive_rooms(&state).await;
+    #[tokio::test]
+    async fn test_admin_rooms_disabled_without_configured_token() {
+        let (_server, port, _state) = start_test_server().await;
 
-        let room_map = state.room_map.lock().await;
+        assert_eq!(list_rooms(port, Some("Bearer ")).await.status(), 401);
+    }
+}
+
+mod metrics {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_counts_activity() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(&state, &room_code).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let _ = recv_msgs(&mut host_ws).await;
+        let (mut player_ws, _) = add_player(port, &room_code, "Alice").await;
+        let _ = recv_msgs(&mut host_ws).await;
+
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+        play_question(&mut host_ws, &mut player_ws, 0, 0, true).await;
+
+        let url = format!("http://127.0.0.1:{}/metrics", port);
+        let body = reqwest::get(&url)
+            .await
+            .expect("Metrics request failed")
+            .text()
+            .await
+            .expect("Failed to read body");
+
+        assert!(body.contains("buzzer_rooms_created_total 1\n"), "{}", body);
+        assert!(body.contains("buzzer_players_joined_total 1\n"), "{}", body);
         assert!(
-            !room_map.contains_key("TEST01"),
-            "Inactive room should be removed"
+            body.contains("buzzer_buzzes_processed_total 1\n"),
+            "{}",
+            body
+        );
+        assert!(
+            body.contains("buzzer_rooms_cleaned_up_total 0\n"),
+            "{}",
+            body
+        );
+        assert!(
+            body.contains("# TYPE buzzer_active_rooms gauge\n"),
+            "{}",
+            body
+        );
+        assert!(body.contains("buzzer_active_rooms 1\n"), "{}", body);
+    }
+}
+
+mod persistence {
+    use std::sync::Arc;
+
+    use madhacks2025::{AppState, ConnectionStatus};
+
+    use super::*;
+
+    async fn rejoin_host(port: u16, room_code: &str, host_token: &str) -> reqwest::Response {
+        let url = format!(
+            "http://127.0.0.1:{}/api/v1/rooms/{}/rejoin-host",
+            port, room_code
         );
+        reqwest::Client::new()
+            .post(&url)
+            .json(&serde_json::json!({ "host_token": host_token }))
+            .send()
+            .await
+            .expect("Rejoin request failed")
     }
 
     #[tokio::test]
-    async fn test_touch_extends_room_lifetime() {
-        let state = Arc::new(AppState::with_ttl(Duration::from_millis(100)));
-        let mut room_map = state.room_map.lock().await;
+    async fn test_host_rejoin_existing_room() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
 
-        let room = Room::new("TEST01".to_string(), "token".to_string());
-        room_map.insert("TEST01".to_string(), room);
-        drop(room_map);
+        let response = rejoin_host(port, &room_code, &host_token).await;
+        assert_eq!(response.status(), 200);
+        let json: serde_json::Value = response.json().await.expect("Failed to parse response");
+        assert_eq!(json["room_code"], room_code);
+        assert_eq!(json["host_token"], host_token);
+        assert_eq!(json["state"], "start");
+
+        let ws_path = json["ws_path"].as_str().expect("No ws_path in response");
+        let query = ws_path
+            .split_once('?')
+            .map(|(_, q)| format!("?{}", q))
+            .expect("ws_path should include the token");
+        let mut host_ws = connect_ws_client(port, &room_code, &query).await;
+        let msgs = recv_msgs(&mut host_ws).await;
+        assert!(
+            msgs.iter().any(|m| matches!(m, WsMsg::PlayerList(_))),
+            "Connection info should authenticate as host"
+        );
 
-        tokio::time::sleep(Duration::from_millis(80)).await;
+        let bad = rejoin_host(port, &room_code, "wrong-token").await;
+        assert_eq!(bad.status(), 401);
 
-        let mut room_map = state.room_map.lock().await;
-        room_map
-            .get_mut("TEST01")
-            .expect("TEST01 should be in room map")
-            .touch();
-        drop(room_map);
+        let missing = rejoin_host(port, "ZZZZZZ", &host_token).await;
+        assert_eq!(missing.status(), 404);
+    }
 
-        // Total time waited: 160ms
-        tokio::time::sleep(Duration::from_millis(80)).await;
+    #[tokio::test]
+    async fn test_host_rejoin_restores_from_snapshot() {
+        let path =
+            std::env::temp_dir().join(format!("rejoin-snapshot-{}.json", std::process::id()));
+        let state = Arc::new(AppState {
+            snapshot_path: Some(path.clone()),
+            ..AppState::new()
+        });
+        let (_server, port, state) = start_test_server_with(state).await;
+        let room_code = create_room_http(port).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
 
-        cleanup_inactive_rooms(&state).await;
+        state.save_snapshot(&path).await.expect("Failed to save");
+        // Simulate the room being lost from memory by a restart
+        state.room_map.lock().await.clear();
 
-        let room_map = state.room_map.lock().await;
+        let response = rejoin_host(port, &room_code, "not-the-token").await;
+        assert_eq!(response.status(), 404);
         assert!(
-            room_map.contains_key("TEST01"),
-            "Touched room should not be removed"
+            state.room_map.lock().await.is_empty(),
+            "A wrong token must not restore the room"
+        );
+
+        let response = rejoin_host(port, &room_code, &host_token).await;
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(response.status(), 200);
+        assert!(
+            state.room_map.lock().await.contains_key(&room_code),
+            "Room should be restored from the snapshot"
         );
     }
 
     #[tokio::test]
-    async fn test_cleanup_only_inactive_rooms() {
-        let state = Arc::new(AppState::with_ttl(Duration::from_millis(150)));
-        let mut room_map = state.room_map.lock().await;
+    async fn test_snapshot_restores_room_for_reconnect() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        add_room_categories(&state, &room_code).await;
 
-        room_map.insert(
-            "ACTIVE".to_string(),
-            Room::new("ACTIVE".to_string(), "t1".to_string()),
+        let (_player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        let (host_token, player_token) = {
+            let mut room_map = state.room_map.lock().await;
+            let room = room_map.get_mut(&room_code).expect("Could not find room");
+            room.state = GameState::Selection;
+            room.players[0].player.score = 300;
+            room.question_stats
+                .push(madhacks2025::stats::QuestionStats::new(0, 0, 1));
+            (
+                room.host_token.clone(),
+                room.players[0].player.token.clone(),
+            )
+        };
+
+        let path = std::env::temp_dir().join(format!("snapshot-{}.json", room_code));
+        let saved = state.save_snapshot(&path).await.expect("Failed to save");
+        assert_eq!(saved, 1);
+
+        // Fresh server, as if restarted
+        let restored = Arc::new(AppState::new());
+        let loaded = restored.load_snapshot(&path).await.expect("Failed to load");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded, 1);
+
+        {
+            let room_map = restored.room_map.lock().await;
+            let room = room_map.get(&room_code).expect("Room should be restored");
+            assert_eq!(room.host_token, host_token);
+            assert_eq!(room.state, GameState::Selection);
+            assert_eq!(room.categories[0].questions.len(), 3);
+            assert_eq!(room.question_stats.len(), 1, "Stats survive a restart");
+            assert!(room.hosts.is_empty());
+            assert!(matches!(
+                room.players[0].status,
+                ConnectionStatus::Disconnected
+            ));
+        }
+
+        let (_server, port, _) = start_test_server_with(restored.clone()).await;
+        let mut player_ws = connect_ws_client(
+            port,
+            &room_code,
+            &format!("?token={}&playerID={}", player_token, player_id),
+        )
+        .await;
+        let msgs = recv_msgs(&mut player_ws).await;
+
+        assert!(
+            msgs.iter().any(|m| matches!(
+                m,
+                WsMsg::PlayerState { pid, score: 300, .. } if *pid == player_id
+            )),
+            "Restored player should reconnect with their score, got {:?}",
+            msgs
         );
-        room_map.insert(
-            "STALE1".to_string(),
-            Room::new("STALE1".to_string(), "t2".to_string()),
+
+        let room_map = restored.room_map.lock().await;
+        let room = room_map.get(&room_code).expect("Could not find room");
+        assert!(matches!(
+            room.players[0].status,
+            ConnectionStatus::Connected
+        ));
+    }
+}
+
+mod transcript {
+    use super::*;
+
+    async fn get_transcript(port: u16, room_code: &str, token: &str) -> reqwest::Response {
+        let url = format!(
+            "http://127.0.0.1:{}/api/v1/rooms/{}/transcript",
+            port, room_code
         );
+        reqwest::Client::new()
+            .get(&url)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Transcript request failed")
+    }
 
-        // Wait a bit to allow STALE1 to expire before ACTIVE
-        tokio::time::sleep(Duration::from_millis(100)).await;
-        room_map
-            .get_mut("ACTIVE")
-            .expect("ACTIVE should be in room map")
-            .touch();
-        drop(room_map);
+    #[tokio::test]
+    async fn test_transcript_records_buzz_and_ruling() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http_with(
+            port,
+            serde_json::json!({ "settings": { "transcript": true } }),
+        )
+        .await;
+        add_room_categories(&state, &room_code).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+        let mut host_ws =
+            connect_ws_client(port, &room_code, &format!("?token={}", host_token)).await;
+        let (mut player_ws, player_id) = add_player(port, &room_code, "AJ").await;
+        start_game(&mut host_ws, &mut [&mut player_ws]).await;
+        play_question(&mut host_ws, &mut player_ws, 0, 0, true).await;
 
-        // Wait for STALE1 to expire
-        tokio::time::sleep(Duration::from_millis(100)).await;
+        let response = get_transcript(port, &room_code, "wrong").await;
+        assert_eq!(response.status(), 401);
 
-        cleanup_inactive_rooms(&state).await;
+        let response = get_transcript(port, &room_code, &host_token).await;
+        assert_eq!(response.status(), 200);
+        let transcript: serde_json::Value = response.json().await.expect("Transcript is JSON");
+        let entries = transcript["entries"].as_array().expect("Entries list");
 
-        let room_map = state.room_map.lock().await;
-        assert!(room_map.contains_key("ACTIVE"));
-        assert!(!room_map.contains_key("STALE1"));
+        let buzz = entries
+            .iter()
+            .position(|e| e["kind"] == "command" && e["msg"].get("Buzz").is_some())
+            .expect("Buzz should be in the transcript");
+        assert_eq!(entries[buzz]["pid"], player_id);
+        assert!(
+            entries[buzz..]
+                .iter()
+                .any(|e| e["kind"] == "event" && e["msg"]["Buzzed"]["pid"] == player_id),
+            "Buzzed event should follow the buzz"
+        );
+        let ruling = entries
+            .iter()
+            .position(|e| e["kind"] == "command" && e["msg"]["HostChecked"]["correct"] == true)
+            .expect("Ruling should be in the transcript");
+        assert!(buzz < ruling);
+        assert!(
+            entries[ruling]["pid"].is_null(),
+            "Rulings come from the host"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transcript_is_opt_in() {
+        let (_server, port, state) = start_test_server().await;
+        let room_code = create_room_http(port).await;
+        let host_token = {
+            let room_map = state.room_map.lock().await;
+            room_map
+                .get(&room_code)
+                .expect("Could not find room")
+                .host_token
+                .clone()
+        };
+
+        let response = get_transcript(port, &room_code, &host_token).await;
+        assert_eq!(response.status(), 404);
     }
 }